@@ -1,6 +1,87 @@
+#include <stdbool.h>
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <time.h>
+
+// Zeroed heap memory for objects
+void* coral_alloc(long long size) {
+    return calloc(1, size);
+}
+
+// Seconds since the epoch, for `now`
+long long coral_now() {
+    return (long long)time(NULL);
+}
+
+// Strings are immutable and length-prefixed: `len` bytes of UTF-8 followed
+// by a NUL that `len` does not count, the same layout as the constants the
+// compiler emits for literals. Since nothing writes to a string after it is
+// built, results share their operands whenever the contents allow.
+typedef struct {
+    long long len;
+    char chars[];
+} String;
+
+static struct {
+    long long len;
+    char chars[1];
+} empty_string = {0, ""};
+
+static String* string_alloc(long long len) {
+    String* str = (String*)malloc(sizeof(String) + len + 1);
+    str->len = len;
+    str->chars[len] = '\0';
+    return str;
+}
+
+static String* string_from_chars(const char* chars, long long len) {
+    String* str = string_alloc(len);
+    memcpy(str->chars, chars, len);
+    return str;
+}
+
+String* string_new() {
+    return (String*)&empty_string;
+}
+
+String* string_concat(String* s1, String* s2) {
+    if (s1->len == 0) {
+        return s2;
+    }
+    if (s2->len == 0) {
+        return s1;
+    }
+    String* str = string_alloc(s1->len + s2->len);
+    memcpy(str->chars, s1->chars, s1->len);
+    memcpy(str->chars + s1->len, s2->chars, s2->len);
+    return str;
+}
+
+String* string_from_int(long long val) {
+    char buf[21];
+    int len = snprintf(buf, sizeof(buf), "%lld", val);
+    return string_from_chars(buf, len);
+}
+
+String* string_from_float(double val) {
+    char buf[32];
+    int len = snprintf(buf, sizeof(buf), "%f", val);
+    return string_from_chars(buf, len);
+}
+
+String* string_from_bool(bool val) {
+    return val ? string_from_chars("true", 4) : string_from_chars("false", 5);
+}
+
+int string_equals(String* s1, String* s2) {
+    return s1 == s2 || (s1->len == s2->len && memcmp(s1->chars, s2->chars, s1->len) == 0);
+}
+
+void print_string(String* s) {
+    fwrite(s->chars, 1, s->len, stdout);
+    fputc('\n', stdout);
+}
 
 // A simple list implementation
 typedef struct {
@@ -26,53 +107,141 @@ void list_append(void* list_ptr, void* item) {
     list->data[list->size++] = item;
 }
 
-// A simple string implementation
+// Element accesses carry the Coral source location to report failures at
+static void check_index(long long index, long long length, const char* location) {
+    if (index < 0 || index >= length) {
+        fprintf(stderr, "%s: index %lld out of bounds for length %lld\n", location, index, length);
+        exit(1);
+    }
+}
+
+void* list_get(void* list_ptr, long long index, const char* location) {
+    List* list = (List*)list_ptr;
+    check_index(index, list->size, location);
+    return list->data[index];
+}
+
+void list_set(void* list_ptr, long long index, void* item, const char* location) {
+    List* list = (List*)list_ptr;
+    check_index(index, list->size, location);
+    list->data[index] = item;
+}
+
+// A UTF-8 continuation byte, which never starts a code point
+static int is_continuation(char c) {
+    return ((unsigned char)c & 0xC0) == 0x80;
+}
+
+// Strings are indexed by code point, counted from their UTF-8 lead bytes
+static long long code_point_count(String* s) {
+    long long count = 0;
+    for (long long i = 0; i < s->len; i++) {
+        if (!is_continuation(s->chars[i])) {
+            count++;
+        }
+    }
+    return count;
+}
+
+// The code point at code-point offset `index`, as a string of its own
+String* string_at(String* s, long long index, const char* location) {
+    check_index(index, code_point_count(s), location);
+    long long start = 0;
+    for (long long seen = 0; seen < index; seen++) {
+        do {
+            start++;
+        } while (is_continuation(s->chars[start]));
+    }
+    long long end = start + 1;
+    while (end < s->len && is_continuation(s->chars[end])) {
+        end++;
+    }
+    return string_from_chars(s->chars + start, end - start);
+}
+
+// A simple map implementation: keys are compared by value, or by contents
+// when they are strings
 typedef struct {
-    int len;
-    char* chars;
-} String;
+    int size;
+    int capacity;
+    int string_keys;
+    void** keys;
+    void** values;
+} Map;
 
-void* string_new() {
-    String* str = (String*)malloc(sizeof(String));
-    str->len = 0;
-    str->chars = (char*)malloc(1);
-    str->chars[0] = '\0';
-    return str;
+void* map_new(int string_keys) {
+    Map* map = (Map*)malloc(sizeof(Map));
+    map->size = 0;
+    map->capacity = 4;
+    map->string_keys = string_keys;
+    map->keys = (void**)malloc(sizeof(void*) * map->capacity);
+    map->values = (void**)malloc(sizeof(void*) * map->capacity);
+    return map;
 }
 
-void* string_concat(void* s1_ptr, void* s2_ptr) {
-    String* s1 = (String*)s1_ptr;
-    String* s2 = (String*)s2_ptr;
-    String* new_str = (String*)malloc(sizeof(String));
-    new_str->len = s1->len + s2->len;
-    new_str->chars = (char*)malloc(new_str->len + 1);
-    strcpy(new_str->chars, s1->chars);
-    strcat(new_str->chars, s2->chars);
-    return new_str;
+static int map_find(Map* map, void* key) {
+    for (int i = 0; i < map->size; i++) {
+        if (map->string_keys ? string_equals((String*)map->keys[i], (String*)key) : map->keys[i] == key) {
+            return i;
+        }
+    }
+    return -1;
 }
 
-void* string_from_int(long long val) {
-    String* str = (String*)malloc(sizeof(String));
-    char buf[21];
-    sprintf(buf, "%lld", val);
-    str->len = strlen(buf);
-    str->chars = (char*)malloc(str->len + 1);
-    strcpy(str->chars, buf);
-    return str;
+void map_set(void* map_ptr, void* key, void* value) {
+    Map* map = (Map*)map_ptr;
+    int index = map_find(map, key);
+    if (index >= 0) {
+        map->values[index] = value;
+        return;
+    }
+    if (map->size == map->capacity) {
+        map->capacity *= 2;
+        map->keys = (void**)realloc(map->keys, sizeof(void*) * map->capacity);
+        map->values = (void**)realloc(map->values, sizeof(void*) * map->capacity);
+    }
+    map->keys[map->size] = key;
+    map->values[map->size++] = value;
 }
 
-void* string_from_float(double val) {
-    String* str = (String*)malloc(sizeof(String));
-    char buf[32];
-    sprintf(buf, "%f", val);
-    str->len = strlen(buf);
-    str->chars = (char*)malloc(str->len + 1);
-    strcpy(str->chars, buf);
-    return str;
+void* map_get(void* map_ptr, void* key, const char* location) {
+    Map* map = (Map*)map_ptr;
+    int index = map_find(map, key);
+    if (index < 0) {
+        fprintf(stderr, "%s: key not found in map\n", location);
+        exit(1);
+    }
+    return map->values[index];
 }
 
-void print_string(String* s) {
-    printf("%s\n", s->chars);
+// Collections hold untyped words, so the compiler passes a formatter for
+// each element type
+typedef String* (*Formatter)(void*);
+
+String* string_from_list(void* list_ptr, Formatter format) {
+    List* list = (List*)list_ptr;
+    String* str = string_from_chars("[", 1);
+    for (int i = 0; i < list->size; i++) {
+        if (i > 0) {
+            str = string_concat(str, string_from_chars(", ", 2));
+        }
+        str = string_concat(str, format(list->data[i]));
+    }
+    return string_concat(str, string_from_chars("]", 1));
+}
+
+String* string_from_map(void* map_ptr, Formatter format_key, Formatter format_value) {
+    Map* map = (Map*)map_ptr;
+    String* str = string_from_chars("{", 1);
+    for (int i = 0; i < map->size; i++) {
+        if (i > 0) {
+            str = string_concat(str, string_from_chars(", ", 2));
+        }
+        str = string_concat(str, format_key(map->keys[i]));
+        str = string_concat(str, string_from_chars(": ", 2));
+        str = string_concat(str, format_value(map->values[i]));
+    }
+    return string_concat(str, string_from_chars("}", 1));
 }
 
 // Iterator implementation
@@ -28,23 +28,35 @@ impl SourceSpan {
     }
 }
 
-// Global node ID counter for unique AST node identification
-static NODE_COUNTER: AtomicU32 = AtomicU32::new(0);
-
 /// Unique identifier for each AST node
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NodeId(u32);
 
 impl NodeId {
-    pub fn new() -> Self {
-        NodeId(NODE_COUNTER.fetch_add(1, Ordering::SeqCst))
-    }
-    
     pub fn as_u32(&self) -> u32 {
         self.0
     }
 }
 
+/// Per-compilation allocator for node IDs. IDs are handed out in parse order,
+/// so the same source always produces the same IDs.
+#[derive(Debug, Default)]
+pub struct NodeIdGen {
+    next: u32,
+}
+
+impl NodeIdGen {
+    pub fn new() -> Self {
+        Self { next: 0 }
+    }
+
+    pub fn fresh(&mut self) -> NodeId {
+        let id = NodeId(self.next);
+        self.next += 1;
+        id
+    }
+}
+
 /// Comprehensive type system for Coral
 #[derive(Debug, Clone, Eq)]
 pub enum Type {
@@ -77,6 +89,20 @@ pub enum Type {
         name: String,
         message_types: Vec<Type>,
     },
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<Type>)>,
+    },
+    Trait {
+        name: String,
+    },
+    
+    // Generics: a user-written parameter like `T`, and an applied type like `stack of i64`
+    Param(String),
+    Generic {
+        name: String,
+        args: Vec<Type>,
+    },
     
     // Type variables for inference
     TypeVar(u32),
@@ -131,6 +157,23 @@ impl Hash for Type {
                 "actor".hash(state);
                 name.hash(state);
             }
+            Type::Enum { name, .. } => {
+                "enum".hash(state);
+                name.hash(state);
+            }
+            Type::Trait { name } => {
+                "trait".hash(state);
+                name.hash(state);
+            }
+            Type::Param(name) => {
+                "param".hash(state);
+                name.hash(state);
+            }
+            Type::Generic { name, args } => {
+                "generic".hash(state);
+                name.hash(state);
+                args.hash(state);
+            }
             Type::TypeVar(id) => {
                 "typevar".hash(state);
                 id.hash(state);
@@ -165,6 +208,10 @@ impl PartialEq for Type {
             (Type::Object { name: n1, .. }, Type::Object { name: n2, .. }) => n1 == n2,
             (Type::Store { name: n1, .. }, Type::Store { name: n2, .. }) => n1 == n2,
             (Type::Actor { name: n1, .. }, Type::Actor { name: n2, .. }) => n1 == n2,
+            (Type::Enum { name: n1, .. }, Type::Enum { name: n2, .. }) => n1 == n2,
+            (Type::Trait { name: n1 }, Type::Trait { name: n2 }) => n1 == n2,
+            (Type::Param(n1), Type::Param(n2)) => n1 == n2,
+            (Type::Generic { name: n1, args: a1 }, Type::Generic { name: n2, args: a2 }) => n1 == n2 && a1 == a2,
             (Type::TypeVar(id1), Type::TypeVar(id2)) => id1 == id2,
             (Type::Result(o1, e1), Type::Result(o2, e2)) => o1 == o2 && e1 == e2,
             (Type::Unknown, Type::Unknown) => true,
@@ -191,6 +238,47 @@ impl Type {
         matches!(self, Type::F32 | Type::F64)
     }
     
+    /// Whether this type mentions a type parameter or an applied generic
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Type::Param(_) | Type::Generic { .. } => true,
+            Type::List(inner) => inner.is_generic(),
+            Type::Map(key, value) | Type::Result(key, value) => key.is_generic() || value.is_generic(),
+            Type::Function { params, return_type } => {
+                params.iter().any(Type::is_generic) || return_type.is_generic()
+            }
+            _ => false,
+        }
+    }
+    
+    /// Collect the names of type parameters mentioned in this type, in first-seen order
+    pub fn collect_type_params(&self, out: &mut Vec<String>) {
+        match self {
+            Type::Param(name) if !out.contains(name) => out.push(name.clone()),
+            Type::List(inner) => inner.collect_type_params(out),
+            Type::Map(key, value) => {
+                key.collect_type_params(out);
+                value.collect_type_params(out);
+            }
+            Type::Function { params, return_type } => {
+                for param in params {
+                    param.collect_type_params(out);
+                }
+                return_type.collect_type_params(out);
+            }
+            Type::Generic { args, .. } => {
+                for arg in args {
+                    arg.collect_type_params(out);
+                }
+            }
+            Type::Result(ok, err) => {
+                ok.collect_type_params(out);
+                err.collect_type_params(out);
+            }
+            _ => {}
+        }
+    }
+    
     /// Get the size in bytes for primitive types
     pub fn size_bytes(&self) -> Option<usize> {
         match self {
@@ -247,6 +335,13 @@ impl Type {
             Type::Object { name, .. } => format!("object {}", name),
             Type::Store { name, value_type } => format!("store {} of {}", name, value_type.to_string()),
             Type::Actor { name, .. } => format!("actor {}", name),
+            Type::Enum { name, .. } => format!("enum {}", name),
+            Type::Trait { name } => format!("trait {}", name),
+            Type::Param(name) => name.clone(),
+            Type::Generic { name, args } => {
+                let arg_types: Vec<String> = args.iter().map(|t| t.to_string()).collect();
+                format!("{} of {}", name, arg_types.join(", "))
+            }
             Type::TypeVar(id) => format!("T{}", id),
             Type::Result(ok, err) => format!("Result<{}, {}>", ok.to_string(), err.to_string()),
             Type::Unit => "unit".to_string(),
@@ -279,6 +374,17 @@ impl fmt::Display for Type {
             Type::Object { name, .. } => write!(f, "object {}", name),
             Type::Store { name, value_type } => write!(f, "store {} of {}", name, value_type),
             Type::Actor { name, .. } => write!(f, "actor {}", name),
+            Type::Enum { name, .. } => write!(f, "enum {}", name),
+            Type::Trait { name } => write!(f, "trait {}", name),
+            Type::Param(name) => write!(f, "{}", name),
+            Type::Generic { name, args } => {
+                write!(f, "{} of ", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 { write!(f, ", ")?; }
+                    write!(f, "{}", arg)?;
+                }
+                Ok(())
+            }
             Type::TypeVar(id) => write!(f, "T{}", id),
             Type::Result(ok, err) => write!(f, "Result<{}, {}>", ok, err),
             Type::Unit => write!(f, "unit"),
@@ -349,7 +455,6 @@ pub enum StringPart {
 pub struct Expr {
     pub id: NodeId,
     pub span: SourceSpan,
-    pub type_: Type,
     pub kind: ExprKind,
 }
 
@@ -412,21 +517,18 @@ pub enum ExprKind {
         name: String,
         fields: Vec<(String, Expr)>,
     },
+    // `err payload` - constructs the error side of a Result
+    ErrValue(Box<Expr>),
+    // `expr as i32` - explicit numeric conversion
+    Cast {
+        expr: Box<Expr>,
+        target: Type,
+    },
 }
 
 impl Expr {
-    pub fn new(span: SourceSpan, kind: ExprKind) -> Self {
-        Self {
-            id: NodeId::new(),
-            span,
-            type_: Type::Unknown,
-            kind,
-        }
-    }
-    
-    pub fn with_type(mut self, type_: Type) -> Self {
-        self.type_ = type_;
-        self
+    pub fn new(id: NodeId, span: SourceSpan, kind: ExprKind) -> Self {
+        Self { id, span, kind }
     }
 }
 
@@ -474,9 +576,13 @@ pub enum StmtKind {
         params: Vec<Parameter>,
         return_type: Option<Type>,
         body: Vec<Stmt>,
+        /// Declared `pure fn`: the body may not perform any effects
+        is_pure: bool,
     },
     Object {
         name: String,
+        type_params: Vec<String>,  // Generic parameters declared with `object name of T, U`
+        traits: Vec<String>,  // Traits declared with `object name is trait_a, trait_b`
         fields: Vec<Field>,
         methods: Vec<ObjectMethod>,  // Methods within the object
     },
@@ -490,6 +596,14 @@ pub enum StmtKind {
         fields: Vec<Field>,
         handlers: Vec<MessageHandler>,
     },
+    Enum {
+        name: String,
+        variants: Vec<EnumVariant>,
+    },
+    Trait {
+        name: String,
+        methods: Vec<TraitMethod>,
+    },
     Import {
         module: String,
         items: Option<Vec<String>>,
@@ -501,12 +615,8 @@ pub enum StmtKind {
 }
 
 impl Stmt {
-    pub fn new(span: SourceSpan, kind: StmtKind) -> Self {
-        Self {
-            id: NodeId::new(),
-            span,
-            kind,
-        }
+    pub fn new(id: NodeId, span: SourceSpan, kind: StmtKind) -> Self {
+        Self { id, span, kind }
     }
 }
 
@@ -542,6 +652,18 @@ pub struct Parameter {
     pub span: SourceSpan,
 }
 
+/// Type parameters a function signature mentions, e.g. `T` in `fn first of T(xs: (T)) -> T`
+pub fn signature_type_params(params: &[Parameter], return_type: Option<&Type>) -> Vec<String> {
+    let mut names = Vec::new();
+    for param in params {
+        param.type_.collect_type_params(&mut names);
+    }
+    if let Some(ty) = return_type {
+        ty.collect_type_params(&mut names);
+    }
+    names
+}
+
 /// Object field definition
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field {
@@ -561,6 +683,28 @@ pub struct ObjectMethod {
     pub span: SourceSpan,
 }
 
+/// Name of the method an `as <type>` member of an object body defines
+pub fn conversion_method_name(target: &Type) -> String {
+    format!("as_{}", target)
+}
+
+/// Enum variant definition, either a unit variant or one carrying a payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub payload: Vec<Type>,
+    pub span: SourceSpan,
+}
+
+/// Trait method signature - no body, conforming objects supply it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitMethod {
+    pub name: String,
+    pub params: Vec<Parameter>,
+    pub return_type: Option<Type>,
+    pub span: SourceSpan,
+}
+
 /// Actor message handler
 #[derive(Debug, Clone, PartialEq)]
 pub struct MessageHandler {
@@ -631,6 +775,7 @@ impl StmtKind {
             params,
             return_type: None,
             body,
+            is_pure: false,
         }
     }
 }
@@ -664,9 +809,11 @@ mod tests {
 
     #[test]
     fn test_node_id_uniqueness() {
-        let id1 = NodeId::new();
-        let id2 = NodeId::new();
+        let mut ids = NodeIdGen::new();
+        let id1 = ids.fresh();
+        let id2 = ids.fresh();
         assert_ne!(id1, id2);
+        assert_eq!(NodeIdGen::new().fresh(), id1);
     }
     
     #[test]
@@ -685,20 +832,21 @@ mod tests {
     #[test]
     fn test_expr_construction() {
         let span = SourceSpan::default();
-        let expr = Expr::new(span.clone(), ExprKind::literal(Literal::Integer(42)));
+        let mut ids = NodeIdGen::new();
+        let expr = Expr::new(ids.fresh(), span.clone(), ExprKind::literal(Literal::Integer(42)));
         
         assert_eq!(expr.span, span);
-        assert_eq!(expr.type_, Type::Unknown);
         assert!(matches!(expr.kind, ExprKind::Literal(Literal::Integer(42))));
     }
     
     #[test]
     fn test_binary_expr_construction() {
         let span = SourceSpan::default();
-        let left = Expr::new(span.clone(), ExprKind::literal(Literal::Integer(1)));
-        let right = Expr::new(span.clone(), ExprKind::literal(Literal::Integer(2)));
+        let mut ids = NodeIdGen::new();
+        let left = Expr::new(ids.fresh(), span.clone(), ExprKind::literal(Literal::Integer(1)));
+        let right = Expr::new(ids.fresh(), span.clone(), ExprKind::literal(Literal::Integer(2)));
         
-        let binary = Expr::new(span.clone(), ExprKind::binary(BinaryOp::Add, left, right));
+        let binary = Expr::new(ids.fresh(), span.clone(), ExprKind::binary(BinaryOp::Add, left, right));
         
         if let ExprKind::Binary { op, .. } = &binary.kind {
             assert_eq!(*op, BinaryOp::Add);
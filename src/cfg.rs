@@ -0,0 +1,354 @@
+//! Control-flow graphs over statement bodies.
+//!
+//! Each function, method or handler body gets its own graph. Conditions and
+//! iterables sit at the end of the block that branches on them; nested
+//! function definitions are opaque statements.
+
+use crate::ast::*;
+use std::collections::HashMap;
+
+pub type BlockId = usize;
+
+/// Something a basic block executes, in order
+#[derive(Debug, Clone, Copy)]
+pub enum Node<'a> {
+    /// A simple statement (assignment, expression, return, ...)
+    Stmt(&'a Stmt),
+    /// A condition or iterable evaluated before the block branches
+    Expr(&'a Expr),
+}
+
+#[derive(Debug, Default)]
+pub struct BasicBlock<'a> {
+    pub nodes: Vec<Node<'a>>,
+    pub successors: Vec<BlockId>,
+    pub predecessors: Vec<BlockId>,
+}
+
+#[derive(Debug)]
+pub struct Cfg<'a> {
+    pub blocks: Vec<BasicBlock<'a>>,
+    /// Block each statement starts in, compound statements included
+    pub stmt_blocks: HashMap<NodeId, BlockId>,
+    /// Block that runs off the end of the body
+    pub fallthrough: BlockId,
+    /// `break`/`continue` statements with no enclosing loop
+    pub stray_jumps: Vec<&'a Stmt>,
+}
+
+impl<'a> Cfg<'a> {
+    pub const ENTRY: BlockId = 0;
+    pub const EXIT: BlockId = 1;
+
+    pub fn build(body: &'a [Stmt]) -> Self {
+        let mut builder = Builder {
+            cfg: Cfg {
+                blocks: vec![BasicBlock::default(), BasicBlock::default()],
+                stmt_blocks: HashMap::new(),
+                fallthrough: Self::ENTRY,
+                stray_jumps: Vec::new(),
+            },
+            current: Self::ENTRY,
+            loops: Vec::new(),
+        };
+        builder.stmts(body);
+        let fallthrough = builder.current;
+        builder.edge(fallthrough, Self::EXIT);
+        builder.cfg.fallthrough = fallthrough;
+        builder.cfg
+    }
+
+    /// Which blocks can be reached from the entry
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reached = vec![false; self.blocks.len()];
+        let mut work = vec![Self::ENTRY];
+        while let Some(block) = work.pop() {
+            if !reached[block] {
+                reached[block] = true;
+                work.extend(&self.blocks[block].successors);
+            }
+        }
+        reached
+    }
+}
+
+struct Builder<'a> {
+    cfg: Cfg<'a>,
+    current: BlockId,
+    /// `(continue target, break target)` of each enclosing loop
+    loops: Vec<(BlockId, BlockId)>,
+}
+
+impl<'a> Builder<'a> {
+    fn new_block(&mut self) -> BlockId {
+        self.cfg.blocks.push(BasicBlock::default());
+        self.cfg.blocks.len() - 1
+    }
+
+    fn edge(&mut self, from: BlockId, to: BlockId) {
+        self.cfg.blocks[from].successors.push(to);
+        self.cfg.blocks[to].predecessors.push(from);
+    }
+
+    fn push(&mut self, node: Node<'a>) {
+        self.cfg.blocks[self.current].nodes.push(node);
+    }
+
+    /// Leave the current block for good; anything after starts out unreachable
+    fn jump(&mut self, target: BlockId) {
+        self.edge(self.current, target);
+        self.current = self.new_block();
+    }
+
+    fn stmts(&mut self, stmts: &'a [Stmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &'a Stmt) {
+        self.cfg.stmt_blocks.insert(stmt.id, self.current);
+        match &stmt.kind {
+            StmtKind::If { condition, then_branch, else_branch } => {
+                self.branch(condition, then_branch, else_branch.as_deref());
+            }
+            StmtKind::Unless { condition, body } => self.branch(condition, body, None),
+            StmtKind::While { condition, body } => {
+                let runs_forever = matches!(condition.kind, ExprKind::Literal(Literal::Bool(true)));
+                self.loop_(Some(condition), runs_forever, body);
+            }
+            StmtKind::Until { condition, body } => {
+                let runs_forever = matches!(condition.kind, ExprKind::Literal(Literal::Bool(false)));
+                self.loop_(Some(condition), runs_forever, body);
+            }
+            StmtKind::Iterate { iterable, body } => {
+                self.push(Node::Expr(iterable));
+                self.loop_(None, false, body);
+            }
+            StmtKind::Return(_) => {
+                self.push(Node::Stmt(stmt));
+                self.jump(Cfg::EXIT);
+            }
+            StmtKind::Break | StmtKind::Continue => {
+                self.push(Node::Stmt(stmt));
+                match self.loops.last() {
+                    Some(&(continue_to, break_to)) => {
+                        let target = if matches!(stmt.kind, StmtKind::Break) { break_to } else { continue_to };
+                        self.jump(target);
+                    }
+                    None => {
+                        self.cfg.stray_jumps.push(stmt);
+                        self.current = self.new_block();
+                    }
+                }
+            }
+            StmtKind::ErrorHandler { handler, .. } => {
+                self.push(Node::Stmt(stmt));
+                // `err return` leaves the body, but only when the guarded statement fails
+                if handler.actions.iter().any(|action| matches!(action, ErrorAction::Return(_))) {
+                    self.edge(self.current, Cfg::EXIT);
+                }
+            }
+            _ => self.push(Node::Stmt(stmt)),
+        }
+    }
+
+    fn branch(&mut self, condition: &'a Expr, then_branch: &'a [Stmt], else_branch: Option<&'a [Stmt]>) {
+        self.push(Node::Expr(condition));
+        let branch_from = self.current;
+        let join = self.new_block();
+
+        self.current = self.new_block();
+        self.edge(branch_from, self.current);
+        self.stmts(then_branch);
+        self.edge(self.current, join);
+
+        match else_branch {
+            Some(else_branch) => {
+                self.current = self.new_block();
+                self.edge(branch_from, self.current);
+                self.stmts(else_branch);
+                self.edge(self.current, join);
+            }
+            None => self.edge(branch_from, join),
+        }
+        self.current = join;
+    }
+
+    fn loop_(&mut self, condition: Option<&'a Expr>, runs_forever: bool, body: &'a [Stmt]) {
+        let header = self.new_block();
+        let after = self.new_block();
+        self.edge(self.current, header);
+        self.current = header;
+        if let Some(condition) = condition {
+            self.push(Node::Expr(condition));
+        }
+        if !runs_forever {
+            self.edge(header, after);
+        }
+
+        self.current = self.new_block();
+        self.edge(header, self.current);
+        self.loops.push((header, after));
+        self.stmts(body);
+        self.loops.pop();
+        self.edge(self.current, header);
+        self.current = after;
+    }
+}
+
+/// Identifiers a node reads, ignoring the variable an assignment writes
+pub fn node_reads<'a>(node: Node<'a>, out: &mut Vec<&'a Expr>) {
+    match node {
+        Node::Expr(expr) => expr_reads(expr, out),
+        Node::Stmt(stmt) => stmt_reads(stmt, out),
+    }
+}
+
+fn stmt_reads<'a>(stmt: &'a Stmt, out: &mut Vec<&'a Expr>) {
+    match &stmt.kind {
+        StmtKind::Expression(expr) | StmtKind::Return(Some(expr)) => expr_reads(expr, out),
+        StmtKind::Assignment { target, value } => {
+            expr_reads(value, out);
+            if !matches!(target.kind, ExprKind::Identifier(_)) {
+                expr_reads(target, out);
+            }
+        }
+        StmtKind::ErrorHandler { handler, inner } => {
+            stmt_reads(inner, out);
+            for action in &handler.actions {
+                match action {
+                    ErrorAction::Log(Some(expr)) | ErrorAction::Return(Some(expr)) | ErrorAction::Custom(expr) => {
+                        expr_reads(expr, out);
+                    }
+                    ErrorAction::Log(None) | ErrorAction::Return(None) => {}
+                }
+            }
+        }
+        StmtKind::If { condition, then_branch, else_branch } => {
+            expr_reads(condition, out);
+            then_branch.iter().chain(else_branch.iter().flatten()).for_each(|stmt| stmt_reads(stmt, out));
+        }
+        StmtKind::Unless { condition, body } | StmtKind::While { condition, body } | StmtKind::Until { condition, body } => {
+            expr_reads(condition, out);
+            body.iter().for_each(|stmt| stmt_reads(stmt, out));
+        }
+        StmtKind::Iterate { iterable, body } => {
+            expr_reads(iterable, out);
+            body.iter().for_each(|stmt| stmt_reads(stmt, out));
+        }
+        _ => {}
+    }
+}
+
+fn expr_reads<'a>(expr: &'a Expr, out: &mut Vec<&'a Expr>) {
+    match &expr.kind {
+        ExprKind::Identifier(_) => out.push(expr),
+        ExprKind::Literal(_) => {}
+        ExprKind::Binary { left, right, .. } => {
+            expr_reads(left, out);
+            expr_reads(right, out);
+        }
+        ExprKind::Unary { operand, .. } => expr_reads(operand, out),
+        ExprKind::Call { callee, args } => {
+            expr_reads(callee, out);
+            for arg in args {
+                expr_reads(&arg.value, out);
+            }
+        }
+        ExprKind::Index { object, index } => {
+            expr_reads(object, out);
+            expr_reads(index, out);
+        }
+        ExprKind::FieldAccess { object, .. } => expr_reads(object, out),
+        ExprKind::ListLiteral(elements) => {
+            for element in elements {
+                expr_reads(element, out);
+            }
+        }
+        ExprKind::MapLiteral(pairs) => {
+            for (key, value) in pairs {
+                expr_reads(key, out);
+                expr_reads(value, out);
+            }
+        }
+        ExprKind::ListAppend { list, element } => {
+            expr_reads(list, out);
+            expr_reads(element, out);
+        }
+        ExprKind::MapInsert { map, key, value } => {
+            expr_reads(map, out);
+            expr_reads(key, out);
+            expr_reads(value, out);
+        }
+        ExprKind::Across { callee, iterable, .. } => {
+            expr_reads(callee, out);
+            expr_reads(iterable, out);
+        }
+        ExprKind::StringInterpolation { parts } => {
+            for part in parts {
+                if let StringPart::Expression(inner) = part {
+                    expr_reads(inner, out);
+                }
+            }
+        }
+        ExprKind::If { condition, then_branch, else_branch } => {
+            expr_reads(condition, out);
+            expr_reads(then_branch, out);
+            if let Some(else_branch) = else_branch {
+                expr_reads(else_branch, out);
+            }
+        }
+        ExprKind::Block(stmts) => stmts.iter().for_each(|stmt| stmt_reads(stmt, out)),
+        ExprKind::Lambda { body, .. } => expr_reads(body, out),
+        ExprKind::ObjectInstantiation { fields, .. } => {
+            for (_, value) in fields {
+                expr_reads(value, out);
+            }
+        }
+        ExprKind::ErrValue(inner) | ExprKind::Cast { expr: inner, .. } => expr_reads(inner, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn body_of(input: &str) -> Vec<Stmt> {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        let program = parser.parse().unwrap();
+        match program.statements.into_iter().next().map(|stmt| stmt.kind) {
+            Some(StmtKind::Function { body, .. }) => body,
+            _ => panic!("Expected function"),
+        }
+    }
+
+    #[test]
+    fn test_return_ends_reachability() {
+        let body = body_of("fn f(flag)\n    if flag\n        return 1\n    else\n        return 2\n    log('done')\n");
+        let cfg = Cfg::build(&body);
+        let reachable = cfg.reachable();
+        assert!(reachable[cfg.stmt_blocks[&body[0].id]]);
+        assert!(!reachable[cfg.stmt_blocks[&body[1].id]]);
+        assert!(!reachable[cfg.fallthrough]);
+    }
+
+    #[test]
+    fn test_loops() {
+        let body = body_of("fn f()\n    while true\n        break\n    return 1\n");
+        let cfg = Cfg::build(&body);
+        assert!(cfg.reachable()[cfg.stmt_blocks[&body[1].id]]);
+        assert!(cfg.stray_jumps.is_empty());
+
+        let body = body_of("fn f()\n    while true\n        log('spin')\n    return 1\n");
+        let cfg = Cfg::build(&body);
+        assert!(!cfg.reachable()[cfg.stmt_blocks[&body[1].id]]);
+
+        let body = body_of("fn f(flag)\n    if flag\n        continue\n");
+        assert_eq!(Cfg::build(&body).stray_jumps.len(), 1);
+    }
+}
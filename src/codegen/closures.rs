@@ -0,0 +1,236 @@
+//! Lambdas and functions as values.
+//!
+//! Every function value is a closure `{ i8*, i8* }`: a pointer to code that
+//! takes the environment as a leading `i8*`, and the environment itself. A
+//! lambda copies the locals it mentions into a heap-allocated environment
+//! struct when it is created; top-level functions used as values get a thunk
+//! that ignores the environment and forwards to the function.
+
+use std::collections::HashSet;
+
+use crate::ast::{Expr, ExprKind, NodeId, Parameter, StmtKind, Type};
+use crate::codegen::types::{closure_type, infer_to_llvm_type, LLVMType};
+use crate::codegen::{Binding, CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::generalize::collect_expr_references;
+use crate::resolver::InferType;
+
+/// A top-level function referenced by symbol, called without an environment
+pub(crate) fn is_direct_function(value: &LLVMValue) -> bool {
+    matches!(&value.llvm_type, LLVMType::Pointer(inner) if matches!(**inner, LLVMType::Function { .. }))
+}
+
+/// LLVM type of the code behind a closure: the environment, then the parameters
+fn closure_code_type(params: &[InferType], return_type: &InferType) -> LLVMType {
+    let mut llvm_params = vec![LLVMType::Pointer(Box::new(LLVMType::Int(8)))];
+    llvm_params.extend(params.iter().map(infer_to_llvm_type));
+    LLVMType::Function { ret: Box::new(infer_to_llvm_type(return_type)), params: llvm_params }
+}
+
+impl LLVMCodegen {
+    /// Compile `fn(params) -> body` into its own function and build its closure
+    pub fn compile_lambda(&mut self, id: NodeId, params: &[Parameter], body: &Expr) -> Result<LLVMValue, CodegenError> {
+        // Unannotated parameters take the types inference solved for the lambda
+        let solved = match self.types.get(id) {
+            Some(Type::Function { params, .. }) => params.clone(),
+            _ => Vec::new(),
+        };
+        let mut param_types = Vec::new();
+        for (index, param) in params.iter().enumerate() {
+            let declared = match &param.type_ {
+                Type::Unknown => solved.get(index).unwrap_or(&Type::Unknown),
+                annotated => annotated,
+            };
+            match self.ast_type_to_infer_type(declared) {
+                InferType::Unknown => return Err(CodegenError::UnsupportedFeature(format!(
+                    "lambda parameter '{}' needs a type annotation", param.name
+                ))),
+                param_type => param_types.push(param_type),
+            }
+        }
+
+        let captures = self.captures(params, body);
+        let symbol = format!("__coral_lambda.{}", self.lambda_count);
+        self.lambda_count += 1;
+        let env_type = LLVMType::NamedStruct(format!("{}.env", symbol));
+        if !captures.is_empty() {
+            let fields = captures.iter().map(|(_, binding)| binding.value().llvm_type.to_string()).collect::<Vec<_>>();
+            self.module.define_type(format!("{} = type {{ {} }}", env_type, fields.join(", ")));
+        }
+
+        // The return type is only known once the body is compiled: the header is
+        // written last, and until then the first `return` fixes the type
+        let frame = self.begin_function("", InferType::Unknown);
+        if !captures.is_empty() {
+            let env = self.next_temp();
+            self.emit(&format!("  %{} = bitcast i8* %env to {}*", env, env_type));
+            // Captured variables are read and written in place in the environment
+            for (index, (name, binding)) in captures.iter().enumerate() {
+                let field = self.next_temp();
+                self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}", field, env_type, env_type, env, index));
+                self.symbols.define_slot(name.clone(), LLVMValue { value_id: format!("%{}", field), ..binding.value().clone() });
+            }
+        }
+        self.bind_params(params, param_types.clone());
+
+        let trailing = match &body.kind {
+            ExprKind::Block(stmts) => {
+                let mut last_value = None;
+                for stmt in stmts {
+                    last_value = self.compile_statement(stmt)?;
+                }
+                last_value.filter(|_| matches!(stmts.last().map(|stmt| &stmt.kind), Some(StmtKind::Expression(_))))
+            }
+            _ => Some(self.compile_expression(body)?),
+        };
+        let return_type = match self.current_return_type.clone() {
+            Some(InferType::Unknown) | None => trailing.as_ref().map_or(InferType::Unit, |value| value.type_info.clone()),
+            Some(returned) => returned,
+        };
+        self.current_return_type = Some(return_type.clone());
+        let return_llvm_type = infer_to_llvm_type(&return_type);
+        self.emit_implicit_return(&return_llvm_type, trailing)?;
+
+        let mut signature = vec!["i8* %env".to_string()];
+        signature.extend(params.iter().zip(&param_types)
+            .map(|(param, ty)| format!("{} %{}.arg", infer_to_llvm_type(ty), param.name)));
+        let header_end = self.output.find('\n').unwrap_or(0);
+        self.output.replace_range(..header_end, &format!(
+            "define internal {} @{}({}) {{", return_llvm_type, symbol, signature.join(", ")
+        ));
+        self.finish_function(frame);
+
+        let env = if captures.is_empty() {
+            "null".to_string()
+        } else {
+            self.allocate_environment(&env_type, &captures)
+        };
+        let code_type = closure_code_type(&param_types, &return_type);
+        Ok(self.build_closure(&format!("{}* @{}", code_type, symbol), &env, InferType::Function {
+            params: param_types,
+            return_type: Box::new(return_type),
+            effects: Default::default(),
+        }))
+    }
+
+    /// Locals of the enclosing function that `body` mentions, in name order
+    fn captures(&self, params: &[Parameter], body: &Expr) -> Vec<(String, Binding)> {
+        let mut names = HashSet::new();
+        collect_expr_references(body, &mut names);
+        for param in params {
+            names.remove(&param.name);
+        }
+        let mut captures: Vec<_> = names.into_iter()
+            .filter_map(|name| {
+                let binding = self.symbols.lookup_local(&name)?.clone();
+                Some((name, binding))
+            })
+            .collect();
+        captures.sort_by(|a, b| a.0.cmp(&b.0));
+        captures
+    }
+
+    /// Copy the captured variables' current values into a fresh environment
+    fn allocate_environment(&mut self, env_type: &LLVMType, captures: &[(String, Binding)]) -> String {
+        let size = self.size_of(env_type);
+        self.module.declare("declare i8* @malloc(i64)");
+        let raw = self.next_temp();
+        self.emit(&format!("  %{} = call i8* @malloc(i64 {})", raw, size));
+        let env = self.next_temp();
+        self.emit(&format!("  %{} = bitcast i8* %{} to {}*", env, raw, env_type));
+
+        for (index, (_, binding)) in captures.iter().enumerate() {
+            let value = self.binding_value(binding);
+            let field = self.next_temp();
+            self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}", field, env_type, env_type, env, index));
+            self.emit(&format!("  store {} {}, {}* %{}", value.llvm_type, value.value_id, value.llvm_type, field));
+        }
+        format!("%{}", raw)
+    }
+
+    /// Pair typed code pointer `code` with environment `env`
+    fn build_closure(&mut self, code: &str, env: &str, type_info: InferType) -> LLVMValue {
+        let closure = closure_type();
+        let with_code = self.next_temp();
+        self.emit(&format!("  %{} = insertvalue {} undef, i8* bitcast ({} to i8*), 0", with_code, closure, code));
+        let with_env = self.next_temp();
+        self.emit(&format!("  %{} = insertvalue {} %{}, i8* {}, 1", with_env, closure, with_code, env));
+        LLVMValue { type_info, llvm_type: closure, value_id: format!("%{}", with_env) }
+    }
+
+    /// The closure for a top-level function, through a thunk emitted on first use
+    pub(crate) fn function_closure(&mut self, function: &LLVMValue) -> LLVMValue {
+        let (params, return_type) = match &function.type_info {
+            InferType::Function { params, return_type, .. } => (params.clone(), return_type.as_ref().clone()),
+            _ => (Vec::new(), InferType::Unit),
+        };
+        let thunk = format!("{}.closure", function.value_id);
+        if self.thunks.insert(thunk.clone()) {
+            let return_llvm_type = infer_to_llvm_type(&return_type);
+            let params = params.iter().enumerate()
+                .map(|(index, ty)| format!("{} %a{}", infer_to_llvm_type(ty), index))
+                .collect::<Vec<_>>();
+            let call = format!("call {} {}({})", return_llvm_type, function.value_id, params.join(", "));
+            let body = if return_llvm_type == LLVMType::Void {
+                format!("  {}\n  ret void\n", call)
+            } else {
+                format!("  %result = {}\n  ret {} %result\n", call, return_llvm_type)
+            };
+            let mut signature = vec!["i8* %env".to_string()];
+            signature.extend(params);
+            self.module.add_function(format!(
+                "define internal {} {}({}) {{\nentry:\n{}}}\n", return_llvm_type, thunk, signature.join(", "), body
+            ));
+        }
+        let code_type = closure_code_type(&params, &return_type);
+        self.build_closure(&format!("{}* {}", code_type, thunk), "null", function.type_info.clone())
+    }
+
+    /// Split `closure` into its typed code pointer and its environment
+    pub(crate) fn closure_parts(&mut self, closure: &LLVMValue) -> (String, String) {
+        let (params, return_type) = match &closure.type_info {
+            InferType::Function { params, return_type, .. } => (params.clone(), return_type.as_ref().clone()),
+            _ => (Vec::new(), InferType::Unit),
+        };
+        let code = self.next_temp();
+        self.emit(&format!("  %{} = extractvalue {} {}, 0", code, closure.llvm_type, closure.value_id));
+        let env = self.next_temp();
+        self.emit(&format!("  %{} = extractvalue {} {}, 1", env, closure.llvm_type, closure.value_id));
+        let typed = self.next_temp();
+        self.emit(&format!("  %{} = bitcast i8* %{} to {}*", typed, code, closure_code_type(&params, &return_type)));
+        (format!("%{}", typed), format!("i8* %{}", env))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::compile_source;
+
+    #[test]
+    fn test_lambda_captures_locals_in_environment() {
+        let ir = compile_source("fn adder(k: i64) -> fn(i64) -> i64\n    return fn(n: i64) -> n + k\n").unwrap();
+        assert!(ir.contains("%__coral_lambda.0.env = type { i64 }"));
+        assert!(ir.contains("define internal i64 @__coral_lambda.0(i8* %env, i64 %n.arg)"));
+        assert!(ir.contains("define { i8*, i8* } @adder(i64 %k.arg)"));
+        assert!(ir.contains("call i8* @malloc"));
+        assert!(ir.contains("i8* bitcast (i64 (i8*, i64)* @__coral_lambda.0 to i8*)"));
+    }
+
+    #[test]
+    fn test_functions_as_values_call_through_closures() {
+        let ir = compile_source(
+            "fn apply(f: fn(i64) -> i64, x: i64) -> i64\n    return f(x)\nfn double(n: i64) -> i64\n    return n * 2\nfn main() -> i64\n    return apply(double, 3) + double(1)\n"
+        ).unwrap();
+        // Named functions are wrapped once and still called directly by name
+        assert!(ir.contains("define internal i64 @double.closure(i8* %env, i64 %a0)"));
+        assert!(ir.contains("call i64 @double(i64 1)"));
+        assert!(ir.contains(" to i64 (i8*, i64)*\n"));
+    }
+
+    #[test]
+    fn test_unannotated_lambda_params_take_inferred_types() {
+        let ir = compile_source(
+            "fn twice(f: fn(i64) -> i64, x: i64) -> i64\n    return f(f(x))\nfn main() -> i64\n    step is 2\n    return twice(fn(n) -> n + step, 1)\n"
+        ).unwrap();
+        assert!(ir.contains("define internal i64 @__coral_lambda.0(i8* %env, i64 %n.arg)"));
+    }
+}
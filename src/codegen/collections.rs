@@ -0,0 +1,211 @@
+//! Lists and maps.
+//!
+//! Both are opaque runtime handles (`i8*`) whose elements are pointer-sized
+//! words. Pointers are stored as they are; integers and bools are widened to
+//! `i64` and floats reinterpreted as `i64` bits before being cast to `i8*`.
+//! Element accesses pass the runtime their source location, which it reports
+//! when an index is out of bounds or a key is missing.
+
+use crate::ast::{Expr, ExprKind, SourceSpan};
+use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::InferType;
+
+impl LLVMCodegen {
+    /// `value` packed into an `i8*` word, as the runtime stores it
+    pub(crate) fn pack_word(&mut self, value: &LLVMValue) -> Result<String, CodegenError> {
+        let bits = match &value.llvm_type {
+            LLVMType::Pointer(inner) if **inner == LLVMType::Int(8) => return Ok(value.value_id.clone()),
+            LLVMType::Pointer(_) => {
+                let word = self.next_temp();
+                self.emit(&format!("  %{} = bitcast {} {} to i8*", word, value.llvm_type, value.value_id));
+                return Ok(format!("%{}", word));
+            }
+            LLVMType::Int(64) => value.value_id.clone(),
+            LLVMType::Int(1) => {
+                let bits = self.next_temp();
+                self.emit(&format!("  %{} = zext i1 {} to i64", bits, value.value_id));
+                format!("%{}", bits)
+            }
+            LLVMType::Int(_) => self.convert_numeric(value.clone(), &InferType::Int)?.value_id,
+            LLVMType::Float | LLVMType::Double => {
+                let double = self.convert_numeric(value.clone(), &InferType::Float)?;
+                let bits = self.next_temp();
+                self.emit(&format!("  %{} = bitcast double {} to i64", bits, double.value_id));
+                format!("%{}", bits)
+            }
+            other => return Err(CodegenError::UnsupportedFeature(format!(
+                "{} values cannot be stored in a collection", other
+            ))),
+        };
+        let word = self.next_temp();
+        self.emit(&format!("  %{} = inttoptr i64 {} to i8*", word, bits));
+        Ok(format!("%{}", word))
+    }
+
+    /// A word read back from the runtime as an `element_type` value
+    pub(crate) fn unpack_word(&mut self, word: &str, element_type: &InferType) -> Result<LLVMValue, CodegenError> {
+        let llvm_type = infer_to_llvm_type(element_type);
+        let value_id = match &llvm_type {
+            LLVMType::Pointer(inner) if **inner == LLVMType::Int(8) => word.to_string(),
+            LLVMType::Pointer(_) => {
+                let value = self.next_temp();
+                self.emit(&format!("  %{} = bitcast i8* {} to {}", value, word, llvm_type));
+                format!("%{}", value)
+            }
+            LLVMType::Int(_) | LLVMType::Float | LLVMType::Double => {
+                let bits = self.next_temp();
+                self.emit(&format!("  %{} = ptrtoint i8* {} to i64", bits, word));
+                let bits = LLVMValue { type_info: InferType::Int, llvm_type: LLVMType::Int(64), value_id: format!("%{}", bits) };
+                if llvm_type == LLVMType::Int(64) {
+                    bits.value_id
+                } else if let LLVMType::Int(width) = llvm_type {
+                    let value = self.next_temp();
+                    self.emit(&format!("  %{} = trunc i64 {} to i{}", value, bits.value_id, width));
+                    format!("%{}", value)
+                } else {
+                    let double = self.next_temp();
+                    self.emit(&format!("  %{} = bitcast i64 {} to double", double, bits.value_id));
+                    let double = LLVMValue { type_info: InferType::Float, llvm_type: LLVMType::Double, value_id: format!("%{}", double) };
+                    return self.convert_numeric(double, element_type);
+                }
+            }
+            other => return Err(CodegenError::UnsupportedFeature(format!(
+                "{} values cannot be read from a collection", other
+            ))),
+        };
+        Ok(LLVMValue { type_info: element_type.clone(), llvm_type, value_id })
+    }
+
+    /// `file:line:col` of `span` as an `i8*` constant for runtime error messages
+    fn source_location(&mut self, span: &SourceSpan) -> String {
+        let location = format!("{}:{}:{}", span.file, span.start_line, span.start_col);
+        self.module.c_string(&location)
+    }
+
+    /// Type of a variable or a chain of fields on one, known without emitting code
+    pub(crate) fn static_type(&self, expr: &Expr) -> Option<InferType> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.symbols.lookup(name).map(|binding| binding.value().type_info.clone()),
+            ExprKind::FieldAccess { object, field } => match self.static_type(object)? {
+                InferType::Object { fields, .. } => fields.get(field).cloned(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// `collection(index)` or `collection[index]`: a list or string position, or a map entry
+    pub fn compile_element_load(&mut self, collection_val: LLVMValue, index: &Expr, span: &SourceSpan) -> Result<LLVMValue, CodegenError> {
+        let index_val = self.compile_expression(index)?;
+        let location = self.source_location(span);
+        match collection_val.type_info.clone() {
+            InferType::List(element_type) => {
+                let index_val = self.convert_numeric(index_val, &InferType::Int)?;
+                self.module.declare("declare i8* @list_get(i8*, i64, i8*)");
+                let word = self.next_temp();
+                self.emit(&format!(
+                    "  %{} = call i8* @list_get(i8* {}, i64 {}, i8* {})",
+                    word, collection_val.value_id, index_val.value_id, location
+                ));
+                self.unpack_word(&format!("%{}", word), &element_type)
+            }
+            InferType::Map(_, value_type) => {
+                let key_word = self.pack_word(&index_val)?;
+                self.module.declare("declare i8* @map_get(i8*, i8*, i8*)");
+                let word = self.next_temp();
+                self.emit(&format!(
+                    "  %{} = call i8* @map_get(i8* {}, i8* {}, i8* {})",
+                    word, collection_val.value_id, key_word, location
+                ));
+                self.unpack_word(&format!("%{}", word), &value_type)
+            }
+            InferType::String => {
+                let index_val = self.convert_numeric(index_val, &InferType::Int)?;
+                self.module.declare("declare %string* @string_at(%string*, i64, i8*)");
+                let character = self.next_temp();
+                self.emit(&format!(
+                    "  %{} = call %string* @string_at(%string* {}, i64 {}, i8* {})",
+                    character, collection_val.value_id, index_val.value_id, location
+                ));
+                Ok(LLVMValue {
+                    type_info: InferType::String,
+                    llvm_type: infer_to_llvm_type(&InferType::String),
+                    value_id: format!("%{}", character),
+                })
+            }
+            other => {
+                let type_name = self.type_to_string(&other);
+                Err(CodegenError::InvalidOperation(format!("Cannot index {}", type_name)))
+            }
+        }
+    }
+
+    /// `collection(index) is value`: a list position or a map entry
+    pub fn compile_element_store(&mut self, collection: &Expr, index: &Expr, value: LLVMValue, span: &SourceSpan) -> Result<(), CodegenError> {
+        let collection_val = self.compile_expression(collection)?;
+        let index_val = self.compile_expression(index)?;
+        let value_word = self.pack_word(&value)?;
+        match &collection_val.type_info {
+            InferType::List(_) => {
+                let index_val = self.convert_numeric(index_val, &InferType::Int)?;
+                let location = self.source_location(span);
+                self.module.declare("declare void @list_set(i8*, i64, i8*, i8*)");
+                self.emit(&format!(
+                    "  call void @list_set(i8* {}, i64 {}, i8* {}, i8* {})",
+                    collection_val.value_id, index_val.value_id, value_word, location
+                ));
+            }
+            InferType::Map(_, _) => {
+                let key_word = self.pack_word(&index_val)?;
+                self.module.declare("declare void @map_set(i8*, i8*, i8*)");
+                self.emit(&format!(
+                    "  call void @map_set(i8* {}, i8* {}, i8* {})",
+                    collection_val.value_id, key_word, value_word
+                ));
+            }
+            other => {
+                let type_name = self.type_to_string(other);
+                return Err(CodegenError::InvalidOperation(format!("Cannot assign an element of {}", type_name)));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::compile_source;
+
+    #[test]
+    fn test_assignment_to_fields_and_elements() {
+        let ir = compile_source(
+            "object point\n    x: i64\nobject line\n    start: point\nfn f(l: line)\n    l.start.x is 2\n    xs is [1.5]\n    xs(0) is 2.5\n    cfg is {\"host\": 1}\n    cfg[\"host\"] is 2\n"
+        ).unwrap();
+        // The nested object is loaded, then its field written in place
+        assert!(ir.contains("load %point*, %point** %"));
+        assert!(ir.contains("getelementptr inbounds %point, %point* %"));
+        assert!(ir.contains("store i64 2, i64* %"));
+        // Elements travel as words: floats by their bits
+        assert!(ir.contains("bitcast double 0x4004000000000000 to i64"));
+        assert!(ir.contains("call void @list_set(i8* %"));
+        assert!(ir.contains("call i8* @map_new(i32 1)"));
+        assert!(ir.contains("call void @map_set(i8* %"));
+    }
+
+    #[test]
+    fn test_element_reads_go_through_the_runtime() {
+        let ir = compile_source(
+            "fn f(xs: (i64), flags: (bool), ages: (string: f64), word: string) -> i64\n    a is ages(\"ann\")\n    c is word[0]\n    if flags(1)\n        return 0\n    return xs(2)\n"
+        ).unwrap();
+        // Out-of-bounds reports point at the collection in the source
+        assert!(ir.contains("c\"test:6:12\\00\""));
+        assert!(ir.contains("call i8* @list_get(i8* %"));
+        assert!(ir.contains("call i8* @map_get(i8* %"));
+        assert!(ir.contains("call %string* @string_at(%string* %"));
+        // Words are unpacked back into the element type
+        assert!(ir.contains("to double\n"));
+        assert!(ir.contains("trunc i64 %"));
+    }
+}
+
@@ -1,7 +1,12 @@
 use crate::ast::Program;
+use crate::codegen::closures::is_direct_function;
+use crate::codegen::entry::is_definition;
+use crate::consteval::{program_constants, ConstValue};
 use crate::resolver::types::InferType;
 use crate::codegen::types::{LLVMType, infer_to_llvm_type};
-use std::collections::HashMap;
+use crate::codegen::module::ModuleBuilder;
+use crate::tables::TypeTable;
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct LLVMValue {
@@ -25,88 +30,293 @@ pub enum CodegenError {
     NotCallable(InferType),
 }
 
+/// How a name is bound while compiling
+#[derive(Debug, Clone)]
+pub enum Binding {
+    /// Used as is: functions, `self`, the current iteration value
+    Value(LLVMValue),
+    /// A variable in memory; `value_id` is the address of a slot holding a
+    /// value of the given type
+    Slot(LLVMValue),
+}
+
+/// Names visible at the current point, innermost scope last
 #[derive(Debug, Clone)]
 pub struct SymbolTable {
-    variables: HashMap<String, LLVMValue>,
-    parent: Option<Box<SymbolTable>>,
+    /// The first scope holds functions and module globals
+    scopes: Vec<HashMap<String, Binding>>,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Binding {
+    /// The bound value, or for a slot, its address and element type
+    pub fn value(&self) -> &LLVMValue {
+        match self {
+            Binding::Value(value) | Binding::Slot(value) => value,
+        }
+    }
 }
 
 impl SymbolTable {
     pub fn new() -> Self {
         Self {
-            variables: HashMap::new(),
-            parent: None,
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
         }
     }
 
+    /// Set aside the enclosing function's scopes, so a function body only
+    /// sees module-level names and its own
+    pub fn enter_function(&mut self) -> Vec<HashMap<String, Binding>> {
+        let outer = self.scopes.split_off(1);
+        self.push_scope();
+        outer
+    }
+
+    pub fn exit_function(&mut self, outer: Vec<HashMap<String, Binding>>) {
+        self.scopes.truncate(1);
+        self.scopes.extend(outer);
+    }
+
     pub fn define_variable(&mut self, name: String, value: LLVMValue) {
-        self.variables.insert(name, value);
+        self.define(name, Binding::Value(value));
     }
 
-    pub fn lookup_variable(&self, name: &str) -> Option<LLVMValue> {
-        self.variables.get(name).cloned().or_else(|| {
-            self.parent
-                .as_ref()
-                .and_then(|p| p.lookup_variable(name))
-        })
+    /// Bind `name` to memory at `slot.value_id` holding a `slot.llvm_type`
+    pub fn define_slot(&mut self, name: String, slot: LLVMValue) {
+        self.define(name, Binding::Slot(slot));
+    }
+
+    /// Bind a function or global, visible from every function
+    pub fn define_module_level(&mut self, name: String, binding: Binding) {
+        self.scopes[0].insert(name, binding);
+    }
+
+    fn define(&mut self, name: String, binding: Binding) {
+        self.scopes.last_mut().unwrap().insert(name, binding);
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// A binding of the current function, skipping module-level names
+    pub fn lookup_local(&self, name: &str) -> Option<&Binding> {
+        self.scopes[1..].iter().rev().find_map(|scope| scope.get(name))
     }
+
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.lookup(name).is_some()
+    }
+}
+
+/// State of the enclosing function, set aside while another one is compiled
+pub(crate) struct FunctionFrame {
+    output: String,
+    entry_allocas: String,
+    block: String,
+    module_level: bool,
+    return_type: Option<InferType>,
+    loop_targets: Vec<(usize, usize)>,
+    scopes: Vec<HashMap<String, Binding>>,
 }
 
 pub struct LLVMCodegen {
+    /// Instructions of the function being compiled
     pub output: String,
     pub symbols: SymbolTable,
-    pub global_strings: Vec<String>,
+    pub(crate) module: ModuleBuilder,
     temp_counter: usize,
     label_counter: usize,
-    object_types: HashMap<String, InferType>,
-    current_bb: usize,
+    pub(crate) object_types: HashMap<String, InferType>,
+    /// Fields of each object layout, in declaration order
+    pub(crate) object_fields: HashMap<String, Vec<crate::ast::Field>>,
+    enum_types: HashMap<String, InferType>,
+    trait_types: HashMap<String, InferType>,
+    pub(crate) current_return_type: Option<InferType>,
+    /// Generic definitions, compiled once per distinct set of type arguments
+    pub(crate) generic_functions: HashMap<String, crate::ast::Stmt>,
+    pub(crate) generic_objects: HashMap<String, crate::ast::Stmt>,
+    /// Concrete types for the type parameters of the specialization being compiled
+    pub(crate) type_bindings: HashMap<String, InferType>,
+    pub(crate) specializations: HashSet<String>,
+    /// Top-level constants, inlined at each use
+    pub(crate) constants: HashMap<String, ConstValue>,
+    /// `alloca`s of the function being compiled, hoisted into its entry block
+    entry_allocas: String,
+    /// Whether new variables are module globals (top-level code) or locals
+    pub(crate) module_level: bool,
+    /// `(continue, break)` labels of the enclosing loops, innermost last
+    pub(crate) loop_targets: Vec<(usize, usize)>,
+    /// Label of the basic block instructions are currently appended to
+    pub(crate) current_block: String,
+    /// Lambdas compiled so far, numbering their symbols
+    pub(crate) lambda_count: usize,
+    /// Thunks already emitted for top-level functions used as values
+    pub(crate) thunks: HashSet<String>,
+    /// Formatters emitted so far for list and map elements, by element type
+    pub(crate) formatters: HashMap<String, String>,
+    /// Formatters emitted so far for enum and result values, by type
+    pub(crate) value_formatters: HashMap<String, String>,
+    /// Types the resolver solved, for what the source leaves unannotated
+    pub(crate) types: TypeTable,
+}
+
+/// Size and alignment of a struct with members of the given layouts, padded
+/// to its alignment like an LLVM struct
+fn struct_layout(members: impl IntoIterator<Item = (usize, usize)>) -> (usize, usize) {
+    let (mut size, mut align) = (0usize, 1);
+    for (member_size, member_align) in members {
+        size = size.next_multiple_of(member_align) + member_size;
+        align = align.max(member_align);
+    }
+    (size.next_multiple_of(align), align)
 }
 
 impl LLVMCodegen {
     pub fn new(module_name: String) -> Self {
         Self {
-            output: format!("; ModuleID = '{}'\n", module_name),
+            output: String::new(),
             symbols: SymbolTable::new(),
-            global_strings: Vec::new(),
+            module: ModuleBuilder::new(module_name),
             temp_counter: 0,
             label_counter: 0,
             object_types: HashMap::new(),
-            current_bb: 0,
+            object_fields: HashMap::new(),
+            enum_types: HashMap::new(),
+            trait_types: HashMap::new(),
+            current_return_type: None,
+            generic_functions: HashMap::new(),
+            generic_objects: HashMap::new(),
+            type_bindings: HashMap::new(),
+            specializations: HashSet::new(),
+            constants: HashMap::new(),
+            entry_allocas: String::new(),
+            module_level: false,
+            loop_targets: Vec::new(),
+            current_block: "entry".to_string(),
+            lambda_count: 0,
+            thunks: HashSet::new(),
+            formatters: HashMap::new(),
+            value_formatters: HashMap::new(),
+            types: TypeTable::new(),
         }
     }
 
+    /// Compile with the resolver's solved types filling in unannotated fields
+    pub fn with_types(mut self, types: TypeTable) -> Self {
+        self.types = types;
+        self
+    }
+
     pub fn compile_program(&mut self, program: &Program) -> Result<String, CodegenError> {
-        println!("Compiling program...");
+        let program = &self.with_inferred_signatures(program);
+        // The runtime's string header: byte length, then the NUL-terminated bytes
+        self.module.define_type("%string = type { i64, [0 x i8] }".to_string());
         self.emit_object_structs(program)?;
+        self.emit_vtables(program)?;
+        self.emit_generic_instances(program)?;
+        self.emit_constants(program)?;
+        self.declare_functions(program);
+        // Top-level code first, so functions see the globals it defines
+        let has_init = self.compile_init(&Self::top_level_code(program))?;
         for stmt in &program.statements {
-            println!("Compiling statement: {:?}", stmt);
-            self.compile_statement(stmt)?;
+            if is_definition(stmt) {
+                self.compile_statement(stmt)?;
+            }
         }
-        let mut final_ir = self.output.clone();
-        for g_str in &self.global_strings {
-            final_ir.push_str(g_str);
-            final_ir.push('\n');
+        self.emit_entry_point(has_init)?;
+        Ok(self.module.finish())
+    }
+
+    /// Fold the program's constants; string constants become constant globals
+    pub fn emit_constants(&mut self, program: &Program) -> Result<(), CodegenError> {
+        let constants = program_constants(program).map_err(|errors| {
+            CodegenError::InvalidOperation(errors[0].message.clone())
+        })?;
+        let mut names: Vec<_> = constants.keys().cloned().collect();
+        names.sort();
+        for name in names {
+            if let ConstValue::String(s) = &constants[&name] {
+                self.module.string_constant(&format!("@{}", name), s);
+            }
         }
-        println!("Finished compiling program.");
-        Ok(final_ir)
+        self.constants = constants;
+        Ok(())
     }
 
     pub fn emit_object_structs(&mut self, program: &Program) -> Result<(), CodegenError> {
+        // Trait signatures first so object methods and parameters can name them
         for stmt in &program.statements {
-            if let crate::ast::StmtKind::Object { name, fields, .. } = &stmt.kind {
+            if let crate::ast::StmtKind::Trait { name, methods } = &stmt.kind {
+                let trait_type = self.trait_type_from_ast(name, methods);
+                self.trait_types.insert(name.clone(), trait_type);
+            }
+            // Generic objects only get layouts once their type arguments are known
+            if let crate::ast::StmtKind::Object { name, type_params, .. } = &stmt.kind {
+                if !type_params.is_empty() {
+                    self.generic_objects.insert(name.clone(), stmt.clone());
+                }
+            }
+        }
+
+        for stmt in &program.statements {
+            if let crate::ast::StmtKind::Object { name, type_params, fields, methods, .. } = &stmt.kind {
+                if !type_params.is_empty() {
+                    continue;
+                }
                 let mut field_types = Vec::new();
+                let mut field_infer_types = HashMap::new();
                 for field in fields {
-                    let field_type = self.ast_type_to_infer_type(&field.type_);
+                    let field_type = self.field_infer_type(name, field);
                     field_types.push(infer_to_llvm_type(&field_type));
+                    field_infer_types.insert(field.name.clone(), field_type);
                 }
-                self.emit(&format!("%{} = type {{ {} }}", name, field_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")));
+                let method_types = methods.iter()
+                    .map(|m| (m.name.clone(), self.method_infer_type(m, &self.type_bindings)))
+                    .collect();
+                self.object_fields.insert(name.clone(), fields.clone());
+                self.object_types.insert(name.clone(), InferType::Object {
+                    name: name.clone(),
+                    fields: field_infer_types,
+                    methods: method_types,
+                    is_actor: false,
+                    is_store: false,
+                });
+                self.module.define_type(format!("%{} = type {{ {} }}", name, field_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")));
             } else if let crate::ast::StmtKind::Store { name, fields, .. } = &stmt.kind {
                 let mut field_types = Vec::new();
                 for field in fields {
-                    let field_type = self.ast_type_to_infer_type(&field.type_);
+                    let field_type = self.field_infer_type(name, field);
                     field_types.push(infer_to_llvm_type(&field_type));
                 }
-                self.emit(&format!("%{} = type {{ {} }}", name, field_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")));
+                self.module.define_type(format!("%{} = type {{ {} }}", name, field_types.iter().map(|t| t.to_string()).collect::<Vec<_>>().join(", ")));
+            } else if let crate::ast::StmtKind::Enum { name, variants } = &stmt.kind {
+                // Tagged union: i32 tag followed by storage for the largest payload
+                let variant_types = variants.iter()
+                    .map(|variant| (
+                        variant.name.clone(),
+                        variant.payload.iter().map(|ty| self.ast_type_to_infer_type(ty)).collect(),
+                    ))
+                    .collect();
+                let enum_type = InferType::Enum { name: name.clone(), variants: variant_types };
+                let storage_type = self.enum_storage_type(&enum_type);
+                self.enum_types.insert(name.clone(), enum_type);
+                self.module.define_type(format!("%{} = type {{ i32, {} }}", name, storage_type));
             }
         }
         Ok(())
@@ -117,9 +327,64 @@ impl LLVMCodegen {
         self.output.push('\n');
     }
 
-    pub(crate) fn next_temp(&mut self) -> usize {
+    /// A fresh named temporary; named rather than numbered so values need not
+    /// be sequential within each function
+    pub(crate) fn next_temp(&mut self) -> String {
         self.temp_counter += 1;
-        self.temp_counter
+        format!("t{}", self.temp_counter)
+    }
+
+    /// Start a function whose `define ... {` line is `header`; its body is
+    /// built in a fresh buffer with a fresh scope
+    pub(crate) fn begin_function(&mut self, header: &str, return_type: InferType) -> FunctionFrame {
+        let frame = FunctionFrame {
+            output: std::mem::take(&mut self.output),
+            entry_allocas: std::mem::take(&mut self.entry_allocas),
+            block: std::mem::replace(&mut self.current_block, "entry".to_string()),
+            module_level: std::mem::replace(&mut self.module_level, false),
+            return_type: self.current_return_type.replace(return_type),
+            loop_targets: std::mem::take(&mut self.loop_targets),
+            scopes: self.symbols.enter_function(),
+        };
+        self.emit(header);
+        self.emit("entry:");
+        frame
+    }
+
+    /// Close the function begun by `begin_function`, hoist its allocas into
+    /// the entry block and file it with the module
+    pub(crate) fn finish_function(&mut self, frame: FunctionFrame) {
+        self.emit("}");
+        let mut function = std::mem::replace(&mut self.output, frame.output);
+        let allocas = std::mem::replace(&mut self.entry_allocas, frame.entry_allocas);
+        let entry_end = function.find("entry:\n").map_or(0, |at| at + "entry:\n".len());
+        function.insert_str(entry_end, &allocas);
+        self.current_block = frame.block;
+        self.module_level = frame.module_level;
+        self.current_return_type = frame.return_type;
+        self.loop_targets = frame.loop_targets;
+        self.symbols.exit_function(frame.scopes);
+        self.module.add_function(function);
+    }
+
+    /// Stack slot for a `llvm_type`, allocated once in the entry block
+    pub(crate) fn alloca(&mut self, llvm_type: &LLVMType) -> String {
+        let slot = self.next_temp();
+        self.entry_allocas.push_str(&format!("  %{} = alloca {}\n", slot, llvm_type));
+        slot
+    }
+
+    /// Emit a terminator; whatever follows lands in a fresh, unreachable block
+    pub(crate) fn terminate(&mut self, instruction: &str) {
+        self.emit(instruction);
+        let label = self.next_label();
+        self.start_block(label);
+    }
+
+    /// Begin the block labelled `L<label>`
+    pub(crate) fn start_block(&mut self, label: usize) {
+        self.emit(&format!("L{}:", label));
+        self.current_block = format!("L{}", label);
     }
 
     pub(crate) fn next_label(&mut self) -> usize {
@@ -128,37 +393,123 @@ impl LLVMCodegen {
     }
     
 pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> InferType {
+        self.ast_type_with_bindings(ast_type, &self.type_bindings)
+    }
+
+    /// Convert an annotation, reading type parameters from `bindings`
+    pub(crate) fn ast_type_with_bindings(&self, ast_type: &crate::ast::Type, bindings: &HashMap<String, InferType>) -> InferType {
         // This is a simplified conversion
         match ast_type {
-            crate::ast::Type::I32 => InferType::Int,
+            crate::ast::Type::I8 => InferType::I8,
+            crate::ast::Type::I16 => InferType::I16,
+            crate::ast::Type::I32 => InferType::I32,
+            crate::ast::Type::I64 => InferType::Int,
+            crate::ast::Type::F32 => InferType::F32,
             crate::ast::Type::F64 => InferType::Float,
             crate::ast::Type::String => InferType::String,
             crate::ast::Type::Bool => InferType::Bool,
+            crate::ast::Type::Result(ok, err) => InferType::Result(
+                Box::new(self.ast_type_with_bindings(ok, bindings)),
+                Box::new(self.ast_type_with_bindings(err, bindings)),
+            ),
+            crate::ast::Type::List(element) => InferType::List(Box::new(self.ast_type_with_bindings(element, bindings))),
+            crate::ast::Type::Map(key, value) => InferType::Map(
+                Box::new(self.ast_type_with_bindings(key, bindings)),
+                Box::new(self.ast_type_with_bindings(value, bindings)),
+            ),
+            crate::ast::Type::Param(name) => bindings.get(name).cloned().unwrap_or(InferType::Unknown),
+            crate::ast::Type::Function { params, return_type } => InferType::Function {
+                params: params.iter().map(|param| self.ast_type_with_bindings(param, bindings)).collect(),
+                return_type: Box::new(self.ast_type_with_bindings(return_type, bindings)),
+                effects: Default::default(),
+            },
+            crate::ast::Type::Generic { name, args } => {
+                let args = args.iter().map(|arg| self.ast_type_with_bindings(arg, bindings)).collect::<Vec<_>>();
+                self.generic_object_type(name, &args).unwrap_or(InferType::Unknown)
+            }
+            crate::ast::Type::Object { name, .. } | crate::ast::Type::Enum { name, .. } | crate::ast::Type::Trait { name } => {
+                self.lookup_enum_type(name)
+                    .or_else(|| self.lookup_trait_type(name))
+                    .or_else(|| self.lookup_object_type(name))
+                    .unwrap_or(InferType::Unknown)
+            }
             _ => InferType::Unknown,
         }
     }
 
-    
+    /// Type of an object or store field. An unannotated field takes the type
+    /// the resolver solved for its default, or else for the field itself
+    fn field_infer_type(&self, owner: &str, field: &crate::ast::Field) -> InferType {
+        match (self.ast_type_to_infer_type(&field.type_), &field.default_value) {
+            (InferType::Unknown, Some(default)) => {
+                let solved = self.types.type_of(default.id);
+                self.ast_type_to_infer_type(&solved)
+            }
+            (InferType::Unknown, None) => match self.types.field_type(owner, &field.name) {
+                Some(solved) => self.ast_type_to_infer_type(solved),
+                None => InferType::Unknown,
+            },
+            (field_type, _) => field_type,
+        }
+    }
+
+    /// Signature of an object method, excluding the receiver
+    pub(crate) fn method_infer_type(&self, method: &crate::ast::ObjectMethod, bindings: &HashMap<String, InferType>) -> InferType {
+        let params = method.params.iter().map(|p| self.ast_type_with_bindings(&p.type_, bindings)).collect();
+        let return_type = method.return_type.as_ref()
+            .map(|ty| self.ast_type_with_bindings(ty, bindings))
+            .unwrap_or(InferType::Unit);
+        InferType::Function {
+            params,
+            return_type: Box::new(return_type),
+            effects: Default::default(),
+        }
+    }
 
-    pub(crate) fn get_type_size(&self, ty: &InferType) -> usize {
+    /// Size and alignment in bytes of a value of `ty` in the x86-64 data layout
+    pub(crate) fn type_layout(&self, ty: &InferType) -> (usize, usize) {
         match ty {
-            InferType::Int => 8,
-            InferType::Float => 8,
-            InferType::Bool => 1,
-            InferType::String | InferType::List(_) | InferType::Map(_, _) => 8, // Pointer size
-            InferType::Object { fields, .. } => {
-                fields.values().map(|f| self.get_type_size(f)).sum()
-            }
-            _ => 8, // Default pointer size
+            InferType::Unit => (0, 1),
+            InferType::Bool | InferType::I8 => (1, 1),
+            InferType::I16 => (2, 2),
+            InferType::I32 | InferType::F32 => (4, 4),
+            InferType::Int | InferType::Float => (8, 8),
+            // Code and environment pointers; data and vtable pointers
+            InferType::Function { .. } | InferType::Trait { .. } => (16, 8),
+            InferType::Result(ok, err) => struct_layout([(1, 1), self.type_layout(ok), self.type_layout(err)]),
+            InferType::Enum { .. } => struct_layout([(4, 4), self.enum_payload_layout(ty)]),
+            // Strings, collections and objects are pointers
+            _ => (8, 8),
         }
     }
 
+    /// Size and alignment of the storage an enum's largest payload needs
+    pub(crate) fn enum_payload_layout(&self, ty: &InferType) -> (usize, usize) {
+        let InferType::Enum { variants, .. } = ty else {
+            return (0, 1);
+        };
+        variants.iter()
+            .map(|(_, payload)| struct_layout(payload.iter().map(|p| self.type_layout(p))))
+            .fold((0, 1), |(size, align), (payload_size, payload_align)| (size.max(payload_size), align.max(payload_align)))
+    }
+
+    /// Payload storage of an enum: words of its strictest alignment, so every
+    /// payload struct fits when the storage is cast to it
+    pub(crate) fn enum_storage_type(&self, ty: &InferType) -> String {
+        let (size, align) = self.enum_payload_layout(ty);
+        format!("[{} x i{}]", size.div_ceil(align), align * 8)
+    }
+
     pub(crate) fn type_to_string(&self, ty: &InferType) -> String {
         match ty {
             InferType::Unit => "unit".to_string(),
             InferType::Bool => "bool".to_string(),
             InferType::Int => "int".to_string(),
             InferType::Float => "float".to_string(),
+            InferType::I8 => "i8".to_string(),
+            InferType::I16 => "i16".to_string(),
+            InferType::I32 => "i32".to_string(),
+            InferType::F32 => "f32".to_string(),
             InferType::String => "string".to_string(),
             InferType::List(inner) => format!("List[{}]", self.type_to_string(inner)),
             InferType::Map(key, value) => format!("Map[{}, {}]", 
@@ -168,6 +519,8 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
                 format!("({}) -> {}", param_strs.join(", "), self.type_to_string(return_type))
             },
             InferType::Object { name, .. } => format!("object {}", name),
+            InferType::Enum { name, .. } => format!("enum {}", name),
+            InferType::Trait { name, .. } => format!("trait {}", name),
             InferType::Store { name, .. } => format!("store {}", name),
             InferType::Actor { name, .. } => format!("actor {}", name),
             InferType::Result(ok, err) => format!("Result[{}, {}]", 
@@ -182,56 +535,278 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
         self.object_types.get(name).cloned()
     }
 
+    pub(crate) fn lookup_enum_type(&self, name: &str) -> Option<InferType> {
+        self.enum_types.get(name).cloned()
+    }
+
+    pub(crate) fn lookup_trait_type(&self, name: &str) -> Option<InferType> {
+        self.trait_types.get(name).cloned()
+    }
+
+    /// Build `enum_name.variant(args)` as a tag store plus payload stores through a stack slot
+    pub fn compile_enum_variant(&mut self, enum_type: InferType, variant: &str, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
+        let (tag, payload) = match enum_type.enum_variant(variant) {
+            Some((tag, payload)) => (tag, payload.to_vec()),
+            None => return Err(CodegenError::InvalidOperation(format!("Unknown enum variant {}", variant))),
+        };
+        if payload.len() != args.len() {
+            return Err(CodegenError::InvalidOperation(format!(
+                "Variant {} expects {} values, got {}", variant, payload.len(), args.len()
+            )));
+        }
+
+        let enum_llvm_type = infer_to_llvm_type(&enum_type);
+        let slot = self.alloca(&enum_llvm_type);
+        let tag_ptr = self.next_temp();
+        self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 0", tag_ptr, enum_llvm_type, enum_llvm_type, slot));
+        self.emit(&format!("  store i32 {}, i32* %{}", tag, tag_ptr));
+
+        if !payload.is_empty() {
+            let payload_llvm_type = LLVMType::Struct(payload.iter().map(infer_to_llvm_type).collect());
+            let storage_ptr = self.next_temp();
+            self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 1", storage_ptr, enum_llvm_type, enum_llvm_type, slot));
+            let payload_ptr = self.next_temp();
+            let storage_type = self.enum_storage_type(&enum_type);
+            self.emit(&format!("  %{} = bitcast {}* %{} to {}*", payload_ptr, storage_type, storage_ptr, payload_llvm_type));
+
+            for (index, (arg, payload_type)) in args.iter().zip(&payload).enumerate() {
+                let arg_val = self.compile_expression(arg)?;
+                let arg_val = if payload_type.is_numeric() && arg_val.type_info.is_numeric() {
+                    self.convert_numeric(arg_val, payload_type)?
+                } else {
+                    arg_val
+                };
+                let field_ptr = self.next_temp();
+                self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}", field_ptr, payload_llvm_type, payload_llvm_type, payload_ptr, index));
+                self.emit(&format!("  store {} {}, {}* %{}", arg_val.llvm_type, arg_val.value_id, arg_val.llvm_type, field_ptr));
+            }
+        }
+
+        let result_temp = self.next_temp();
+        self.emit(&format!("  %{} = load {}, {}* %{}", result_temp, enum_llvm_type, enum_llvm_type, slot));
+
+        Ok(LLVMValue {
+            type_info: enum_type,
+            llvm_type: enum_llvm_type,
+            value_id: format!("%{}", result_temp),
+        })
+    }
+
+    /// Build the error side of the enclosing function's `T ! E` return value
+    pub fn compile_err_value(&mut self, payload: &crate::ast::Expr) -> Result<LLVMValue, CodegenError> {
+        let result_type = match &self.current_return_type {
+            Some(ty @ InferType::Result(..)) => ty.clone(),
+            _ => return Err(CodegenError::InvalidOperation("err used outside a function returning a result".to_string())),
+        };
+        let payload_val = self.compile_expression(payload)?;
+        let result_llvm_type = infer_to_llvm_type(&result_type);
+
+        let flagged = self.next_temp();
+        self.emit(&format!("  %{} = insertvalue {} undef, i1 true, 0", flagged, result_llvm_type));
+        let result_temp = self.next_temp();
+        self.emit(&format!("  %{} = insertvalue {} %{}, {} {}, 2", result_temp, result_llvm_type, flagged, payload_val.llvm_type, payload_val.value_id));
+
+        Ok(LLVMValue {
+            type_info: result_type,
+            llvm_type: result_llvm_type,
+            value_id: format!("%{}", result_temp),
+        })
+    }
+
+    /// Fit a returned value to the function's return type: numerics widen, and
+    /// a plain value becomes the success side of a `T ! E` result
+    pub(crate) fn coerce_return(&mut self, value: LLVMValue) -> Result<LLVMValue, CodegenError> {
+        match self.current_return_type.clone() {
+            Some(return_type) if return_type.is_numeric() && value.type_info.is_numeric() => {
+                self.convert_numeric(value, &return_type)
+            }
+            Some(InferType::Result(ok_type, err_type)) if !matches!(value.type_info, InferType::Result(..)) => {
+                let value = if ok_type.is_numeric() && value.type_info.is_numeric() {
+                    self.convert_numeric(value, &ok_type)?
+                } else {
+                    value
+                };
+                let result_type = InferType::Result(ok_type, err_type);
+                let result_llvm_type = infer_to_llvm_type(&result_type);
+                let flagged = self.next_temp();
+                self.emit(&format!("  %{} = insertvalue {} undef, i1 false, 0", flagged, result_llvm_type));
+                let result_temp = self.next_temp();
+                self.emit(&format!("  %{} = insertvalue {} %{}, {} {}, 1", result_temp, result_llvm_type, flagged, value.llvm_type, value.value_id));
+                Ok(LLVMValue {
+                    type_info: result_type,
+                    llvm_type: result_llvm_type,
+                    value_id: format!("%{}", result_temp),
+                })
+            }
+            _ => Ok(value),
+        }
+    }
+
+    /// Call `type_name`'s constructor; fields past the given arguments take
+    /// their defaults, evaluated where the object is made
     pub fn compile_object_instantiation(&mut self, type_name: &str, obj_type: InferType, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
-        let struct_type = infer_to_llvm_type(&obj_type);
-        let size = self.get_type_size(&obj_type);
+        let fields = self.object_fields.get(type_name).cloned().unwrap_or_default();
+        let values = self.compile_field_values(type_name, &fields, args)?;
+        self.call_constructor(type_name, obj_type, values)
+    }
 
-        let malloc_temp = self.next_temp();
-        self.emit(&format!("  %{} = call i8* @malloc(i64 {})", malloc_temp, size));
-        
-        let ptr_temp = self.next_temp();
-        self.emit(&format!("  %{} = bitcast i8* %{} to {}*", ptr_temp, malloc_temp, struct_type));
+    /// The value of every field in declaration order: the given arguments,
+    /// then the defaults of the fields they leave out
+    pub(crate) fn compile_field_values(&mut self, type_name: &str, fields: &[crate::ast::Field], args: &[crate::ast::Expr]) -> Result<Vec<LLVMValue>, CodegenError> {
+        if args.len() > fields.len() {
+            return Err(CodegenError::InvalidOperation(format!(
+                "{} has {} fields, got {} values", type_name, fields.len(), args.len()
+            )));
+        }
+        let mut values = Vec::new();
+        for (index, field) in fields.iter().enumerate() {
+            let value = match (args.get(index), &field.default_value) {
+                (Some(expr), _) | (None, Some(expr)) => self.compile_expression(expr)?,
+                (None, None) => return Err(CodegenError::InvalidOperation(format!(
+                    "Missing value for field {} of {}", field.name, type_name
+                ))),
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// Call `@T__make` with one value per field
+    pub(crate) fn call_constructor(&mut self, type_name: &str, obj_type: InferType, values: Vec<LLVMValue>) -> Result<LLVMValue, CodegenError> {
+        let fields = self.object_fields.get(type_name).cloned().unwrap_or_default();
+        let field_types = match &obj_type {
+            InferType::Object { fields, .. } => fields.clone(),
+            _ => HashMap::new(),
+        };
 
-        // Call the 'make' constructor
-        let make_method_name = format!("_ZN{}{}{}{}", type_name.len(), type_name, 4, "make");
         let mut arg_vals = Vec::new();
-        for arg in args {
-            arg_vals.push(self.compile_expression(arg)?);
+        for (field, value) in fields.iter().zip(values) {
+            let value = match field_types.get(&field.name) {
+                Some(field_type) if field_type.is_numeric() && value.type_info.is_numeric() => {
+                    self.convert_numeric(value, field_type)?
+                }
+                _ => value,
+            };
+            arg_vals.push(value);
         }
+
+        let struct_type = LLVMType::NamedStruct(type_name.to_string());
         let call_args = arg_vals.iter()
             .map(|v| format!("{} {}", v.llvm_type, v.value_id))
             .collect::<Vec<String>>()
             .join(", ");
-        self.emit(&format!("  call void @{}({}* %{}, {})", make_method_name, struct_type, ptr_temp, call_args));
+        let result_temp = self.next_temp();
+        self.emit(&format!("  %{} = call {}* @{}({})", result_temp, struct_type, self.method_symbol(type_name, "make"), call_args));
 
         Ok(LLVMValue {
             type_info: obj_type,
             llvm_type: LLVMType::Pointer(Box::new(struct_type)),
-            value_id: format!("%{}", ptr_temp),
+            value_id: format!("%{}", result_temp),
         })
     }
 
+    /// Emit `@T__make`, which takes every field in declaration order, allocates
+    /// the object through the runtime and then runs `initializer`, the object's
+    /// own `make`, if it has one
+    pub(crate) fn emit_constructor(&mut self, type_name: &str, initializer: Option<&str>) -> Result<(), CodegenError> {
+        let object_type = self.lookup_object_type(type_name)
+            .ok_or_else(|| CodegenError::UndefinedVariable(type_name.to_string()))?;
+        let fields = self.object_fields.get(type_name).cloned().unwrap_or_default();
+        let struct_type = LLVMType::NamedStruct(type_name.to_string());
+        let signature = match &object_type {
+            InferType::Object { fields: field_types, .. } => fields.iter()
+                .filter_map(|field| field_types.get(&field.name)
+                    .map(|ty| format!("{} %{}.arg", infer_to_llvm_type(ty), field.name)))
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        let frame = self.begin_function(
+            &format!("define {}* @{}({}) {{", struct_type, self.method_symbol(type_name, "make"), signature.join(", ")),
+            object_type.clone(),
+        );
+
+        let size = self.size_of(&struct_type);
+        self.module.declare("declare i8* @coral_alloc(i64)");
+        let raw = self.next_temp();
+        self.emit(&format!("  %{} = call i8* @coral_alloc(i64 {})", raw, size));
+        let object = self.next_temp();
+        self.emit(&format!("  %{} = bitcast i8* %{} to {}*", object, raw, struct_type));
+        let this = LLVMValue {
+            type_info: object_type,
+            llvm_type: LLVMType::Pointer(Box::new(struct_type)),
+            value_id: format!("%{}", object),
+        };
+
+        for field in &fields {
+            let field_ptr = self.field_pointer(&this, &field.name)?;
+            self.emit(&format!("  store {} %{}.arg, {}* {}", field_ptr.llvm_type, field.name, field_ptr.llvm_type, field_ptr.value_id));
+        }
+        if let Some(initializer) = initializer {
+            self.emit(&format!("  call void @{}({} {})", initializer, this.llvm_type, this.value_id));
+        }
+        self.emit(&format!("  ret {} {}", this.llvm_type, this.value_id));
+        self.finish_function(frame);
+        Ok(())
+    }
+
     pub fn compile_function_call(&mut self, callee: &crate::ast::Expr, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
-        let callee_val = self.compile_expression(callee)?;
+        // The builtin `log` formats whatever it is given
+        if matches!(&callee.kind, crate::ast::ExprKind::Identifier(name) if name == "log" && self.symbols.lookup(name).is_none()) {
+            return self.compile_log(args);
+        }
+        // Top-level functions are called directly; any other function value is a closure
+        let direct = match &callee.kind {
+            crate::ast::ExprKind::Identifier(name) => match self.symbols.lookup(name) {
+                Some(Binding::Value(function)) if is_direct_function(function) => Some(function.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let callee_val = match direct {
+            Some(function) => function,
+            None => self.compile_expression(callee)?,
+        };
         
+        let (param_types, return_type, result_llvm_type) = if let InferType::Function { params, return_type, .. } = &callee_val.type_info {
+            (params.clone(), return_type.as_ref().clone(), infer_to_llvm_type(return_type))
+        } else {
+            return Err(CodegenError::NotCallable(callee_val.type_info));
+        };
+
         let mut arg_vals = Vec::new();
-        for arg in args {
-            arg_vals.push(self.compile_expression(arg)?);
+        for (index, arg) in args.iter().enumerate() {
+            let arg_val = self.compile_expression(arg)?;
+            // Objects passed where a trait is expected become trait values
+            match param_types.get(index) {
+                Some(param_type @ InferType::Trait { .. }) => {
+                    arg_vals.push(self.compile_trait_coercion(arg_val, param_type)?);
+                }
+                // Narrower numerics widen to the parameter type
+                Some(param_type) if param_type.is_numeric() && arg_val.type_info.is_numeric() => {
+                    arg_vals.push(self.convert_numeric(arg_val, param_type)?);
+                }
+                _ => arg_vals.push(arg_val),
+            }
         }
 
-        let (return_type, result_llvm_type) = if let InferType::Function { return_type, .. } = &callee_val.type_info {
-            (return_type.as_ref().clone(), infer_to_llvm_type(return_type))
+        let mut call_args = arg_vals.iter()
+            .map(|v| format!("{} {}", v.llvm_type, v.value_id))
+            .collect::<Vec<String>>();
+        let code = if is_direct_function(&callee_val) {
+            callee_val.value_id
         } else {
-            return Err(CodegenError::NotCallable(callee_val.type_info));
+            let (code, env) = self.closure_parts(&callee_val);
+            call_args.insert(0, env);
+            code
         };
+        let call = format!("call {} {}({})", result_llvm_type, code, call_args.join(", "));
 
+        if result_llvm_type == LLVMType::Void {
+            self.emit(&format!("  {}", call));
+            return Ok(LLVMValue { type_info: InferType::Unit, llvm_type: LLVMType::Void, value_id: String::new() });
+        }
         let result_temp = self.next_temp();
-        let call_args = arg_vals.iter()
-            .map(|v| format!("{} {}", v.llvm_type, v.value_id))
-            .collect::<Vec<String>>()
-            .join(", ");
-
-        self.emit(&format!("  %{} = call {} {}({})", result_temp, result_llvm_type, callee_val.value_id, call_args));
+        self.emit(&format!("  %{} = {}", result_temp, call));
 
         Ok(LLVMValue {
             type_info: return_type,
@@ -242,6 +817,14 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
 
     pub fn compile_method_call(&mut self, object: &crate::ast::Expr, field: &str, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
         let object_val = self.compile_expression(object)?;
+        self.call_method(object_val, field, args)
+    }
+
+    /// Call `field` on an already compiled receiver
+    fn call_method(&mut self, object_val: LLVMValue, field: &str, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
+        if let InferType::Trait { .. } = &object_val.type_info {
+            return self.compile_dynamic_method_call(object_val, field, args);
+        }
         
         let mut arg_vals = Vec::new();
         // First argument is always the object itself
@@ -251,8 +834,13 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
             arg_vals.push(self.compile_expression(arg)?);
         }
 
-        let type_info_str = self.type_to_string(&object_val.type_info);
-        let mangled_name = format!("_ZN{}{}{}{}", type_info_str.len(), type_info_str, field.len(), field);
+        let mangled_name = match &object_val.type_info {
+            InferType::Object { name, .. } => self.method_symbol(name, field),
+            other => {
+                let type_info_str = self.type_to_string(other);
+                self.method_symbol(&type_info_str, field)
+            }
+        };
 
         let (return_type, result_llvm_type) = if let InferType::Object { methods, .. } = &object_val.type_info {
             if let Some(InferType::Function { return_type, .. }) = methods.get(field) {
@@ -264,13 +852,18 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
             (InferType::Unknown, LLVMType::Pointer(Box::new(LLVMType::Int(8))))
         };
 
-        let result_temp = self.next_temp();
         let call_args = arg_vals.iter()
             .map(|v| format!("{} {}", v.llvm_type, v.value_id))
             .collect::<Vec<String>>()
             .join(", ");
+        let call = format!("call {} @{}({})", result_llvm_type, mangled_name, call_args);
 
-        self.emit(&format!("  %{} = call {} @{}({})", result_temp, result_llvm_type, mangled_name, call_args));
+        if result_llvm_type == LLVMType::Void {
+            self.emit(&format!("  {}", call));
+            return Ok(LLVMValue { type_info: InferType::Unit, llvm_type: LLVMType::Void, value_id: String::new() });
+        }
+        let result_temp = self.next_temp();
+        self.emit(&format!("  %{} = {}", result_temp, call));
 
         Ok(LLVMValue {
             type_info: return_type,
@@ -281,48 +874,57 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
 
     pub fn compile_property_access(&mut self, object: &crate::ast::Expr, field: &str) -> Result<LLVMValue, CodegenError> {
         let object_val = self.compile_expression(object)?;
-        
-        let (field_index, field_type) = if let InferType::Object { fields, .. } = &object_val.type_info {
-            let mut index = 0;
-            let mut result = None;
-            for (name, ty) in fields.iter() {
-                if name == field {
-                    result = Some((index, ty.clone()));
-                    break;
-                }
-                index += 1;
+        // A member that is not a field calls the method of that name without arguments
+        if let InferType::Object { fields, methods, .. } = &object_val.type_info {
+            if !fields.contains_key(field) && methods.contains_key(field) {
+                return self.call_method(object_val, field, &[]);
             }
-            if let Some(r) = result {
-                r
-            } else {
-                return Err(CodegenError::InvalidOperation(format!("Field {} not found in object", field)));
-            }
-        } else {
-            return Err(CodegenError::InvalidOperation("Cannot access field on non-object type".to_string()));
-        };
-        
-        let field_ptr_temp = self.next_temp();
-        let object_llvm_type = infer_to_llvm_type(&object_val.type_info);
-
-        self.emit(&format!("  %{} = getelementptr inbounds {}, {} {}, i32 0, i32 {}", 
-            field_ptr_temp, 
-            object_llvm_type.to_string().strip_suffix('*').unwrap_or(&object_llvm_type.to_string()), 
-            object_llvm_type, 
-            object_val.value_id, 
-            field_index
-        ));
+        }
+        let field_ptr = self.field_pointer(&object_val, field)?;
 
         let result_temp = self.next_temp();
-        let field_llvm_type = infer_to_llvm_type(&field_type);
-        self.emit(&format!("  %{} = load {}, {}* %{}", result_temp, field_llvm_type, field_llvm_type, field_ptr_temp));
+        self.emit(&format!("  %{} = load {}, {}* {}", result_temp, field_ptr.llvm_type, field_ptr.llvm_type, field_ptr.value_id));
 
         Ok(LLVMValue {
-            type_info: field_type,
-            llvm_type: field_llvm_type,
             value_id: format!("%{}", result_temp),
+            ..field_ptr
+        })
+    }
+
+    /// Address of `field` inside the object `object_val` points to, typed like a slot
+    pub(crate) fn field_pointer(&mut self, object_val: &LLVMValue, field: &str) -> Result<LLVMValue, CodegenError> {
+        let (name, fields) = match &object_val.type_info {
+            InferType::Object { name, fields, .. } => (name, fields),
+            _ => return Err(CodegenError::InvalidOperation("Cannot access field on non-object type".to_string())),
+        };
+        // Layouts follow declaration order, which the field map does not keep
+        let index = self.object_fields.get(name)
+            .and_then(|order| order.iter().position(|f| f.name == field));
+        let (index, field_type) = match (index, fields.get(field)) {
+            (Some(index), Some(field_type)) => (index, field_type.clone()),
+            _ => return Err(CodegenError::InvalidOperation(format!("Field {} not found in object", field))),
+        };
+
+        let field_ptr_temp = self.next_temp();
+        self.emit(&format!("  %{} = getelementptr inbounds %{}, %{}* {}, i32 0, i32 {}",
+            field_ptr_temp, name, name, object_val.value_id, index
+        ));
+        Ok(LLVMValue {
+            llvm_type: infer_to_llvm_type(&field_type),
+            type_info: field_type,
+            value_id: format!("%{}", field_ptr_temp),
         })
     }
 
+    /// `i64` size of a type, computed by LLVM so padding is accounted for
+    pub(crate) fn size_of(&mut self, llvm_type: &LLVMType) -> String {
+        let end = self.next_temp();
+        self.emit(&format!("  %{} = getelementptr {}, {}* null, i32 1", end, llvm_type, llvm_type));
+        let size = self.next_temp();
+        self.emit(&format!("  %{} = ptrtoint {}* %{} to i64", size, llvm_type, end));
+        format!("%{}", size)
+    }
+
     pub fn compile_if_expression(&mut self, condition: &crate::ast::Expr, then_branch: &crate::ast::Expr, else_branch: Option<&crate::ast::Expr>) -> Result<LLVMValue, CodegenError> {
         let cond_val = self.compile_expression(condition)?;
 
@@ -330,14 +932,14 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
         let else_label = self.next_label();
         let merge_label = self.next_label();
 
-        self.emit(&format!("  br i1 {}, label %{}, label %{}", cond_val.value_id, then_label, else_label));
+        self.emit(&format!("  br i1 {}, label %L{}, label %L{}", cond_val.value_id, then_label, else_label));
 
-        self.emit(&format!("{}:", then_label));
+        self.start_block(then_label);
         let then_val = self.compile_expression(then_branch)?;
-        self.emit(&format!("  br label %{}", merge_label));
-        let then_bb = self.current_bb; // Basic block where `then_val` is valid
+        self.emit(&format!("  br label %L{}", merge_label));
+        let then_bb = self.current_block.clone(); // Basic block where `then_val` is valid
 
-        self.emit(&format!("{}:", else_label));
+        self.start_block(else_label);
         let else_val = if let Some(else_expr) = else_branch {
             self.compile_expression(else_expr)?
         } else {
@@ -348,10 +950,10 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
                 value_id: "".to_string(),
             }
         };
-        self.emit(&format!("  br label %{}", merge_label));
-        let else_bb = self.current_bb;
+        self.emit(&format!("  br label %L{}", merge_label));
+        let else_bb = self.current_block.clone();
 
-        self.emit(&format!("{}:", merge_label));
+        self.start_block(merge_label);
         
         let result_type = then_val.type_info.clone();
         let result_llvm_type = infer_to_llvm_type(&result_type);
@@ -379,22 +981,23 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
     }
 
     pub fn compile_list_literal(&mut self, elements: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
-        self.emit("declare i8* @list_new()");
-        self.emit("declare void @list_append(i8*, i8*)");
+        self.module.declare("declare i8* @list_new()");
+        self.module.declare("declare void @list_append(i8*, i8*)");
 
         let list_ptr = self.next_temp();
         self.emit(&format!("  %{} = call i8* @list_new()", list_ptr));
 
+        let mut element_type = InferType::Unknown;
         for element in elements {
             let element_val = self.compile_expression(element)?;
-            let element_ptr = self.next_temp();
-            self.emit(&format!("  %{} = bitcast {} {} to i8*", element_ptr, element_val.llvm_type, element_val.value_id));
-            self.emit(&format!("  call void @list_append(i8* %{}, i8* %{})", list_ptr, element_ptr));
+            let element_word = self.pack_word(&element_val)?;
+            self.emit(&format!("  call void @list_append(i8* %{}, i8* {})", list_ptr, element_word));
+            element_type = element_val.type_info;
         }
 
         Ok(LLVMValue {
-            type_info: InferType::List(Box::new(InferType::Unknown)), // Placeholder
-            llvm_type: LLVMType::Pointer(Box::new(LLVMType::NamedStruct("list".to_string()))),
+            type_info: InferType::List(Box::new(element_type)),
+            llvm_type: LLVMType::Pointer(Box::new(LLVMType::Int(8))),
             value_id: format!("%{}", list_ptr),
         })
     }
@@ -403,9 +1006,9 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
         let list_val = self.compile_expression(list)?;
         let element_val = self.compile_expression(element)?;
 
-        let element_ptr = self.next_temp();
-        self.emit(&format!("  %{} = bitcast {} {} to i8*", element_ptr, element_val.llvm_type, element_val.value_id));
-        self.emit(&format!("  call void @list_append({} {}, i8* %{})", list_val.llvm_type, list_val.value_id, element_ptr));
+        let element_word = self.pack_word(&element_val)?;
+        self.module.declare("declare void @list_append(i8*, i8*)");
+        self.emit(&format!("  call void @list_append(i8* {}, i8* {})", list_val.value_id, element_word));
 
         Ok(LLVMValue {
             type_info: InferType::Unit,
@@ -415,42 +1018,38 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
     }
 
     pub fn compile_map_literal(&mut self, elements: &[(crate::ast::Expr, crate::ast::Expr)]) -> Result<LLVMValue, CodegenError> {
-        self.emit("declare i8* @map_new()");
-        self.emit("declare void @map_insert(i8*, i8*, i8*)");
-
-        let map_ptr = self.next_temp();
-        self.emit(&format!("  %{} = call i8* @map_new()", map_ptr));
+        self.module.declare("declare i8* @map_new(i32)");
+        self.module.declare("declare void @map_set(i8*, i8*, i8*)");
 
+        let mut entries = Vec::new();
         for (key, value) in elements {
             let key_val = self.compile_expression(key)?;
             let value_val = self.compile_expression(value)?;
+            entries.push((key_val, value_val));
+        }
+        let (key_type, value_type) = entries.last()
+            .map(|(key, value)| (key.type_info.clone(), value.type_info.clone()))
+            .unwrap_or((InferType::Unknown, InferType::Unknown));
 
-            let key_ptr = self.next_temp();
-            self.emit(&format!("  %{} = bitcast {} {} to i8*", key_ptr, key_val.llvm_type, key_val.value_id));
-            let value_ptr = self.next_temp();
-            self.emit(&format!("  %{} = bitcast {} {} to i8*", value_ptr, value_val.llvm_type, value_val.value_id));
-
-            self.emit(&format!("  call void @map_insert(i8* %{}, i8* %{}, i8* %{})", map_ptr, key_ptr, value_ptr));
+        // The runtime compares string keys by contents
+        let map_ptr = self.next_temp();
+        self.emit(&format!("  %{} = call i8* @map_new(i32 {})", map_ptr, (key_type == InferType::String) as i32));
+        for (key_val, value_val) in &entries {
+            let key_word = self.pack_word(key_val)?;
+            let value_word = self.pack_word(value_val)?;
+            self.emit(&format!("  call void @map_set(i8* %{}, i8* {}, i8* {})", map_ptr, key_word, value_word));
         }
 
         Ok(LLVMValue {
-            type_info: InferType::Map(Box::new(InferType::Unknown), Box::new(InferType::Unknown)), // Placeholder
-            llvm_type: LLVMType::Pointer(Box::new(LLVMType::NamedStruct("map".to_string()))),
+            type_info: InferType::Map(Box::new(key_type), Box::new(value_type)),
+            llvm_type: LLVMType::Pointer(Box::new(LLVMType::Int(8))),
             value_id: format!("%{}", map_ptr),
         })
     }
 
     pub fn compile_map_insert(&mut self, map: &crate::ast::Expr, key: &crate::ast::Expr, value: &crate::ast::Expr) -> Result<LLVMValue, CodegenError> {
-        let map_val = self.compile_expression(map)?;
-        let key_val = self.compile_expression(key)?;
         let value_val = self.compile_expression(value)?;
-
-        let key_ptr = self.next_temp();
-        self.emit(&format!("  %{} = bitcast {} {} to i8*", key_ptr, key_val.llvm_type, key_val.value_id));
-        let value_ptr = self.next_temp();
-        self.emit(&format!("  %{} = bitcast {} {} to i8*", value_ptr, value_val.llvm_type, value_val.value_id));
-
-        self.emit(&format!("  call void @map_insert({} {}, i8* %{}, i8* %{})", map_val.llvm_type, map_val.value_id, key_ptr, value_ptr));
+        self.compile_element_store(map, key, value_val, &map.span)?;
 
         Ok(LLVMValue {
             type_info: InferType::Unit,
@@ -460,42 +1059,165 @@ pub(crate) fn ast_type_to_infer_type(&self, ast_type: &crate::ast::Type) -> Infe
     }
 
     pub fn compile_string_interpolation(&mut self, parts: &[crate::ast::StringPart]) -> Result<LLVMValue, CodegenError> {
-        self.emit("declare i8* @string_concat(i8*, i8*)");
-        self.emit("declare i8* @string_from_int(i64)");
-        self.emit("declare i8* @string_from_float(double)");
-        self.emit("declare i8* @string_new()");
+        self.module.declare("declare %string* @string_concat(%string*, %string*)");
+        self.module.declare("declare %string* @string_new()");
 
         let mut last_string_ptr = self.next_temp();
-        self.emit(&format!("  %{} = call i8* @string_new()", last_string_ptr));
+        self.emit(&format!("  %{} = call %string* @string_new()", last_string_ptr));
 
         for part in parts {
-            match part {
-                crate::ast::StringPart::Literal(s) => {
-                    let literal_val = self.compile_literal(&crate::ast::Literal::String(s.clone()))?;
-                    let next_temp = self.next_temp();
-                    self.emit(&format!("  %{} = call i8* @string_concat(i8* %{}, {} {})", next_temp, last_string_ptr, literal_val.llvm_type, literal_val.value_id));
-                    last_string_ptr = next_temp;
-                }
+            let part_val = match part {
+                crate::ast::StringPart::Literal(s) => self.compile_literal(&crate::ast::Literal::String(s.clone()))?,
                 crate::ast::StringPart::Expression(expr) => {
                     let expr_val = self.compile_expression(expr)?;
-                    let converted_str_ptr = self.next_temp();
-                    match expr_val.type_info {
-                        InferType::Int => self.emit(&format!("  %{} = call i8* @string_from_int({} {})", converted_str_ptr, expr_val.llvm_type, expr_val.value_id)),
-                        InferType::Float => self.emit(&format!("  %{} = call i8* @string_from_float({} {})", converted_str_ptr, expr_val.llvm_type, expr_val.value_id)),
-                        InferType::String => self.emit(&format!("  %{} = bitcast {} {} to i8*", converted_str_ptr, expr_val.llvm_type, expr_val.value_id)),
-                        _ => return Err(CodegenError::InvalidOperation("Cannot interpolate non-stringable type".to_string())),
-                    }
-                    let next_temp = self.next_temp();
-                    self.emit(&format!("  %{} = call i8* @string_concat(i8* %{}, i8* %{})", next_temp, last_string_ptr, converted_str_ptr));
-                    last_string_ptr = next_temp;
+                    self.compile_to_string(expr_val)?
                 }
-            }
+            };
+            let next_temp = self.next_temp();
+            self.emit(&format!("  %{} = call %string* @string_concat(%string* %{}, %string* {})", next_temp, last_string_ptr, part_val.value_id));
+            last_string_ptr = next_temp;
         }
 
+
         Ok(LLVMValue {
             type_info: InferType::String,
-            llvm_type: LLVMType::Pointer(Box::new(LLVMType::NamedStruct("string".to_string()))),
+            llvm_type: infer_to_llvm_type(&InferType::String),
             value_id: format!("%{}", last_string_ptr),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codegen::{compile_source, compile_unchecked};
+
+    #[test]
+    fn test_scopes() {
+        let int = LLVMValue { type_info: InferType::Int, llvm_type: LLVMType::Int(64), value_id: "1".to_string() };
+        let mut symbols = SymbolTable::new();
+        symbols.define_module_level("f".to_string(), Binding::Value(int.clone()));
+        let outer = symbols.enter_function();
+        symbols.define_variable("x".to_string(), int.clone());
+        symbols.push_scope();
+        symbols.define_variable("$".to_string(), int);
+        assert!(symbols.is_defined("x") && symbols.is_defined("f"));
+        symbols.pop_scope();
+        assert!(!symbols.is_defined("$"));
+        symbols.exit_function(outer);
+        assert!(!symbols.is_defined("x"));
+        assert!(symbols.is_defined("f"));
+    }
+
+    #[test]
+    fn test_enum_payload_storage_is_aligned() {
+        let ir = compile_source(
+            "enum shape\n    tagged(bool, f64)\n    small(i8, i16)\nenum layer\n    stacked(shape, bool)\nfn f() -> shape\n    s is shape.small(1, 2)\n    return shape.tagged(yes, 2.5)\n"
+        ).unwrap();
+        // `{ i1, double }` pads its bool to the double's alignment
+        assert!(ir.contains("%shape = type { i32, [2 x i64] }"));
+        assert!(ir.contains("store i8 1, i8* %"));
+        assert!(ir.contains("store i16 2, i16* %"));
+        assert!(ir.contains("%layer = type { i32, [4 x i64] }"));
+        assert!(ir.contains("bitcast [2 x i64]* %t3 to { i8, i16 }*"));
+        assert!(ir.contains("to { i1, double }*"));
+    }
+
+    #[test]
+    fn test_locals_live_in_entry_allocas() {
+        let ir = compile_source("fn f(n: i64) -> i64\n    while n gt 0\n        m is n\n        n is n - 1\n    return n\n").unwrap();
+        let entry = &ir[ir.find("entry:").unwrap()..ir.find("br label").unwrap()];
+        assert_eq!(entry.matches("alloca i64").count(), 2);
+        assert!(ir.contains("store i64 %n.arg"));
+        assert!(matches!(
+            compile_unchecked("fn f()\n    x is 1\nfn g() -> i64\n    return x\n"),
+            Err(CodegenError::UndefinedVariable(name)) if name == "x"
+        ));
+    }
+
+    #[test]
+    fn test_break_and_continue_target_enclosing_loop() {
+        let ir = compile_source(
+            "fn f(n: i64) -> i64\n    while true\n        if n gt 9\n            break\n        n is n + 1\n        if n equals 3\n            continue\n    n\n"
+        ).unwrap();
+        // while: L1 is the condition, L3 the exit
+        assert!(ir.contains("L4:\n  br label %L3\n"));
+        assert!(ir.contains("br label %L1\nL11:"));
+        assert!(ir.contains("ret i64 %"));
+        assert!(!ir.contains("undef"));
+        assert!(matches!(compile_unchecked("fn f()\n    break\n"), Err(CodegenError::InvalidOperation(_))));
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let ir = compile_source("fn g() -> bool\n    return true\nfn f(n: i64) -> bool\n    return n gt 0 and g()\n").unwrap();
+        let body = &ir[ir.find("define i1 @f").unwrap()..];
+        // `g` is only called once `n gt 0` has held
+        let branch_at = body.find("br i1").unwrap();
+        assert!(branch_at < body.find("call i1 @g()").unwrap());
+        assert!(body.contains("phi i1 [ false, %entry ]"));
+    }
+
+    #[test]
+    fn test_methods_take_self_and_reach_fields() {
+        let ir = compile_source(
+            "object datapoint\n    value: i64\n    processed: bool\n    process() -> i64:\n        processed is true\n        return value\n    shift(dx: i64):\n        value is value + dx\nfn run(d: datapoint) -> i64\n    d.shift(2)\n    return d.process()\n"
+        ).unwrap();
+        assert!(ir.contains("define i64 @datapoint__process(%datapoint* %self)"));
+        // Fields are addressed in declaration order
+        assert!(ir.contains("%t2 = getelementptr inbounds %datapoint, %datapoint* %self, i32 0, i32 1\n"));
+        assert!(ir.contains("store i1 true, i1* %t2"));
+        assert!(ir.contains("call i64 @datapoint__process(%datapoint* %"));
+        // Unit methods are called without naming a result
+        assert!(ir.contains("\n  call void @datapoint__shift(%datapoint* %"));
+    }
+
+    #[test]
+    fn test_make_allocates_and_runs_user_body() {
+        let ir = compile_source(
+            "object point\n    x: i64\n    y ? 10\n    seen ? no\n    make\n        seen is yes\nfn run() -> i64\n    p is point.make 1\n    return p.y\n"
+        ).unwrap();
+        assert!(ir.contains("%point = type { i64, i64, i1 }"));
+        assert!(ir.contains("define %point* @point__make(i64 %x.arg, i64 %y.arg, i1 %seen.arg)"));
+        assert!(ir.contains("call i8* @coral_alloc(i64 %"));
+        assert!(ir.contains("call void @point__make.body(%point* %"));
+        // Omitted fields take their defaults at the call site
+        assert!(ir.contains("call %point* @point__make(i64 1, i64 10, i1 false)"));
+    }
+
+    #[test]
+    fn test_fields_take_the_solved_type_of_any_default() {
+        let ir = compile_source(
+            "object inner\n    c ? 1\nobject outer\n    b ? inner.make\n    t ? (1, 2)\nfn run() -> i64\n    o is outer.make()\n    o.b.c is 5\n    return o.b.c\n"
+        ).unwrap();
+        assert!(ir.contains("%outer = type { %inner*, i8* }"));
+        assert!(ir.contains("call %inner* @inner__make(i64 1)"));
+    }
+
+    #[test]
+    fn test_store_fields_take_the_solved_type_of_their_default() {
+        let ir = compile_source("store task\n    title: string\n    done ? no\n    count ? 0\n").unwrap();
+        assert!(ir.contains("%task = type { %string*, i1, i64 }"));
+    }
+
+    #[test]
+    fn test_member_without_parens_calls_the_method() {
+        let ir = compile_source(
+            "object doc\n    n: i64\n    process() -> i64:\n        return n + 1\nfn run() -> i64\n    d is doc.make(4)\n    return d.process\n"
+        ).unwrap();
+        assert!(ir.contains("call i64 @doc__process(%doc* %"));
+    }
+
+    #[test]
+    fn test_strings_use_one_runtime_layout() {
+        let ir = compile_source(
+            "GREETING is \"hi\"\nfn same(name: string) -> bool\n    return '{GREETING} {name}' equals name + \"!\"\n"
+        ).unwrap();
+        assert!(ir.contains("%string = type { i64, [0 x i8] }"));
+        assert!(ir.contains("@GREETING = private unnamed_addr constant { i64, [3 x i8] } { i64 2, [3 x i8] c\"hi\\00\" }"));
+        assert!(ir.contains("define i1 @same(%string* %name.arg)"));
+        assert!(ir.contains("call %string* @string_concat(%string* %"));
+        assert!(ir.contains("%string* bitcast ({ i64, [2 x i8] }* @.str."));
+        assert!(ir.contains("call i32 @string_equals(%string* %"));
+    }
+}
@@ -0,0 +1,148 @@
+//! The program entry point.
+//!
+//! Statements written at the top level of a file run, in order, from an
+//! internal `@__coral_init` function, and the variables they bind become
+//! module globals so functions can read them. The synthesized `@main` calls
+//! the initializer and then the program's own `fn main`, which is emitted as
+//! `@__coral_main`; an integer result from it becomes the exit code.
+
+use crate::ast::{signature_type_params, Program, Stmt, StmtKind};
+use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::{Binding, CodegenError, LLVMCodegen, LLVMValue};
+use crate::consteval::constant_binding;
+use crate::resolver::InferType;
+
+/// Symbol of the function that runs top-level statements
+const INIT_SYMBOL: &str = "__coral_init";
+/// Symbol the program's `fn main` is emitted under, leaving `@main` to the entry point
+pub(crate) const USER_MAIN_SYMBOL: &str = "__coral_main";
+
+/// Definitions are compiled on their own; everything else runs at startup
+pub(crate) fn is_definition(stmt: &Stmt) -> bool {
+    matches!(
+        stmt.kind,
+        StmtKind::Function { .. }
+            | StmtKind::Object { .. }
+            | StmtKind::Store { .. }
+            | StmtKind::Actor { .. }
+            | StmtKind::Enum { .. }
+            | StmtKind::Trait { .. }
+            | StmtKind::Import { .. }
+    )
+}
+
+/// The symbol a top-level function is emitted under
+pub(crate) fn function_symbol(name: &str) -> &str {
+    if name == "main" { USER_MAIN_SYMBOL } else { name }
+}
+
+impl LLVMCodegen {
+    /// Register every non-generic top-level function, so calls may precede definitions
+    pub fn declare_functions(&mut self, program: &Program) {
+        for stmt in &program.statements {
+            if let StmtKind::Function { name, params, return_type, .. } = &stmt.kind {
+                if signature_type_params(params, return_type.as_ref()).is_empty() {
+                    let function = self.function_value(function_symbol(name), params, return_type.as_ref());
+                    self.symbols.define_module_level(name.clone(), Binding::Value(function));
+                }
+            }
+        }
+    }
+
+    /// Split off the statements that run at startup, skipping constant bindings
+    pub(crate) fn top_level_code(program: &Program) -> Vec<Stmt> {
+        program.statements.iter()
+            .filter(|stmt| !is_definition(stmt) && constant_binding(stmt).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Emit `@__coral_init` for the top-level statements, if there are any
+    pub fn compile_init(&mut self, statements: &[Stmt]) -> Result<bool, CodegenError> {
+        if statements.is_empty() {
+            return Ok(false);
+        }
+        let frame = self.begin_function(&format!("define internal void @{}() {{", INIT_SYMBOL), InferType::Unit);
+        self.module_level = true;
+        for stmt in statements {
+            self.compile_statement(stmt)?;
+        }
+        self.emit("  ret void");
+        self.finish_function(frame);
+        Ok(true)
+    }
+
+    /// A module-level variable, zero until the initializer stores its value
+    pub(crate) fn define_global(&mut self, name: &str, value: &LLVMValue) -> LLVMValue {
+        self.module.add_global(format!("@{} = internal global {} zeroinitializer", name, value.llvm_type));
+        let global = LLVMValue {
+            type_info: value.type_info.clone(),
+            llvm_type: value.llvm_type.clone(),
+            value_id: format!("@{}", name),
+        };
+        self.symbols.define_module_level(name.to_string(), Binding::Slot(global.clone()));
+        global
+    }
+
+    /// Emit `@main`: run the initializer, then the user's `main`, and return its exit code
+    pub fn emit_entry_point(&mut self, has_init: bool) -> Result<(), CodegenError> {
+        let user_main = match self.symbols.lookup("main") {
+            Some(Binding::Value(main)) if main.value_id == format!("@{}", USER_MAIN_SYMBOL) => Some(main.clone()),
+            _ => None,
+        };
+        if !has_init && user_main.is_none() {
+            return Ok(());
+        }
+
+        let frame = self.begin_function("define i32 @main() {", InferType::I32);
+        if has_init {
+            self.emit(&format!("  call void @{}()", INIT_SYMBOL));
+        }
+
+        let mut exit_code = "0".to_string();
+        if let Some(InferType::Function { return_type, .. }) = user_main.map(|main| main.type_info) {
+            let return_llvm_type = infer_to_llvm_type(&return_type);
+            if return_type.is_integer() {
+                let result = self.next_temp();
+                self.emit(&format!("  %{} = call {} @{}()", result, return_llvm_type, USER_MAIN_SYMBOL));
+                let status = self.convert_numeric(LLVMValue {
+                    type_info: *return_type,
+                    llvm_type: return_llvm_type,
+                    value_id: format!("%{}", result),
+                }, &InferType::I32)?;
+                exit_code = status.value_id;
+            } else if return_llvm_type == LLVMType::Void {
+                self.emit(&format!("  call void @{}()", USER_MAIN_SYMBOL));
+            } else {
+                let ignored = self.next_temp();
+                self.emit(&format!("  %{} = call {} @{}()", ignored, return_llvm_type, USER_MAIN_SYMBOL));
+            }
+        }
+        self.emit(&format!("  ret i32 {}", exit_code));
+        self.finish_function(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::compile_source;
+
+    #[test]
+    fn test_top_level_code_runs_before_main() {
+        let ir = compile_source("total is 0\nfn bump(n: i64) -> i64\n    return n + 1\ntotal is bump(41)\nfn main() -> i64\n    return 3\n").unwrap();
+        assert!(ir.contains("@total = internal global i64 zeroinitializer"));
+        assert!(ir.contains("define internal void @__coral_init()"));
+        assert!(ir.contains("define i64 @__coral_main()"));
+        let entry = &ir[ir.find("define i32 @main()").unwrap()..];
+        assert!(entry.find("call void @__coral_init()").unwrap() < entry.find("call i64 @__coral_main()").unwrap());
+        assert!(entry.contains("trunc i64"));
+    }
+
+    #[test]
+    fn test_library_has_no_entry_point() {
+        let ir = compile_source("fn bump(n: i64) -> i64\n    return n + 1\n").unwrap();
+        assert!(!ir.contains("@main"));
+        assert!(!ir.contains("__coral_init"));
+    }
+}
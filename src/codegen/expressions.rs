@@ -1,15 +1,26 @@
-use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::codegen::{Binding, CodegenError, LLVMCodegen, LLVMValue};
+use crate::codegen::closures::is_direct_function;
 use crate::ast::{Expr, ExprKind, BinaryOp, UnaryOp, Literal};
 use crate::resolver::InferType;
 use crate::codegen::types::infer_to_llvm_type;
+use crate::codegen::numeric::float_constant;
+use crate::consteval::ConstValue;
 
 impl LLVMCodegen {
     pub fn compile_expression(&mut self, expr: &Expr) -> Result<LLVMValue, CodegenError> {
         match &expr.kind {
             ExprKind::Literal(lit) => self.compile_literal(lit),
             ExprKind::Identifier(name) => {
-                if let Some(llvm_val) = self.symbols.lookup_variable(name) {
-                    return Ok(llvm_val.clone());
+                match self.symbols.lookup(name).cloned() {
+                    // A top-level function used as a value becomes a closure
+                    Some(Binding::Value(function)) if is_direct_function(&function) => {
+                        return Ok(self.function_closure(&function));
+                    }
+                    Some(binding) => return Ok(self.binding_value(&binding)),
+                    None => {}
+                }
+                if let Some(value) = self.constants.get(name).cloned() {
+                    return Ok(self.compile_constant(name, &value));
                 }
                 if let Some(obj_type) = self.lookup_object_type(name) {
                     return Ok(LLVMValue {
@@ -28,21 +39,49 @@ impl LLVMCodegen {
             }
             ExprKind::Call { callee, args } => {
                 let arg_exprs: Vec<_> = args.iter().map(|arg| arg.value.clone()).collect();
+                // `primes(0)` reads an element when the callee is a collection
+                if let (Some(InferType::List(_) | InferType::Map(_, _) | InferType::String), [index]) = (self.static_type(callee), arg_exprs.as_slice()) {
+                    let collection = self.compile_expression(callee)?;
+                    return self.compile_element_load(collection, index, &callee.span);
+                }
                 match &callee.kind {
                     ExprKind::Identifier(type_or_func) => {
                         if let Some(obj_type) = self.lookup_object_type(type_or_func) {
                             self.compile_object_instantiation(type_or_func, obj_type, &arg_exprs)
+                        } else if let Some(name) = self.generic_object_of(callee) {
+                            self.compile_generic_instantiation(&name, &arg_exprs)
+                        } else if self.is_generic_function(type_or_func) {
+                            self.compile_generic_call(callee, type_or_func, &arg_exprs)
                         } else {
                             self.compile_function_call(callee, &arg_exprs)
                         }
                     }
                     ExprKind::FieldAccess { object, field } => {
+                        if let Some(enum_type) = self.enum_type_of(object) {
+                            return self.compile_enum_variant(enum_type, field, &arg_exprs);
+                        }
+                        if field == "make" {
+                            if let Some((type_name, obj_type)) = self.object_type_of(object) {
+                                return self.compile_object_instantiation(&type_name, obj_type, &arg_exprs);
+                            }
+                            if let Some(name) = self.generic_object_of(object) {
+                                return self.compile_generic_instantiation(&name, &arg_exprs);
+                            }
+                        }
                         self.compile_method_call(object, field, &arg_exprs)
                     }
                     _ => self.compile_function_call(callee, &arg_exprs),
                 }
             }
+            ExprKind::Index { object, index } => {
+                let collection = self.compile_expression(object)?;
+                self.compile_element_load(collection, index, &expr.span)
+            }
             ExprKind::FieldAccess { object, field } => {
+                // Unit enum variant: enum_name.variant
+                if let Some(enum_type) = self.enum_type_of(object) {
+                    return self.compile_enum_variant(enum_type, field, &[]);
+                }
                 // Property access: object.field
                 self.compile_property_access(object, field)
             }
@@ -62,12 +101,51 @@ impl LLVMCodegen {
                 }
                 self.compile_object_instantiation(name, obj_type, &args)
             }
+            ExprKind::ErrValue(payload) => self.compile_err_value(payload),
+            ExprKind::Lambda { params, body } => self.compile_lambda(expr.id, params, body),
+            ExprKind::Cast { expr, target } => {
+                let value = self.compile_expression(expr)?;
+                let target = self.ast_type_to_infer_type(target);
+                self.convert_numeric(value, &target)
+            }
             _ => Err(CodegenError::UnsupportedFeature(
                 format!("Expression type not implemented: {:?}", expr.kind)
             ))
         }
     }
 
+    /// Current value of a binding, loading it if it lives in a slot
+    pub(crate) fn binding_value(&mut self, binding: &Binding) -> LLVMValue {
+        match binding {
+            Binding::Value(value) => value.clone(),
+            Binding::Slot(slot) => {
+                let loaded = self.next_temp();
+                self.emit(&format!("  %{} = load {}, {}* {}", loaded, slot.llvm_type, slot.llvm_type, slot.value_id));
+                LLVMValue { value_id: format!("%{}", loaded), ..slot.clone() }
+            }
+        }
+    }
+
+    /// The enum type named by a bare identifier, unless a local shadows it
+    fn enum_type_of(&self, expr: &Expr) -> Option<InferType> {
+        match &expr.kind {
+            ExprKind::Identifier(name) if !self.symbols.is_defined(name) => {
+                self.lookup_enum_type(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// The object type named by a bare identifier, unless a local shadows it
+    fn object_type_of(&self, expr: &Expr) -> Option<(String, InferType)> {
+        match &expr.kind {
+            ExprKind::Identifier(name) if !self.symbols.is_defined(name) => {
+                self.lookup_object_type(name).map(|obj_type| (name.clone(), obj_type))
+            }
+            _ => None,
+        }
+    }
+
     pub fn compile_literal(&mut self, lit: &Literal) -> Result<LLVMValue, CodegenError> {
         match lit {
             Literal::Integer(i) => Ok(LLVMValue {
@@ -78,23 +156,14 @@ impl LLVMCodegen {
             Literal::Float(f) => Ok(LLVMValue {
                 type_info: InferType::Float,
                 llvm_type: crate::codegen::types::LLVMType::Double,
-                value_id: f.to_string(),
+                value_id: float_constant(*f, &InferType::Float),
             }),
             Literal::String(s) => {
-                let string_const_name = format!("@.str.{}", self.next_temp());
-                let string_len = s.len() + 1;
-                let global_string_def = format!("{} = private unnamed_addr constant [{} x i8] c\"{}\\00\", align 1", 
-                    string_const_name,
-                    string_len,
-                    s.escape_default().to_string().replace("\"", "\\22")
-                );
-                self.global_strings.push(global_string_def);
-                let global_ptr_temp = self.next_temp();
-                self.emit(&format!("  %{} = getelementptr inbounds [{} x i8], [{} x i8]* {}, i64 0, i64 0", global_ptr_temp, string_len, string_len, string_const_name));
+                let symbol = self.module.string_symbol();
                 Ok(LLVMValue {
                     type_info: InferType::String,
-                    llvm_type: crate::codegen::types::LLVMType::Pointer(Box::new(crate::codegen::types::LLVMType::Int(8))),
-                    value_id: format!("%{}", global_ptr_temp),
+                    llvm_type: infer_to_llvm_type(&InferType::String),
+                    value_id: self.module.string_constant(&symbol, s),
                 })
             },
             Literal::Bool(b) => Ok(LLVMValue {
@@ -102,6 +171,16 @@ impl LLVMCodegen {
                 llvm_type: crate::codegen::types::LLVMType::Int(1),
                 value_id: if *b { "true".to_string() } else { "false".to_string() },
             }),
+            Literal::Now => {
+                self.module.declare("declare i64 @coral_now()");
+                let now_temp = self.next_temp();
+                self.emit(&format!("  %{} = call i64 @coral_now()", now_temp));
+                Ok(LLVMValue {
+                    type_info: InferType::Int,
+                    llvm_type: crate::codegen::types::LLVMType::Int(64),
+                    value_id: format!("%{}", now_temp),
+                })
+            }
             Literal::Unit => Ok(LLVMValue {
                 type_info: InferType::Unit,
                 llvm_type: crate::codegen::types::LLVMType::Void,
@@ -111,32 +190,103 @@ impl LLVMCodegen {
         }
     }
 
+    /// Inline a constant's value; strings point into its global
+    fn compile_constant(&mut self, name: &str, value: &ConstValue) -> LLVMValue {
+        match value {
+            ConstValue::Int(i) => LLVMValue {
+                type_info: InferType::Int,
+                llvm_type: crate::codegen::types::LLVMType::Int(64),
+                value_id: i.to_string(),
+            },
+            ConstValue::Float(f) => LLVMValue {
+                type_info: InferType::Float,
+                llvm_type: crate::codegen::types::LLVMType::Double,
+                value_id: float_constant(*f, &InferType::Float),
+            },
+            ConstValue::Bool(b) => LLVMValue {
+                type_info: InferType::Bool,
+                llvm_type: crate::codegen::types::LLVMType::Int(1),
+                value_id: b.to_string(),
+            },
+            ConstValue::String(s) => LLVMValue {
+                type_info: InferType::String,
+                llvm_type: infer_to_llvm_type(&InferType::String),
+                value_id: format!("bitcast ({{ i64, [{} x i8] }}* @{} to %string*)", s.len() + 1, name),
+            },
+        }
+    }
+
+    /// `and`/`or` only evaluate `right` when `left` does not decide the result
+    fn compile_short_circuit(&mut self, op: &BinaryOp, left: &Expr, right: &Expr) -> Result<LLVMValue, CodegenError> {
+        let left_val = self.compile_expression(left)?;
+        let left_block = self.current_block.clone();
+        let right_label = self.next_label();
+        let merge_label = self.next_label();
+
+        // The value the whole expression takes when `left` decides it
+        let decided = if *op == BinaryOp::And {
+            self.emit(&format!("  br i1 {}, label %L{}, label %L{}", left_val.value_id, right_label, merge_label));
+            "false"
+        } else {
+            self.emit(&format!("  br i1 {}, label %L{}, label %L{}", left_val.value_id, merge_label, right_label));
+            "true"
+        };
+
+        self.start_block(right_label);
+        let right_val = self.compile_expression(right)?;
+        let right_block = self.current_block.clone();
+        self.emit(&format!("  br label %L{}", merge_label));
+
+        self.start_block(merge_label);
+        let result_temp = self.next_temp();
+        self.emit(&format!(
+            "  %{} = phi i1 [ {}, %{} ], [ {}, %{} ]",
+            result_temp, decided, left_block, right_val.value_id, right_block
+        ));
+        Ok(LLVMValue {
+            type_info: InferType::Bool,
+            llvm_type: crate::codegen::types::LLVMType::Int(1),
+            value_id: format!("%{}", result_temp),
+        })
+    }
+
     pub fn compile_binary_operation(&mut self, op: &BinaryOp, left: &Expr, right: &Expr) -> Result<LLVMValue, CodegenError> {
+        if matches!(op, BinaryOp::And | BinaryOp::Or) {
+            return self.compile_short_circuit(op, left, right);
+        }
         let left_val = self.compile_expression(left)?;
         let right_val = self.compile_expression(right)?;
+        if left_val.type_info == InferType::String && right_val.type_info == InferType::String {
+            match op {
+                BinaryOp::Add => return Ok(self.compile_string_concat(&left_val, &right_val)),
+                BinaryOp::Eq | BinaryOp::Ne => return Ok(self.compile_string_equality(op, &left_val, &right_val)),
+                _ => {}
+            }
+        }
+        let (left_val, right_val) = self.unify_operands(left_val, right_val)?;
         
         let result_temp = self.next_temp();
+        let is_float = left_val.type_info.is_float();
         
         let (instruction, result_type) = match op {
-            BinaryOp::Add => (if left_val.type_info == InferType::Float { "fadd" } else { "add" }, left_val.type_info.clone()),
-            BinaryOp::Sub => (if left_val.type_info == InferType::Float { "fsub" } else { "sub" }, left_val.type_info.clone()),
-            BinaryOp::Mul => (if left_val.type_info == InferType::Float { "fmul" } else { "mul" }, left_val.type_info.clone()),
-            BinaryOp::Div => (if left_val.type_info == InferType::Float { "fdiv" } else { "sdiv" }, left_val.type_info.clone()),
-            BinaryOp::Mod => ("srem", InferType::Int),
-            BinaryOp::Eq => (if left_val.type_info == InferType::Float { "fcmp oeq" } else { "icmp eq" }, InferType::Bool),
-            BinaryOp::Ne => (if left_val.type_info == InferType::Float { "fcmp one" } else { "icmp ne" }, InferType::Bool),
-            BinaryOp::Lt => (if left_val.type_info == InferType::Float { "fcmp olt" } else { "icmp slt" }, InferType::Bool),
-            BinaryOp::Le => (if left_val.type_info == InferType::Float { "fcmp ole" } else { "icmp sle" }, InferType::Bool),
-            BinaryOp::Gt => (if left_val.type_info == InferType::Float { "fcmp ogt" } else { "icmp sgt" }, InferType::Bool),
-            BinaryOp::Ge => (if left_val.type_info == InferType::Float { "fcmp oge" } else { "icmp sge" }, InferType::Bool),
-            BinaryOp::And => ("and", InferType::Bool),
-            BinaryOp::Or => ("or", InferType::Bool),
+            BinaryOp::Add => (if is_float { "fadd" } else { "add" }, left_val.type_info.clone()),
+            BinaryOp::Sub => (if is_float { "fsub" } else { "sub" }, left_val.type_info.clone()),
+            BinaryOp::Mul => (if is_float { "fmul" } else { "mul" }, left_val.type_info.clone()),
+            BinaryOp::Div => (if is_float { "fdiv" } else { "sdiv" }, left_val.type_info.clone()),
+            BinaryOp::Mod => (if is_float { "frem" } else { "srem" }, left_val.type_info.clone()),
+            BinaryOp::Eq => (if is_float { "fcmp oeq" } else { "icmp eq" }, InferType::Bool),
+            BinaryOp::Ne => (if is_float { "fcmp one" } else { "icmp ne" }, InferType::Bool),
+            BinaryOp::Lt => (if is_float { "fcmp olt" } else { "icmp slt" }, InferType::Bool),
+            BinaryOp::Le => (if is_float { "fcmp ole" } else { "icmp sle" }, InferType::Bool),
+            BinaryOp::Gt => (if is_float { "fcmp ogt" } else { "icmp sgt" }, InferType::Bool),
+            BinaryOp::Ge => (if is_float { "fcmp oge" } else { "icmp sge" }, InferType::Bool),
+            BinaryOp::And | BinaryOp::Or => unreachable!("logical operators short-circuit"),
             BinaryOp::Xor => ("xor", InferType::Bool),
-            BinaryOp::BitAnd => ("and", InferType::Int),
-            BinaryOp::BitOr => ("or", InferType::Int),
-            BinaryOp::BitXor => ("xor", InferType::Int),
-            BinaryOp::Shl => ("shl", InferType::Int),
-            BinaryOp::Shr => ("lshr", InferType::Int),
+            BinaryOp::BitAnd => ("and", left_val.type_info.clone()),
+            BinaryOp::BitOr => ("or", left_val.type_info.clone()),
+            BinaryOp::BitXor => ("xor", left_val.type_info.clone()),
+            BinaryOp::Shl => ("shl", left_val.type_info.clone()),
+            BinaryOp::Shr => ("lshr", left_val.type_info.clone()),
             BinaryOp::Is => ("icmp eq", InferType::Bool),
         };
         
@@ -156,27 +306,60 @@ impl LLVMCodegen {
         })
     }
 
+    /// `string + string` goes through the runtime
+    pub(crate) fn compile_string_concat(&mut self, left: &LLVMValue, right: &LLVMValue) -> LLVMValue {
+        self.module.declare("declare %string* @string_concat(%string*, %string*)");
+        let result_temp = self.next_temp();
+        self.emit(&format!(
+            "  %{} = call %string* @string_concat(%string* {}, %string* {})",
+            result_temp, left.value_id, right.value_id
+        ));
+        LLVMValue {
+            type_info: InferType::String,
+            llvm_type: infer_to_llvm_type(&InferType::String),
+            value_id: format!("%{}", result_temp),
+        }
+    }
+
+    /// Strings are equal when their contents are, wherever they live
+    fn compile_string_equality(&mut self, op: &BinaryOp, left: &LLVMValue, right: &LLVMValue) -> LLVMValue {
+        self.module.declare("declare i32 @string_equals(%string*, %string*)");
+        let equals = self.next_temp();
+        self.emit(&format!(
+            "  %{} = call i32 @string_equals(%string* {}, %string* {})",
+            equals, left.value_id, right.value_id
+        ));
+        let result_temp = self.next_temp();
+        let predicate = if *op == BinaryOp::Eq { "ne" } else { "eq" };
+        self.emit(&format!("  %{} = icmp {} i32 %{}, 0", result_temp, predicate, equals));
+        LLVMValue {
+            type_info: InferType::Bool,
+            llvm_type: crate::codegen::types::LLVMType::Int(1),
+            value_id: format!("%{}", result_temp),
+        }
+    }
+
     pub fn compile_unary_operation(&mut self, op: &UnaryOp, operand: &Expr) -> Result<LLVMValue, CodegenError> {
         let operand_val = self.compile_expression(operand)?;
         let result_temp = self.next_temp();
 
         let (instruction, result_type) = match op {
             UnaryOp::Neg => {
-                if operand_val.type_info == InferType::Float {
-                    ("fsub", InferType::Float)
+                if operand_val.type_info.is_float() {
+                    ("fsub", operand_val.type_info.clone())
                 } else {
-                    ("sub", InferType::Int)
+                    ("sub", operand_val.type_info.clone())
                 }
             }
             UnaryOp::Not => ("xor", InferType::Bool),
-            UnaryOp::BitNot => ("xor", InferType::Int),
+            UnaryOp::BitNot => ("xor", operand_val.type_info.clone()),
         };
 
         let llvm_result_type = infer_to_llvm_type(&result_type);
 
         match op {
             UnaryOp::Neg => {
-                if result_type == InferType::Float {
+                if result_type.is_float() {
                     self.emit(&format!(
                         "  %{} = {} {} 0.0, {}",
                         result_temp, instruction, llvm_result_type, operand_val.value_id
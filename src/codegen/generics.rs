@@ -0,0 +1,433 @@
+//! Monomorphization of generic functions and objects.
+//!
+//! Generic definitions emit nothing on their own. Each distinct set of type
+//! arguments gets its own copy: `first__i64` for a function, `box__i64` for an
+//! object layout together with its methods. Specialized bodies are filed with
+//! the module's functions as they are first needed.
+
+use std::collections::HashMap;
+
+use crate::ast::{signature_type_params, Expr, ExprKind, Parameter, Program, Stmt, StmtKind, Type};
+use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::InferType;
+
+impl LLVMCodegen {
+    /// Record generic functions and specialize objects named by concrete signatures
+    pub fn emit_generic_instances(&mut self, program: &Program) -> Result<(), CodegenError> {
+        let no_bindings = HashMap::new();
+        for stmt in &program.statements {
+            match &stmt.kind {
+                StmtKind::Function { name, params, return_type, .. } => {
+                    if !signature_type_params(params, return_type.as_ref()).is_empty() {
+                        self.generic_functions.insert(name.clone(), stmt.clone());
+                        continue;
+                    }
+                    for ty in params.iter().map(|p| &p.type_).chain(return_type.as_ref()) {
+                        self.specialize_generic_types(ty, &no_bindings)?;
+                    }
+                }
+                StmtKind::Object { type_params, fields, methods, .. } if type_params.is_empty() => {
+                    for field in fields {
+                        self.specialize_generic_types(&field.type_, &no_bindings)?;
+                    }
+                    for method in methods {
+                        for ty in method.params.iter().map(|p| &p.type_).chain(method.return_type.as_ref()) {
+                            self.specialize_generic_types(ty, &no_bindings)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// The program with the parameter and return types each top-level function
+    /// leaves out filled in from its inferred signature. Type variables the
+    /// resolver left open become type parameters, so `fn id(x)` is specialized
+    /// per call like a function declared `of T`
+    pub(crate) fn with_inferred_signatures(&self, program: &Program) -> Program {
+        let mut program = program.clone();
+        for stmt in &mut program.statements {
+            let StmtKind::Function { name, params, return_type, .. } = &mut stmt.kind else {
+                continue;
+            };
+            let Some(Type::Function { params: solved_params, return_type: solved_return }) = self.types.function_type(name) else {
+                continue;
+            };
+            for (param, solved) in params.iter_mut().zip(solved_params) {
+                if matches!(param.type_, Type::Unknown) {
+                    param.type_ = open_vars_as_params(solved);
+                }
+            }
+            if return_type.is_none() && !matches!(**solved_return, Type::Unit) {
+                *return_type = Some(open_vars_as_params(solved_return));
+            }
+        }
+        program
+    }
+
+    /// A call by name resolves to a generic function unless a local shadows it
+    pub(crate) fn is_generic_function(&self, name: &str) -> bool {
+        !self.symbols.is_defined(name) && self.generic_functions.contains_key(name)
+    }
+
+    /// Symbol of a specialization, e.g. `first__i64` or `pair__string_bool`
+    pub(crate) fn specialized_symbol(&self, name: &str, type_args: &[InferType]) -> String {
+        let suffix = type_args.iter().map(mangle_type).collect::<Vec<_>>().join("_");
+        format!("{}__{}", name, suffix)
+    }
+
+    /// The concrete object type for a generic object applied to `type_args`
+    pub(crate) fn generic_object_type(&self, name: &str, type_args: &[InferType]) -> Option<InferType> {
+        let (type_params, fields, methods) = match &self.generic_objects.get(name)?.kind {
+            StmtKind::Object { type_params, fields, methods, .. } => (type_params, fields, methods),
+            _ => return None,
+        };
+        let bindings: HashMap<String, InferType> = type_params.iter().cloned().zip(type_args.iter().cloned()).collect();
+        let fields = fields.iter()
+            .map(|f| (f.name.clone(), self.ast_type_with_bindings(&f.type_, &bindings)))
+            .collect();
+        let methods = methods.iter()
+            .map(|m| (m.name.clone(), self.method_infer_type(m, &bindings)))
+            .collect();
+        Some(InferType::Object {
+            name: self.specialized_symbol(name, type_args),
+            fields,
+            methods,
+            is_actor: false,
+            is_store: false,
+        })
+    }
+
+    /// Specialize every applied generic object mentioned in an annotation
+    fn specialize_generic_types(&mut self, ty: &Type, bindings: &HashMap<String, InferType>) -> Result<(), CodegenError> {
+        match ty {
+            Type::Generic { name, args } => {
+                for arg in args {
+                    self.specialize_generic_types(arg, bindings)?;
+                }
+                let type_args: Vec<InferType> = args.iter().map(|arg| self.ast_type_with_bindings(arg, bindings)).collect();
+                // Unbound parameters: wait until an enclosing specialization supplies them
+                if type_args.iter().all(|arg| *arg != InferType::Unknown) {
+                    self.specialize_object(name, &type_args)?;
+                }
+            }
+            Type::List(inner) => self.specialize_generic_types(inner, bindings)?,
+            Type::Map(key, value) | Type::Result(key, value) => {
+                self.specialize_generic_types(key, bindings)?;
+                self.specialize_generic_types(value, bindings)?;
+            }
+            Type::Function { params, return_type } => {
+                for param in params {
+                    self.specialize_generic_types(param, bindings)?;
+                }
+                self.specialize_generic_types(return_type, bindings)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Emit the layout and methods of a generic object for one set of type arguments
+    fn specialize_object(&mut self, name: &str, type_args: &[InferType]) -> Result<(), CodegenError> {
+        let symbol = self.specialized_symbol(name, type_args);
+        if !self.specializations.insert(symbol.clone()) {
+            return Ok(());
+        }
+        let object_type = self.generic_object_type(name, type_args)
+            .ok_or_else(|| CodegenError::UndefinedVariable(name.to_string()))?;
+        let stmt = self.generic_objects[name].clone();
+        let (type_params, fields, methods) = match &stmt.kind {
+            StmtKind::Object { type_params, fields, methods, .. } => (type_params, fields, methods),
+            _ => return Err(CodegenError::UndefinedVariable(name.to_string())),
+        };
+        let bindings: HashMap<String, InferType> = type_params.iter().cloned().zip(type_args.iter().cloned()).collect();
+
+        for field in fields {
+            self.specialize_generic_types(&field.type_, &bindings)?;
+        }
+        let layout = fields.iter()
+            .map(|f| infer_to_llvm_type(&self.ast_type_with_bindings(&f.type_, &bindings)).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.module.define_type(format!("%{} = type {{ {} }}", symbol, layout));
+        self.object_types.insert(symbol.clone(), object_type);
+        self.object_fields.insert(symbol.clone(), fields.clone());
+
+        let mut initializer = None;
+        for method in methods {
+            // A user-written `make` runs inside the generated constructor
+            if method.name == "make" {
+                let body_symbol = format!("{}.body", self.method_symbol(&symbol, "make"));
+                self.compile_specialization(&body_symbol, Some(&symbol), &method.params, None, &method.body, bindings.clone())?;
+                initializer = Some(body_symbol);
+                continue;
+            }
+            let method_symbol = self.method_symbol(&symbol, &method.name);
+            self.compile_specialization(&method_symbol, Some(&symbol), &method.params, method.return_type.as_ref(), &method.body, bindings.clone())?;
+        }
+        self.emit_constructor(&symbol, initializer.as_deref())?;
+        self.emit_to_string(&symbol, name)
+    }
+
+    /// The generic object a bare identifier names, unless a local shadows it
+    pub(crate) fn generic_object_of(&self, expr: &Expr) -> Option<String> {
+        match &expr.kind {
+            ExprKind::Identifier(name) if !self.symbols.is_defined(name) && self.generic_objects.contains_key(name) => {
+                Some(name.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// `box.make(7)`: specialize a generic object for the field values it is
+    /// made from, then construct it
+    pub fn compile_generic_instantiation(&mut self, name: &str, args: &[Expr]) -> Result<LLVMValue, CodegenError> {
+        let stmt = self.generic_objects.get(name).cloned()
+            .ok_or_else(|| CodegenError::UndefinedVariable(name.to_string()))?;
+        let (type_params, fields) = match &stmt.kind {
+            StmtKind::Object { type_params, fields, .. } => (type_params, fields),
+            _ => return Err(CodegenError::UndefinedVariable(name.to_string())),
+        };
+
+        let values = self.compile_field_values(name, fields, args)?;
+        let mut bindings = HashMap::new();
+        for (field, value) in fields.iter().zip(&values) {
+            self.bind_type_args(&field.type_, &value.type_info, &mut bindings);
+        }
+        let mut type_args = Vec::new();
+        for type_param in type_params {
+            match bindings.get(type_param) {
+                Some(ty) => type_args.push(ty.clone()),
+                None => return Err(CodegenError::UnsupportedFeature(format!(
+                    "Cannot infer type parameter {} of {} from its fields",
+                    type_param, name
+                ))),
+            }
+        }
+
+        self.specialize_object(name, &type_args)?;
+        let symbol = self.specialized_symbol(name, &type_args);
+        let object_type = self.lookup_object_type(&symbol)
+            .ok_or_else(|| CodegenError::UndefinedVariable(symbol.clone()))?;
+        self.call_constructor(&symbol, object_type, values)
+    }
+
+    /// Compile one copy of a generic body into the deferred buffer
+    fn compile_specialization(
+        &mut self,
+        symbol: &str,
+        receiver: Option<&str>,
+        params: &[Parameter],
+        return_type: Option<&Type>,
+        body: &[Stmt],
+        bindings: HashMap<String, InferType>,
+    ) -> Result<(), CodegenError> {
+        for ty in params.iter().map(|p| &p.type_).chain(return_type) {
+            self.specialize_generic_types(ty, &bindings)?;
+        }
+
+        let outer_symbols = self.symbols.clone();
+        let outer_return_type = self.current_return_type.take();
+        let outer_bindings = std::mem::replace(&mut self.type_bindings, bindings);
+
+        let result = self.compile_function_with_receiver(symbol, receiver, params, return_type, body);
+
+        self.symbols = outer_symbols;
+        self.current_return_type = outer_return_type;
+        self.type_bindings = outer_bindings;
+        result
+    }
+
+    /// Call a generic function, specializing it for the argument types on first use
+    pub fn compile_generic_call(&mut self, callee: &Expr, name: &str, args: &[Expr]) -> Result<LLVMValue, CodegenError> {
+        let stmt = self.generic_functions.get(name).cloned()
+            .ok_or_else(|| CodegenError::UndefinedVariable(name.to_string()))?;
+        let (params, return_type, body) = match &stmt.kind {
+            StmtKind::Function { params, return_type, body, .. } => (params, return_type.as_ref(), body),
+            _ => return Err(CodegenError::UndefinedVariable(name.to_string())),
+        };
+
+        let mut arg_vals = Vec::new();
+        for arg in args {
+            arg_vals.push(self.compile_expression(arg)?);
+        }
+
+        let mut bindings = HashMap::new();
+        for (param, arg_val) in params.iter().zip(&arg_vals) {
+            self.bind_type_args(&param.type_, &arg_val.type_info, &mut bindings);
+        }
+        // A result type the arguments do not determine comes from the type
+        // the resolver gave this use of the function
+        if let Some(Type::Function { return_type: solved_return, .. }) = self.types.get(callee.id).cloned() {
+            if let Some(return_type) = return_type {
+                let solved_return = self.ast_type_to_infer_type(&solved_return);
+                self.bind_type_args(return_type, &solved_return, &mut bindings);
+            }
+        }
+        let mut type_args = Vec::new();
+        for type_param in signature_type_params(params, return_type) {
+            match bindings.get(&type_param) {
+                Some(ty) => type_args.push(ty.clone()),
+                // Parameters standing for inference variables have no name to report
+                None if type_param.starts_with('\'') => return Err(CodegenError::UnsupportedFeature(format!(
+                    "Cannot infer the signature of {} at this call; annotate its parameters or return type",
+                    name
+                ))),
+                None => return Err(CodegenError::UnsupportedFeature(format!(
+                    "Cannot infer type parameter {} of {} from its arguments",
+                    type_param, name
+                ))),
+            }
+        }
+
+        let symbol = self.specialized_symbol(name, &type_args);
+        if self.specializations.insert(symbol.clone()) {
+            self.compile_specialization(&symbol, None, params, return_type, body, bindings.clone())?;
+        }
+
+        let mut call_args = Vec::new();
+        for (param, arg_val) in params.iter().zip(arg_vals) {
+            let arg_val = match self.ast_type_with_bindings(&param.type_, &bindings) {
+                trait_type @ InferType::Trait { .. } => self.compile_trait_coercion(arg_val, &trait_type)?,
+                numeric if numeric.is_numeric() && arg_val.type_info.is_numeric() => self.convert_numeric(arg_val, &numeric)?,
+                _ => arg_val,
+            };
+            call_args.push(format!("{} {}", arg_val.llvm_type, arg_val.value_id));
+        }
+
+        let return_type = return_type
+            .map(|ty| self.ast_type_with_bindings(ty, &bindings))
+            .unwrap_or(InferType::Unit);
+        let return_llvm_type = infer_to_llvm_type(&return_type);
+        if return_llvm_type == LLVMType::Void {
+            self.emit(&format!("  call void @{}({})", symbol, call_args.join(", ")));
+            return Ok(LLVMValue {
+                type_info: InferType::Unit,
+                llvm_type: LLVMType::Void,
+                value_id: "".to_string(),
+            });
+        }
+
+        let result = self.next_temp();
+        self.emit(&format!("  %{} = call {} @{}({})", result, return_llvm_type, symbol, call_args.join(", ")));
+        Ok(LLVMValue {
+            type_info: return_type,
+            llvm_type: return_llvm_type,
+            value_id: format!("%{}", result),
+        })
+    }
+
+    /// Match a parameter annotation against an argument type, binding type parameters
+    fn bind_type_args(&self, pattern: &Type, actual: &InferType, out: &mut HashMap<String, InferType>) {
+        match (pattern, actual) {
+            (Type::Param(name), _) if *actual != InferType::Unknown => {
+                out.entry(name.clone()).or_insert_with(|| actual.clone());
+            }
+            (Type::List(inner), InferType::List(actual_inner)) => self.bind_type_args(inner, actual_inner, out),
+            (Type::Map(key, value), InferType::Map(actual_key, actual_value))
+            | (Type::Result(key, value), InferType::Result(actual_key, actual_value)) => {
+                self.bind_type_args(key, actual_key, out);
+                self.bind_type_args(value, actual_value, out);
+            }
+            (Type::Function { params, return_type }, InferType::Function { params: actual_params, return_type: actual_return, .. }) => {
+                for (param, actual_param) in params.iter().zip(actual_params) {
+                    self.bind_type_args(param, actual_param, out);
+                }
+                self.bind_type_args(return_type, actual_return, out);
+            }
+            (Type::Generic { name, args }, InferType::Object { fields: actual_fields, .. }) => {
+                let (type_params, fields) = match self.generic_objects.get(name).map(|stmt| &stmt.kind) {
+                    Some(StmtKind::Object { type_params, fields, .. }) => (type_params, fields),
+                    _ => return,
+                };
+                // Recover the object's own type arguments from its fields, then match those
+                let mut object_bindings = HashMap::new();
+                for field in fields {
+                    if let Some(actual_field) = actual_fields.get(&field.name) {
+                        self.bind_type_args(&field.type_, actual_field, &mut object_bindings);
+                    }
+                }
+                for (type_param, arg) in type_params.iter().zip(args) {
+                    if let Some(bound) = object_bindings.get(type_param) {
+                        self.bind_type_args(arg, bound, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `ty` with each inference variable turned into a type parameter; the quote
+/// keeps the names apart from any the source declares
+fn open_vars_as_params(ty: &Type) -> Type {
+    match ty {
+        Type::TypeVar(id) => Type::Param(format!("'{}", id)),
+        Type::List(inner) => Type::List(Box::new(open_vars_as_params(inner))),
+        Type::Map(key, value) => Type::Map(Box::new(open_vars_as_params(key)), Box::new(open_vars_as_params(value))),
+        Type::Result(ok, err) => Type::Result(Box::new(open_vars_as_params(ok)), Box::new(open_vars_as_params(err))),
+        Type::Function { params, return_type } => Type::Function {
+            params: params.iter().map(open_vars_as_params).collect(),
+            return_type: Box::new(open_vars_as_params(return_type)),
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args.iter().map(open_vars_as_params).collect(),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Spelling of a type inside a specialization symbol
+fn mangle_type(ty: &InferType) -> String {
+    match ty {
+        InferType::Unit => "unit".to_string(),
+        InferType::Bool => "bool".to_string(),
+        InferType::Int => "i64".to_string(),
+        InferType::Float => "f64".to_string(),
+        InferType::I8 => "i8".to_string(),
+        InferType::I16 => "i16".to_string(),
+        InferType::I32 => "i32".to_string(),
+        InferType::F32 => "f32".to_string(),
+        InferType::String => "string".to_string(),
+        InferType::List(inner) => format!("list_{}", mangle_type(inner)),
+        InferType::Map(key, value) => format!("map_{}_{}", mangle_type(key), mangle_type(value)),
+        InferType::Result(ok, err) => format!("result_{}_{}", mangle_type(ok), mangle_type(err)),
+        InferType::Object { name, .. }
+        | InferType::Enum { name, .. }
+        | InferType::Trait { name, .. }
+        | InferType::Store { name, .. }
+        | InferType::Actor { name, .. } => name.clone(),
+        _ => "any".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::compile_source;
+
+    #[test]
+    fn test_generic_object_specialized_where_it_is_made() {
+        let ir = compile_source(
+            "object box of T\n    item: T\n    get() -> T:\n        return item\nfn run() -> i64\n    bx is box.make 7\n    named is box.make('seven')\n    return bx.get()\n"
+        ).unwrap();
+        assert!(ir.contains("%box__i64 = type { i64 }"));
+        assert!(ir.contains("%box__string = type { %string* }"));
+        assert!(ir.contains("define %box__i64* @box__i64__make(i64 %item.arg)"));
+        assert!(ir.contains("call %box__i64* @box__i64__make(i64 7)"));
+        assert!(ir.contains("define i64 @box__i64__get(%box__i64* %self)"));
+        assert!(ir.contains("define %string* @box__string__to_string(%box__string* %self)"));
+    }
+
+    #[test]
+    fn test_type_params_bind_through_function_arguments() {
+        let ir = compile_source(
+            "fn apply of A, B(f: fn(A) -> B, v: A) -> B\n    return f(v)\nfn double(n: i64) -> i64\n    return n * 2\nfn run() -> i64\n    return apply(double, 5)\n"
+        ).unwrap();
+        assert!(ir.contains("define i64 @apply__i64_i64({ i8*, i8* } %f.arg, i64 %v.arg)"));
+        assert!(ir.contains("call i64 @apply__i64_i64({ i8*, i8* } %"));
+    }
+}
@@ -2,5 +2,38 @@ pub mod types;
 pub mod expressions;
 pub mod statements;
 pub mod definitions;
+pub mod traits;
+pub mod generics;
+pub mod numeric;
+pub mod module;
+pub mod entry;
+pub mod closures;
+pub mod collections;
+pub mod printing;
 
-pub use definitions::*;
\ No newline at end of file
+pub use definitions::*;
+
+#[cfg(test)]
+fn parse_source(input: &str) -> crate::ast::Program {
+    let mut lexer = crate::lexer::Lexer::new(input.to_string(), "test".to_string());
+    let tokens = lexer.tokenize().unwrap();
+    let mut parser = crate::parser::Parser::new(tokens, "test".to_string());
+    parser.parse().unwrap()
+}
+
+/// Lex, parse and analyze `input`, then compile it with the types the
+/// resolver solved
+#[cfg(test)]
+pub(crate) fn compile_source(input: &str) -> Result<String, CodegenError> {
+    let program = parse_source(input);
+    let mut analyzer = crate::semantic::SemanticAnalyzer::new();
+    analyzer.analyze(&program).unwrap();
+    LLVMCodegen::new("test".to_string()).with_types(analyzer.types().clone()).compile_program(&program)
+}
+
+/// Compile `input` without analyzing it first, to reach codegen's own checks
+/// on programs the analyzer would reject
+#[cfg(test)]
+pub(crate) fn compile_unchecked(input: &str) -> Result<String, CodegenError> {
+    LLVMCodegen::new("test".to_string()).compile_program(&parse_source(input))
+}
@@ -0,0 +1,148 @@
+//! Sections of the LLVM module being built.
+//!
+//! Codegen files each definition under the section it belongs to and the
+//! module text is assembled in a fixed order at the end, so type definitions
+//! and globals never land inside a function body and every external function
+//! is declared exactly once.
+
+use std::collections::HashSet;
+
+#[derive(Debug, Default)]
+pub struct ModuleBuilder {
+    name: String,
+    types: Vec<String>,
+    globals: Vec<String>,
+    strings: Vec<String>,
+    declarations: Vec<String>,
+    /// Symbols already declared, so repeated uses share one `declare`
+    declared: HashSet<String>,
+    functions: Vec<String>,
+}
+
+impl ModuleBuilder {
+    pub fn new(name: String) -> Self {
+        Self { name, ..Self::default() }
+    }
+
+    /// `%name = type { ... }`
+    pub fn define_type(&mut self, definition: String) {
+        self.types.push(definition);
+    }
+
+    /// Module-level variables and constant tables
+    pub fn add_global(&mut self, definition: String) {
+        self.globals.push(definition);
+    }
+
+    /// Symbol for the next anonymous string constant
+    pub fn string_symbol(&self) -> String {
+        format!("@.str.{}", self.strings.len())
+    }
+
+    /// Private character arrays backing string literals and constants
+    pub fn add_string(&mut self, definition: String) {
+        self.strings.push(definition);
+    }
+
+    /// A NUL-terminated C string global for the runtime's own messages, as an `i8*` constant
+    pub fn c_string(&mut self, text: &str) -> String {
+        let symbol = self.string_symbol();
+        let length = text.len() + 1;
+        self.add_string(format!(
+            "{} = private unnamed_addr constant [{} x i8] c\"{}\\00\", align 1",
+            symbol, length, escape_bytes(text)
+        ));
+        format!("getelementptr inbounds ([{} x i8], [{} x i8]* {}, i64 0, i64 0)", length, length, symbol)
+    }
+
+    /// A Coral string global named `symbol`, as a `%string*` constant.
+    ///
+    /// Constants share the runtime's layout: the byte length, then the UTF-8
+    /// bytes with a trailing NUL that the length does not count.
+    pub fn string_constant(&mut self, symbol: &str, text: &str) -> String {
+        let layout = format!("{{ i64, [{} x i8] }}", text.len() + 1);
+        self.add_string(format!(
+            "{} = private unnamed_addr constant {} {{ i64 {}, [{} x i8] c\"{}\\00\" }}, align 8",
+            symbol, layout, text.len(), text.len() + 1, escape_bytes(text)
+        ));
+        format!("bitcast ({}* {} to %string*)", layout, symbol)
+    }
+
+    /// Declare an external function unless its symbol is already declared
+    pub fn declare(&mut self, declaration: &str) {
+        let symbol = declaration.split('@').nth(1)
+            .and_then(|rest| rest.split('(').next())
+            .unwrap_or(declaration);
+        if self.declared.insert(symbol.to_string()) {
+            self.declarations.push(declaration.to_string());
+        }
+    }
+
+    /// A complete `define ... { ... }` body
+    pub fn add_function(&mut self, body: String) {
+        self.functions.push(body);
+    }
+
+    /// The module text: types, globals, strings, declarations, then functions
+    pub fn finish(&self) -> String {
+        let mut ir = format!("; ModuleID = '{}'\n", self.name);
+        for section in [&self.types, &self.globals, &self.strings, &self.declarations] {
+            if section.is_empty() {
+                continue;
+            }
+            ir.push('\n');
+            for line in section {
+                ir.push_str(line);
+                ir.push('\n');
+            }
+        }
+        for function in &self.functions {
+            ir.push('\n');
+            ir.push_str(function);
+        }
+        ir
+    }
+}
+
+/// `text` as the body of an LLVM `c"..."` array: printable ASCII as is, every
+/// other byte of its UTF-8 encoding as a `\XX` escape
+fn escape_bytes(text: &str) -> String {
+    text.bytes().map(|byte| match byte {
+        b'"' | b'\\' => format!("\\{:02X}", byte),
+        0x20..=0x7e => (byte as char).to_string(),
+        _ => format!("\\{:02X}", byte),
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_and_declarations() {
+        let mut module = ModuleBuilder::new("test".to_string());
+        module.add_function("define void @f() {\nentry:\n  ret void\n}\n".to_string());
+        module.declare("declare i8* @list_new()");
+        module.define_type("%point = type { i64, i64 }".to_string());
+        module.declare("declare i8* @list_new()");
+        module.add_string("@.str.1 = private unnamed_addr constant [1 x i8] c\"\\00\", align 1".to_string());
+
+        let ir = module.finish();
+        assert_eq!(ir.matches("declare").count(), 1);
+        let type_at = ir.find("%point = type").unwrap();
+        let string_at = ir.find("@.str.1").unwrap();
+        let declare_at = ir.find("declare").unwrap();
+        let define_at = ir.find("define").unwrap();
+        assert!(type_at < string_at && string_at < declare_at && declare_at < define_at);
+    }
+
+    #[test]
+    fn test_string_constants_are_length_prefixed_utf8() {
+        let mut module = ModuleBuilder::new("test".to_string());
+        let value = module.string_constant("@.str.0", "h\u{e9} \"x\"\n");
+        assert_eq!(value, "bitcast ({ i64, [9 x i8] }* @.str.0 to %string*)");
+        assert!(module.finish().contains(
+            "@.str.0 = private unnamed_addr constant { i64, [9 x i8] } { i64 8, [9 x i8] c\"h\\C3\\A9 \\22x\\22\\0A\\00\" }, align 8"
+        ));
+    }
+}
@@ -0,0 +1,101 @@
+//! Conversions between sized numeric types.
+//!
+//! The resolver only lets integers widen to wider integers or to floats, and
+//! floats to wider floats; anything else has to be spelled with `as`. Both
+//! paths end up here. Constants are retyped in place where the value fits, so
+//! `x + 1` on an `i32` stays a single `add i32`.
+
+use crate::codegen::types::infer_to_llvm_type;
+use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::InferType;
+
+impl LLVMCodegen {
+    /// Convert a numeric value to `target`, emitting `sext`/`trunc`/`sitofp`/... as needed
+    pub fn convert_numeric(&mut self, value: LLVMValue, target: &InferType) -> Result<LLVMValue, CodegenError> {
+        if value.type_info == *target {
+            return Ok(value);
+        }
+        if let Some(constant) = retype_constant(&value, target) {
+            return Ok(constant);
+        }
+
+        let instruction = match (&value.type_info, target) {
+            (InferType::Bool, to) if to.is_integer() => "zext",
+            (from, to) => match (from.int_width(), from.float_width(), to.int_width(), to.float_width()) {
+                (Some(from), _, Some(to), _) => if from < to { "sext" } else { "trunc" },
+                (Some(_), _, _, Some(_)) => "sitofp",
+                (_, Some(_), Some(_), _) => "fptosi",
+                (_, Some(from), _, Some(to)) => if from < to { "fpext" } else { "fptrunc" },
+                _ => return Err(CodegenError::InvalidOperation(format!(
+                    "Cannot convert {} to {}",
+                    self.type_to_string(&value.type_info),
+                    self.type_to_string(target)
+                ))),
+            },
+        };
+
+        let llvm_type = infer_to_llvm_type(target);
+        let result = self.next_temp();
+        self.emit(&format!("  %{} = {} {} {} to {}", result, instruction, value.llvm_type, value.value_id, llvm_type));
+        Ok(LLVMValue {
+            type_info: target.clone(),
+            llvm_type,
+            value_id: format!("%{}", result),
+        })
+    }
+
+    /// Bring two arithmetic operands to a common type. A constant adopts the
+    /// other side's type; otherwise the narrower operand is widened.
+    pub(crate) fn unify_operands(&mut self, left: LLVMValue, right: LLVMValue) -> Result<(LLVMValue, LLVMValue), CodegenError> {
+        if left.type_info == right.type_info || !left.type_info.is_numeric() || !right.type_info.is_numeric() {
+            return Ok((left, right));
+        }
+        if let Some(right) = retype_constant(&right, &left.type_info) {
+            return Ok((left, right));
+        }
+        if let Some(left) = retype_constant(&left, &right.type_info) {
+            return Ok((left, right));
+        }
+        if left.type_info.widens_to(&right.type_info) {
+            let target = right.type_info.clone();
+            Ok((self.convert_numeric(left, &target)?, right))
+        } else {
+            let target = left.type_info.clone();
+            let right = self.convert_numeric(right, &target)?;
+            Ok((left, right))
+        }
+    }
+}
+
+/// LLVM spelling of a floating-point constant. The hex form is exact; `float`
+/// constants are written as the double nearest to their single-precision value.
+pub(crate) fn float_constant(value: f64, ty: &InferType) -> String {
+    let value = if *ty == InferType::F32 { value as f32 as f64 } else { value };
+    format!("0x{:016X}", value.to_bits())
+}
+
+/// A literal operand retyped to `target`, if it is a constant that fits
+fn retype_constant(value: &LLVMValue, target: &InferType) -> Option<LLVMValue> {
+    let value_id = if let Ok(int) = value.value_id.parse::<i64>() {
+        match target.int_width() {
+            Some(64) => int.to_string(),
+            Some(width) if (-(1i64 << (width - 1))..(1i64 << (width - 1))).contains(&int) => int.to_string(),
+            Some(_) => return None,
+            None if target.is_float() => float_constant(int as f64, target),
+            None => return None,
+        }
+    } else if let Some(bits) = value.value_id.strip_prefix("0x") {
+        if !target.is_float() {
+            return None;
+        }
+        let float = f64::from_bits(u64::from_str_radix(bits, 16).ok()?);
+        float_constant(float, target)
+    } else {
+        return None;
+    };
+    Some(LLVMValue {
+        type_info: target.clone(),
+        llvm_type: infer_to_llvm_type(target),
+        value_id,
+    })
+}
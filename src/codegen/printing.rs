@@ -0,0 +1,310 @@
+//! Turning values into strings, for `log` and string interpolation.
+//!
+//! Scalars are formatted by the runtime. Every object gets a generated
+//! `@T__to_string`, which calls the object's `as string` when it has one and
+//! otherwise lists its fields. Enums and results get a generated formatter per
+//! type, and trait values call the `to_string` their vtable ends with. Lists
+//! and maps are formatted by the runtime too, which calls back into a generated
+//! formatter for each element type, since their elements are untyped words.
+
+use crate::ast::{conversion_method_name, Literal, Type};
+use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::InferType;
+
+impl LLVMCodegen {
+    /// `value` as a string
+    pub(crate) fn compile_to_string(&mut self, value: LLVMValue) -> Result<LLVMValue, CodegenError> {
+        let string_type = infer_to_llvm_type(&InferType::String);
+        let call = match value.type_info.clone() {
+            InferType::String => return Ok(value),
+            ty if ty.is_integer() => {
+                let value = self.convert_numeric(value, &InferType::Int)?;
+                self.module.declare("declare %string* @string_from_int(i64)");
+                format!("call %string* @string_from_int(i64 {})", value.value_id)
+            }
+            ty if ty.is_float() => {
+                let value = self.convert_numeric(value, &InferType::Float)?;
+                self.module.declare("declare %string* @string_from_float(double)");
+                format!("call %string* @string_from_float(double {})", value.value_id)
+            }
+            InferType::Bool => {
+                self.module.declare("declare %string* @string_from_bool(i1 zeroext)");
+                format!("call %string* @string_from_bool(i1 zeroext {})", value.value_id)
+            }
+            InferType::Unit => return self.compile_literal(&Literal::String("()".to_string())),
+            InferType::Function { .. } => return self.compile_literal(&Literal::String("<function>".to_string())),
+            InferType::List(element_type) => {
+                let formatter = self.element_formatter(&element_type)?;
+                self.module.declare("declare %string* @string_from_list(i8*, %string* (i8*)*)");
+                format!("call %string* @string_from_list(i8* {}, %string* (i8*)* {})", value.value_id, formatter)
+            }
+            InferType::Map(key_type, value_type) => {
+                let key_formatter = self.element_formatter(&key_type)?;
+                let value_formatter = self.element_formatter(&value_type)?;
+                self.module.declare("declare %string* @string_from_map(i8*, %string* (i8*)*, %string* (i8*)*)");
+                format!(
+                    "call %string* @string_from_map(i8* {}, %string* (i8*)* {}, %string* (i8*)* {})",
+                    value.value_id, key_formatter, value_formatter
+                )
+            }
+            InferType::Object { name, .. } => format!(
+                "call %string* @{}({} {})", self.method_symbol(&name, "to_string"), value.llvm_type, value.value_id
+            ),
+            ty @ (InferType::Enum { .. } | InferType::Result(..)) => {
+                let formatter = self.value_formatter(&ty)?;
+                format!("call %string* {}({} {})", formatter, value.llvm_type, value.value_id)
+            }
+            InferType::Trait { methods, .. } => {
+                let data = self.next_temp();
+                self.emit(&format!("  %{} = extractvalue {} {}, 0", data, value.llvm_type, value.value_id));
+                let vtable = self.next_temp();
+                self.emit(&format!("  %{} = extractvalue {} {}, 1", vtable, value.llvm_type, value.value_id));
+                let slot_ptr = self.next_temp();
+                self.emit(&format!("  %{} = getelementptr inbounds i8*, i8** %{}, i64 {}", slot_ptr, vtable, methods.len()));
+                let raw_fn = self.next_temp();
+                self.emit(&format!("  %{} = load i8*, i8** %{}", raw_fn, slot_ptr));
+                let fn_ptr = self.next_temp();
+                self.emit(&format!("  %{} = bitcast i8* %{} to %string* (i8*)*", fn_ptr, raw_fn));
+                format!("call %string* %{}(i8* %{})", fn_ptr, data)
+            }
+            other => {
+                let type_name = self.type_to_string(&other);
+                return Err(CodegenError::UnsupportedFeature(format!("Cannot format values of type {}", type_name)));
+            }
+        };
+        let result_temp = self.next_temp();
+        self.emit(&format!("  %{} = {}", result_temp, call));
+        Ok(LLVMValue { type_info: InferType::String, llvm_type: string_type, value_id: format!("%{}", result_temp) })
+    }
+
+    /// `log value`: print any value on its own line
+    pub fn compile_log(&mut self, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
+        let [arg] = args else {
+            return Err(CodegenError::InvalidOperation(format!("log takes one value, got {}", args.len())));
+        };
+        let value = self.compile_expression(arg)?;
+        let text = self.compile_to_string(value)?;
+        self.module.declare("declare void @print_string(%string*)");
+        self.emit(&format!("  call void @print_string(%string* {})", text.value_id));
+        Ok(LLVMValue { type_info: InferType::Unit, llvm_type: LLVMType::Void, value_id: String::new() })
+    }
+
+    /// The function the runtime calls to format a collection element of
+    /// `element_type`, emitted once per type
+    fn element_formatter(&mut self, element_type: &InferType) -> Result<String, CodegenError> {
+        let key = self.type_to_string(element_type);
+        if let Some(symbol) = self.formatters.get(&key) {
+            return Ok(symbol.clone());
+        }
+        let symbol = format!("@__coral_format.{}", self.formatters.len());
+        self.formatters.insert(key, symbol.clone());
+
+        let frame = self.begin_function(&format!("define internal %string* {}(i8* %word) {{", symbol), InferType::String);
+        let element = self.unpack_word("%word", element_type)?;
+        let text = self.compile_to_string(element)?;
+        self.emit(&format!("  ret %string* {}", text.value_id));
+        self.finish_function(frame);
+        Ok(symbol)
+    }
+
+    /// The function formatting an enum or result value, emitted once per type:
+    /// `@E__to_string` for an enum, an internal formatter for a result
+    fn value_formatter(&mut self, ty: &InferType) -> Result<String, CodegenError> {
+        let key = self.type_to_string(ty);
+        if let Some(symbol) = self.value_formatters.get(&key) {
+            return Ok(symbol.clone());
+        }
+        let symbol = match ty {
+            InferType::Enum { name, .. } => format!("@{}", self.method_symbol(name, "to_string")),
+            _ => format!("@__coral_to_string.{}", self.value_formatters.len()),
+        };
+        self.value_formatters.insert(key, symbol.clone());
+
+        let llvm_type = infer_to_llvm_type(ty);
+        let frame = self.begin_function(
+            &format!("define internal %string* {}({} %value) {{", symbol, llvm_type),
+            InferType::String,
+        );
+        match ty {
+            InferType::Enum { variants, .. } => self.format_variants(ty, variants)?,
+            InferType::Result(ok_type, err_type) => self.format_result(&llvm_type, ok_type, err_type)?,
+            _ => unreachable!("only enums and results get value formatters"),
+        }
+        self.finish_function(frame);
+        Ok(symbol)
+    }
+
+    /// Body of an enum formatter: a branch per tag returning the variant's
+    /// name, followed by its payload in parentheses when it has one
+    fn format_variants(&mut self, enum_type: &InferType, variants: &[(String, Vec<InferType>)]) -> Result<(), CodegenError> {
+        let enum_llvm_type = infer_to_llvm_type(enum_type);
+        let slot = self.alloca(&enum_llvm_type);
+        self.emit(&format!("  store {} %value, {}* %{}", enum_llvm_type, enum_llvm_type, slot));
+        let tag_ptr = self.next_temp();
+        self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 0", tag_ptr, enum_llvm_type, enum_llvm_type, slot));
+        let tag = self.next_temp();
+        self.emit(&format!("  %{} = load i32, i32* %{}", tag, tag_ptr));
+
+        let labels: Vec<usize> = variants.iter().map(|_| self.next_label()).collect();
+        let unknown_label = self.next_label();
+        let cases = labels.iter().enumerate()
+            .map(|(tag, label)| format!("i32 {}, label %L{}", tag, label))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.emit(&format!("  switch i32 %{}, label %L{} [ {} ]", tag, unknown_label, cases));
+
+        let storage_type = self.enum_storage_type(enum_type);
+        for ((name, payload), label) in variants.iter().zip(labels) {
+            self.start_block(label);
+            if payload.is_empty() {
+                let text = self.compile_literal(&Literal::String(name.clone()))?;
+                self.emit(&format!("  ret %string* {}", text.value_id));
+                continue;
+            }
+            let payload_llvm_type = LLVMType::Struct(payload.iter().map(infer_to_llvm_type).collect());
+            let storage_ptr = self.next_temp();
+            self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 1", storage_ptr, enum_llvm_type, enum_llvm_type, slot));
+            let payload_ptr = self.next_temp();
+            self.emit(&format!("  %{} = bitcast {}* %{} to {}*", payload_ptr, storage_type, storage_ptr, payload_llvm_type));
+
+            let mut text = self.compile_literal(&Literal::String(format!("{}(", name)))?;
+            for (index, payload_type) in payload.iter().enumerate() {
+                if index > 0 {
+                    let separator = self.compile_literal(&Literal::String(", ".to_string()))?;
+                    text = self.compile_string_concat(&text, &separator);
+                }
+                let field_ptr = self.next_temp();
+                self.emit(&format!("  %{} = getelementptr inbounds {}, {}* %{}, i32 0, i32 {}", field_ptr, payload_llvm_type, payload_llvm_type, payload_ptr, index));
+                let llvm_type = infer_to_llvm_type(payload_type);
+                let field_val = self.next_temp();
+                self.emit(&format!("  %{} = load {}, {}* %{}", field_val, llvm_type, llvm_type, field_ptr));
+                let field_text = self.compile_to_string(LLVMValue {
+                    type_info: payload_type.clone(),
+                    llvm_type,
+                    value_id: format!("%{}", field_val),
+                })?;
+                text = self.compile_string_concat(&text, &field_text);
+            }
+            let close = self.compile_literal(&Literal::String(")".to_string()))?;
+            let text = self.compile_string_concat(&text, &close);
+            self.emit(&format!("  ret %string* {}", text.value_id));
+        }
+        // Every tag is a declared variant
+        self.start_block(unknown_label);
+        self.emit("  unreachable");
+        Ok(())
+    }
+
+    /// Body of a result formatter: the value on success, `err` and the
+    /// payload on failure
+    fn format_result(&mut self, result_llvm_type: &LLVMType, ok_type: &InferType, err_type: &InferType) -> Result<(), CodegenError> {
+        let failed = self.next_temp();
+        self.emit(&format!("  %{} = extractvalue {} %value, 0", failed, result_llvm_type));
+        let ok_label = self.next_label();
+        let err_label = self.next_label();
+        self.emit(&format!("  br i1 %{}, label %L{}, label %L{}", failed, err_label, ok_label));
+
+        for (label, index, side_type) in [(ok_label, 1, ok_type), (err_label, 2, err_type)] {
+            self.start_block(label);
+            let side = self.next_temp();
+            self.emit(&format!("  %{} = extractvalue {} %value, {}", side, result_llvm_type, index));
+            let mut text = self.compile_to_string(LLVMValue {
+                type_info: side_type.clone(),
+                llvm_type: infer_to_llvm_type(side_type),
+                value_id: format!("%{}", side),
+            })?;
+            if label == err_label {
+                let prefix = self.compile_literal(&Literal::String("err ".to_string()))?;
+                text = self.compile_string_concat(&prefix, &text);
+            }
+            self.emit(&format!("  ret %string* {}", text.value_id));
+        }
+        Ok(())
+    }
+
+    /// Emit `@T__to_string`: the object's `as string` if it has one, else
+    /// `name(field: value, ...)`, under the name the source gives the object
+    pub(crate) fn emit_to_string(&mut self, type_name: &str, name: &str) -> Result<(), CodegenError> {
+        let object_type = self.lookup_object_type(type_name)
+            .ok_or_else(|| CodegenError::UndefinedVariable(type_name.to_string()))?;
+        let this = LLVMValue {
+            type_info: object_type.clone(),
+            llvm_type: infer_to_llvm_type(&object_type),
+            value_id: "%self".to_string(),
+        };
+        let frame = self.begin_function(
+            &format!("define %string* @{}({} %self) {{", self.method_symbol(type_name, "to_string"), this.llvm_type),
+            InferType::String,
+        );
+
+        let conversion = conversion_method_name(&Type::String);
+        let has_conversion = matches!(&object_type, InferType::Object { methods, .. } if methods.contains_key(&conversion));
+        let text = if has_conversion {
+            let text = self.next_temp();
+            self.emit(&format!(
+                "  %{} = call %string* @{}({} %self)", text, self.method_symbol(type_name, &conversion), this.llvm_type
+            ));
+            format!("%{}", text)
+        } else {
+            let fields = self.object_fields.get(type_name).cloned().unwrap_or_default();
+            let mut text = self.compile_literal(&Literal::String(format!("{}(", name)))?;
+            for (index, field) in fields.iter().enumerate() {
+                let separator = if index == 0 { "" } else { ", " };
+                let label = self.compile_literal(&Literal::String(format!("{}{}: ", separator, field.name)))?;
+                text = self.compile_string_concat(&text, &label);
+                let field_ptr = self.field_pointer(&this, &field.name)?;
+                let field_val = self.next_temp();
+                self.emit(&format!(
+                    "  %{} = load {}, {}* {}", field_val, field_ptr.llvm_type, field_ptr.llvm_type, field_ptr.value_id
+                ));
+                let field_text = self.compile_to_string(LLVMValue { value_id: format!("%{}", field_val), ..field_ptr })?;
+                text = self.compile_string_concat(&text, &field_text);
+            }
+            let close = self.compile_literal(&Literal::String(")".to_string()))?;
+            self.compile_string_concat(&text, &close).value_id
+        };
+        self.emit(&format!("  ret %string* {}", text));
+        self.finish_function(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::codegen::compile_source;
+
+    #[test]
+    fn test_log_formats_any_value() {
+        let ir = compile_source(
+            "object point\n    x: i64\n    as string\n        'at {x}'\nobject pair\n    a: i64\n    b: bool\nfn main()\n    log point.make(1)\n    pairs is [pair.make(2, yes)]\n    log pairs\n    log {\"k\": 1.5}\n"
+        ).unwrap();
+        // Objects convert through `as string` when they have one
+        assert!(ir.contains("define %string* @point__to_string(%point* %self)"));
+        assert!(ir.contains("call %string* @point__as_string(%point* %self)"));
+        assert!(ir.contains("define %string* @pair__to_string(%pair* %self)"));
+        assert!(ir.contains("call %string* @string_from_bool(i1 zeroext %"));
+        // Collection elements are formatted through one callback per element type
+        assert!(ir.contains("define internal %string* @__coral_format.0(i8* %word)"));
+        assert!(ir.contains("call %string* @pair__to_string(%pair* %"));
+        assert!(ir.contains("call %string* @string_from_list(i8* %"));
+        assert!(ir.contains("call %string* @string_from_map(i8* %"));
+        assert!(ir.contains("call void @print_string(%string* %"));
+    }
+
+    #[test]
+    fn test_log_formats_enums_results_and_trait_values() {
+        let ir = compile_source(
+            "enum shape\n    rect(i64, i64)\n    dot\ntrait named\n    label() -> string\nobject city is named\n    title: string\n    label() -> string:\n        return title\nfn half(n: i64) -> i64 ! string\n    if n lt 0\n        return err 'negative'\n    return n / 2\nfn show(thing: named)\n    log thing\nfn main()\n    log shape.rect(2, 3)\n    log half(8)\n"
+        ).unwrap();
+        // Enums switch on the tag and format each variant's payload
+        assert!(ir.contains("define internal %string* @shape__to_string(%shape %value)"));
+        assert!(ir.contains("switch i32 %"));
+        // Results format the value, or `err` and the payload
+        assert!(ir.contains("define internal %string* @__coral_to_string.1({ i1, i64, %string* } %value)"));
+        assert!(ir.contains("insertvalue { i1, i64, %string* } undef, i1 false, 0"));
+        // Trait values format through the vtable slot after the trait's methods
+        assert!(ir.contains("[2 x i8*] [i8* bitcast (%string* (%city*)* @city__label to i8*), i8* bitcast (%string* (%city*)* @city__to_string to i8*)]"));
+        assert!(ir.contains(" to %string* (i8*)*"));
+    }
+}
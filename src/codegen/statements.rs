@@ -1,19 +1,34 @@
-use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::codegen::{Binding, CodegenError, LLVMCodegen, LLVMValue};
 use crate::ast::{Stmt, StmtKind, Expr, Type, Parameter, ExprKind, ObjectMethod};
 use crate::resolver::InferType;
 use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::entry::function_symbol;
 
 impl LLVMCodegen {
     pub fn compile_statement(&mut self, stmt: &Stmt) -> Result<Option<LLVMValue>, CodegenError> {
         match &stmt.kind {
-            StmtKind::Function { name, params, return_type, body } => {
-                self.compile_function_definition(name, params, return_type.as_ref(), body)?;
+            StmtKind::Function { name, params, return_type, body, .. } => {
+                if crate::ast::signature_type_params(params, return_type.as_ref()).is_empty() {
+                    self.compile_function_definition(function_symbol(name), params, return_type.as_ref(), body)?;
+                }
+                // Generic functions are specialized per call site instead
                 Ok(None)
             }
             StmtKind::Store { name, fields, methods } => {
                 self.compile_store_definition(name, fields, methods)?;
                 Ok(None)
             }
+            StmtKind::Object { name, type_params, methods, .. } => {
+                // Generic object methods are emitted with each specialization
+                if type_params.is_empty() {
+                    self.compile_object_definition(name, methods)?;
+                }
+                Ok(None)
+            }
+            StmtKind::Enum { .. } | StmtKind::Trait { .. } => {
+                // Layouts and vtables already emitted before statements
+                Ok(None)
+            }
             StmtKind::Assignment { target, value } => {
                 self.compile_assignment(target, value)?;
                 Ok(None)
@@ -25,17 +40,30 @@ impl LLVMCodegen {
             StmtKind::Return(expr_opt) => {
                 if let Some(expr) = expr_opt {
                     let return_value = self.compile_expression(expr)?;
-                    self.emit(&format!(
+                    // A lambda's first `return` settles its return type
+                    if self.current_return_type == Some(InferType::Unknown) {
+                        self.current_return_type = Some(return_value.type_info.clone());
+                    }
+                    let return_value = self.coerce_return(return_value)?;
+                    self.terminate(&format!(
                         "  ret {} {}",
                         return_value.llvm_type,
                         return_value.value_id
                     ));
                     Ok(Some(return_value))
                 } else {
-                    self.emit("  ret void");
+                    self.terminate("  ret void");
                     Ok(None)
                 }
             }
+            StmtKind::Break | StmtKind::Continue => {
+                let Some(&(continue_label, break_label)) = self.loop_targets.last() else {
+                    return Err(CodegenError::InvalidOperation("break or continue outside of a loop".to_string()));
+                };
+                let target = if matches!(stmt.kind, StmtKind::Break) { break_label } else { continue_label };
+                self.terminate(&format!("  br label %L{}", target));
+                Ok(None)
+            }
             StmtKind::If { condition, then_branch, else_branch } => {
                 self.compile_if_statement(condition, then_branch, else_branch.as_deref())?;
                 Ok(None)
@@ -60,15 +88,15 @@ impl LLVMCodegen {
 
     pub fn compile_store_definition(&mut self, name: &str, _fields: &[crate::ast::Field], methods: &[ObjectMethod]) -> Result<(), CodegenError> {
         let struct_name = format!("%{}", name);
-        self.emit(&format!("@{} = common global {} zeroinitializer, align 8", name, struct_name));
+        self.module.add_global(format!("@{} = common global {} zeroinitializer, align 8", name, struct_name));
 
         for method in methods {
             self.compile_function_definition(&format!("{}_{}", name, method.name), &method.params, method.return_type.as_ref(), &method.body)?;
         }
 
         // Declare runtime functions for store operations
-        self.emit("declare void @store_save(i8*, i8*)");
-        self.emit("declare i8* @store_load(i8*)");
+        self.module.declare("declare void @store_save(i8*, i8*)");
+        self.module.declare("declare i8* @store_load(i8*)");
 
         Ok(())
     }
@@ -79,18 +107,16 @@ impl LLVMCodegen {
         let merge_label = self.next_label();
 
         self.emit(&format!("  br label %L{}", body_label));
-        self.emit(&format!("L{}:", body_label));
+        self.start_block(body_label);
 
-        for stmt in body {
-            self.compile_statement(stmt)?;
-        }
+        self.compile_loop_body(body, cond_label, merge_label)?;
         self.emit(&format!("  br label %L{}", cond_label));
 
-        self.emit(&format!("L{}:", cond_label));
+        self.start_block(cond_label);
         let cond_val = self.compile_expression(condition)?;
         self.emit(&format!("  br i1 {}, label %L{}, label %L{}", cond_val.value_id, merge_label, body_label));
 
-        self.emit(&format!("L{}:", merge_label));
+        self.start_block(merge_label);
         Ok(())
     }
 
@@ -98,9 +124,9 @@ impl LLVMCodegen {
         let iterable_val = self.compile_expression(iterable)?;
 
         // Declare runtime functions for iteration
-        self.emit("declare i8* @iterator_new(i8*)");
-        self.emit("declare i1 @iterator_next(i8*)");
-        self.emit("declare i8* @iterator_get_value(i8*)");
+        self.module.declare("declare i8* @iterator_new(i8*)");
+        self.module.declare("declare i1 @iterator_next(i8*)");
+        self.module.declare("declare i8* @iterator_get_value(i8*)");
 
         let iterator_ptr = self.next_temp();
         self.emit(&format!("  %{} = call i8* @iterator_new({} {})", iterator_ptr, iterable_val.llvm_type, iterable_val.value_id));
@@ -110,139 +136,258 @@ impl LLVMCodegen {
         let loop_end_label = self.next_label();
 
         self.emit(&format!("  br label %L{}", loop_cond_label));
-        self.emit(&format!("L{}:", loop_cond_label));
+        self.start_block(loop_cond_label);
 
         let has_next_ptr = self.next_temp();
         self.emit(&format!("  %{} = call i1 @iterator_next(i8* %{})", has_next_ptr, iterator_ptr));
         self.emit(&format!("  br i1 %{}, label %L{}, label %L{}", has_next_ptr, loop_body_label, loop_end_label));
 
-        self.emit(&format!("L{}:", loop_body_label));
+        self.start_block(loop_body_label);
 
         let value_ptr = self.next_temp();
         self.emit(&format!("  %{} = call i8* @iterator_get_value(i8* %{})", value_ptr, iterator_ptr));
         
-        // The '$' variable holds the current iteration value.
-        // This is a simplified approach; a real implementation would need to know the type of the value.
-        self.symbols.define_variable("$".to_string(), LLVMValue {
-            type_info: InferType::Unknown, // This should be the element type of the iterable
-            llvm_type: LLVMType::Pointer(Box::new(LLVMType::Int(8))),
-            value_id: format!("%{}", value_ptr),
-        });
+        // The '$' variable holds the current iteration value, typed as the
+        // list's elements when the iterable is a list
+        let current = match &iterable_val.type_info {
+            InferType::List(element_type) => self.unpack_word(&format!("%{}", value_ptr), element_type)?,
+            _ => LLVMValue {
+                type_info: InferType::Unknown,
+                llvm_type: LLVMType::Pointer(Box::new(LLVMType::Int(8))),
+                value_id: format!("%{}", value_ptr),
+            },
+        };
+        self.symbols.push_scope();
+        self.symbols.define_variable("$".to_string(), current);
 
-        for stmt in body {
-            self.compile_statement(stmt)?;
-        }
+        self.compile_loop_body(body, loop_cond_label, loop_end_label)?;
+        self.symbols.pop_scope();
 
         self.emit(&format!("  br label %L{}", loop_cond_label));
-        self.emit(&format!("L{}:", loop_end_label));
+        self.start_block(loop_end_label);
 
         Ok(())
     }
 
+    /// Compile a loop body where `continue` jumps to `L<continue_label>` and
+    /// `break` to `L<break_label>`
+    fn compile_loop_body(&mut self, body: &[Stmt], continue_label: usize, break_label: usize) -> Result<(), CodegenError> {
+        self.loop_targets.push((continue_label, break_label));
+        let result = body.iter().try_for_each(|stmt| self.compile_statement(stmt).map(|_| ()));
+        self.loop_targets.pop();
+        result
+    }
+
     pub fn compile_while_statement(&mut self, condition: &Expr, body: &[Stmt]) -> Result<(), CodegenError> {
         let cond_label = self.next_label();
         let body_label = self.next_label();
         let merge_label = self.next_label();
 
         self.emit(&format!("  br label %L{}", cond_label));
-        self.emit(&format!("L{}:", cond_label));
+        self.start_block(cond_label);
 
         let cond_val = self.compile_expression(condition)?;
         self.emit(&format!("  br i1 {}, label %L{}, label %L{}", cond_val.value_id, body_label, merge_label));
 
-        self.emit(&format!("L{}:", body_label));
-        for stmt in body {
-            self.compile_statement(stmt)?;
-        }
+        self.start_block(body_label);
+        self.compile_loop_body(body, cond_label, merge_label)?;
         self.emit(&format!("  br label %L{}", cond_label));
 
-        self.emit(&format!("L{}:", merge_label));
+        self.start_block(merge_label);
         Ok(())
     }
 
+    pub fn compile_object_definition(&mut self, name: &str, methods: &[ObjectMethod]) -> Result<(), CodegenError> {
+        let mut initializer = None;
+        for method in methods {
+            // A user-written `make` runs inside the generated constructor
+            if method.name == "make" {
+                let symbol = format!("{}.body", self.method_symbol(name, "make"));
+                self.compile_function_with_receiver(&symbol, Some(name), &method.params, None, &method.body)?;
+                initializer = Some(symbol);
+                continue;
+            }
+            let symbol = self.method_symbol(name, &method.name);
+            self.compile_function_with_receiver(&symbol, Some(name), &method.params, method.return_type.as_ref(), &method.body)?;
+        }
+        self.emit_constructor(name, initializer.as_deref())?;
+        self.emit_to_string(name, name)
+    }
+
     pub fn compile_function_definition(&mut self, name: &str, params: &[Parameter], return_type: Option<&Type>, body: &[Stmt]) -> Result<(), CodegenError> {
-        let mut param_llvm_types = Vec::new();
-        let mut param_names = Vec::new();
+        self.compile_function_with_receiver(name, None, params, return_type, body)
+    }
 
+    /// The callable value of a function emitted as `@symbol`
+    pub(crate) fn function_value(&self, symbol: &str, params: &[Parameter], return_type: Option<&Type>) -> LLVMValue {
+        let param_types: Vec<InferType> = params.iter().map(|p| self.ast_type_to_infer_type(&p.type_)).collect();
+        let return_type = return_type.map(|ty| self.ast_type_to_infer_type(ty)).unwrap_or(InferType::Unit);
+        LLVMValue {
+            llvm_type: LLVMType::Pointer(Box::new(LLVMType::Function {
+                ret: Box::new(infer_to_llvm_type(&return_type)),
+                params: param_types.iter().map(infer_to_llvm_type).collect(),
+            })),
+            type_info: InferType::Function {
+                params: param_types,
+                return_type: Box::new(return_type),
+                effects: Default::default(),
+            },
+            value_id: format!("@{}", symbol),
+        }
+    }
+
+    /// Parameters live in slots like any other local, so they can be reassigned
+    pub(crate) fn bind_params(&mut self, params: &[Parameter], param_types: Vec<InferType>) {
+        for (param, param_type) in params.iter().zip(param_types) {
+            let llvm_type = infer_to_llvm_type(&param_type);
+            let slot = self.alloca(&llvm_type);
+            self.emit(&format!("  store {} %{}.arg, {}* %{}", llvm_type, param.name, llvm_type, slot));
+            self.symbols.define_slot(param.name.clone(), LLVMValue {
+                type_info: param_type,
+                llvm_type,
+                value_id: format!("%{}", slot),
+            });
+        }
+    }
+
+    /// End a function body that fell through; `trailing` is the value of its final expression
+    pub(crate) fn emit_implicit_return(&mut self, return_llvm_type: &LLVMType, trailing: Option<LLVMValue>) -> Result<(), CodegenError> {
+        if *return_llvm_type == LLVMType::Void {
+            self.emit("  ret void");
+            return Ok(());
+        }
+        match trailing {
+            // A trailing expression is the function's value
+            Some(value) => {
+                let value = self.coerce_return(value)?;
+                self.emit(&format!("  ret {} {}", value.llvm_type, value.value_id));
+            }
+            // Every path returned already; semantic analysis checks this
+            None => self.emit("  unreachable"),
+        }
+        Ok(())
+    }
+
+    /// Emit a function; methods take their object as a leading `%self` pointer
+    pub(crate) fn compile_function_with_receiver(&mut self, name: &str, receiver: Option<&str>, params: &[Parameter], return_type: Option<&Type>, body: &[Stmt]) -> Result<(), CodegenError> {
         let inferred_return_type = if let Some(ty) = return_type {
             self.ast_type_to_infer_type(ty)
         } else {
             InferType::Unit // Default to Unit if no return type specified
         };
-
-        for param in params {
-            let infer_type = self.ast_type_to_infer_type(&param.type_);
-            let llvm_type = infer_to_llvm_type(&infer_type);
-            param_llvm_types.push(llvm_type.clone());
-            param_names.push(param.name.clone());
-        }
-
         let return_llvm_type = infer_to_llvm_type(&inferred_return_type);
+        let param_types: Vec<InferType> = params.iter().map(|p| self.ast_type_to_infer_type(&p.type_)).collect();
+        if let Some(param) = params.iter().zip(&param_types).find(|(_, ty)| **ty == InferType::Unknown).map(|(param, _)| param) {
+            return Err(CodegenError::UnsupportedFeature(format!(
+                "Cannot infer the type of parameter '{}' of {}", param.name, name
+            )));
+        }
 
-        self.emit(&format!(
-            "define {} @{}({}) {{",
-            return_llvm_type,
-            name,
-            param_llvm_types.iter()
-                .zip(param_names.iter())
-                .map(|(ty, name)| format!("{} %{}", ty, name))
-                .collect::<Vec<String>>()
-                .join(", ")
-        ));
-        self.emit("entry:");
+        // Register the function so later calls can find its signature
+        let function = self.function_value(name, params, return_type);
+        self.symbols.define_module_level(name.to_string(), Binding::Value(function));
+
+        // Incoming values are renamed so they cannot clash with the parameters' slots
+        let mut signature: Vec<String> = params.iter()
+            .zip(&param_types)
+            .map(|(param, ty)| format!("{} %{}.arg", infer_to_llvm_type(ty), param.name))
+            .collect();
+        if let Some(object_name) = receiver {
+            signature.insert(0, format!("%{}* %self", object_name));
+        }
 
-        for stmt in body {
-            self.compile_statement(stmt)?;
+        let frame = self.begin_function(
+            &format!("define {} @{}({}) {{", return_llvm_type, name, signature.join(", ")),
+            inferred_return_type,
+        );
+
+        if let Some(object_name) = receiver {
+            if let Some(object_type) = self.lookup_object_type(object_name) {
+                let this = LLVMValue {
+                    type_info: object_type,
+                    llvm_type: LLVMType::Pointer(Box::new(LLVMType::NamedStruct(object_name.to_string()))),
+                    value_id: "%self".to_string(),
+                };
+                // Bare field names read and write the receiver's fields
+                for field in self.object_fields.get(object_name).cloned().unwrap_or_default() {
+                    let slot = self.field_pointer(&this, &field.name)?;
+                    self.symbols.define_slot(field.name, slot);
+                }
+                self.symbols.define_variable("self".to_string(), this);
+            }
         }
+        self.bind_params(params, param_types);
 
-        if return_llvm_type == LLVMType::Void {
-            self.emit("  ret void");
-        } else {
-            self.emit(&format!("  ret {} undef", return_llvm_type));
+        let mut last_value = None;
+        for stmt in body {
+            last_value = self.compile_statement(stmt)?;
         }
-        self.emit("}");
+        let trailing = matches!(body.last().map(|stmt| &stmt.kind), Some(StmtKind::Expression(_)));
+        self.emit_implicit_return(&return_llvm_type, last_value.filter(|_| trailing))?;
+        self.finish_function(frame);
 
         Ok(())
     }
 
     pub fn compile_assignment(&mut self, target: &Expr, value: &Expr) -> Result<(), CodegenError> {
         let value_result = self.compile_expression(value)?;
+        if value_result.llvm_type == LLVMType::Void {
+            return Err(CodegenError::InvalidOperation("Cannot assign an expression that has no value".to_string()));
+        }
 
-        if let ExprKind::Identifier(var_name) = &target.kind {
-            if let Some(existing_var) = self.symbols.lookup_variable(var_name) {
-                if existing_var.llvm_type.to_string().ends_with("*") {
-                    self.emit(&format!(
-                        "  store {} {}, {} {}",
-                        value_result.llvm_type,
-                        value_result.value_id,
-                        existing_var.llvm_type,
-                        existing_var.value_id
-                    ));
-                } else {
-                    return Err(CodegenError::InvalidOperation(format!("Cannot reassign to non-pointer type: {}", existing_var.llvm_type)));
+        match &target.kind {
+            ExprKind::Identifier(var_name) => match self.symbols.lookup(var_name).cloned() {
+                Some(Binding::Slot(slot)) => self.store_into(&slot, value_result),
+                Some(Binding::Value(_)) => {
+                    Err(CodegenError::InvalidOperation(format!("Cannot assign to {}", var_name)))
+                }
+                None if self.module_level => {
+                    let global = self.define_global(var_name, &value_result);
+                    self.store_into(&global, value_result)
                 }
-            } else {
-                let alloca_temp = self.next_temp();
-                self.emit(&format!("  %{} = alloca {}", alloca_temp, value_result.llvm_type));
-                self.emit(&format!("  store {} {}, {}* %{}",
-                    value_result.llvm_type,
-                    value_result.value_id,
-                    value_result.llvm_type,
-                    alloca_temp
-                ));
-
-                self.symbols.define_variable(var_name.clone(), LLVMValue {
-                    type_info: value_result.type_info,
-                    llvm_type: LLVMType::Pointer(Box::new(value_result.llvm_type)),
-                    value_id: format!("%{}", alloca_temp),
-                });
+                None => {
+                    // Blocks share their function's scope, so a variable first
+                    // assigned in one branch is the same slot in the other
+                    let slot = self.alloca(&value_result.llvm_type);
+                    let slot = LLVMValue {
+                        type_info: value_result.type_info.clone(),
+                        llvm_type: value_result.llvm_type.clone(),
+                        value_id: format!("%{}", slot),
+                    };
+                    self.store_into(&slot, value_result)?;
+                    self.symbols.define_slot(var_name.clone(), slot);
+                    Ok(())
+                }
+            },
+            // `a.b.c is x` loads `a.b` and writes its field in place
+            ExprKind::FieldAccess { object, field } => {
+                let object_val = self.compile_expression(object)?;
+                let field_ptr = self.field_pointer(&object_val, field)?;
+                self.store_into(&field_ptr, value_result)
             }
-            Ok(())
-        } else {
-            Err(CodegenError::UnsupportedFeature("Complex assignment targets not yet supported".to_string()))
+            ExprKind::Index { object, index } => self.compile_element_store(object, index, value_result, &target.span),
+            // `xs(0) is 3` and `cfg('host') is 'x'` write an element
+            ExprKind::Call { callee, args } if args.len() == 1 => {
+                self.compile_element_store(callee, &args[0].value, value_result, &callee.span)
+            }
+            _ => Err(CodegenError::UnsupportedFeature("Unsupported assignment target".to_string())),
         }
     }
 
+    /// Store `value` through `pointer`, widening numerics to the pointee's type
+    fn store_into(&mut self, pointer: &LLVMValue, value: LLVMValue) -> Result<(), CodegenError> {
+        let value = if pointer.type_info.is_numeric() && value.type_info.is_numeric() {
+            self.convert_numeric(value, &pointer.type_info)?
+        } else {
+            value
+        };
+        self.emit(&format!(
+            "  store {} {}, {}* {}",
+            value.llvm_type, value.value_id, pointer.llvm_type, pointer.value_id
+        ));
+        Ok(())
+    }
+
     pub fn compile_if_statement(&mut self, condition: &Expr, then_branch: &[Stmt], else_branch: Option<&[Stmt]>) -> Result<(), CodegenError> {
         let cond_val = self.compile_expression(condition)?;
 
@@ -257,21 +402,21 @@ impl LLVMCodegen {
             if else_branch.is_some() { else_label } else { merge_label }
         ));
 
-        self.emit(&format!("L{}:", then_label));
+        self.start_block(then_label);
         for stmt in then_branch {
             self.compile_statement(stmt)?;
         }
         self.emit(&format!("  br label %L{}", merge_label));
 
         if let Some(else_stmts) = else_branch {
-            self.emit(&format!("L{}:", else_label));
+            self.start_block(else_label);
             for stmt in else_stmts {
                 self.compile_statement(stmt)?;
             }
             self.emit(&format!("  br label %L{}", merge_label));
         }
 
-        self.emit(&format!("L{}:", merge_label));
+        self.start_block(merge_label);
         Ok(())
     }
 }
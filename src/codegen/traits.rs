@@ -0,0 +1,194 @@
+//! Trait objects: vtable emission, object-to-trait coercion and dynamic dispatch.
+//!
+//! A trait value is a fat pointer `{ i8*, i8** }` holding the object data and a
+//! pointer to the vtable for that (object, trait) pair. Vtable slots follow the
+//! order in which the trait declares its methods, and a last slot holds the
+//! object's `to_string` so any trait value can be formatted.
+
+use crate::ast::{ObjectMethod, Program, StmtKind};
+use crate::codegen::types::{infer_to_llvm_type, LLVMType};
+use crate::codegen::{CodegenError, LLVMCodegen, LLVMValue};
+use crate::resolver::InferType;
+
+impl LLVMCodegen {
+    /// Symbol name of an object method, shared by direct calls and vtable slots
+    pub(crate) fn method_symbol(&self, type_name: &str, method: &str) -> String {
+        format!("{}__{}", type_name, method)
+    }
+
+    /// Name of the vtable global for an (object, trait) pair
+    fn vtable_symbol(&self, object_name: &str, trait_name: &str) -> String {
+        format!("{}.{}.vtable", object_name, trait_name)
+    }
+
+    /// LLVM function type of an object method, receiving the object by pointer
+    pub(crate) fn method_llvm_type(&self, object_name: &str, method: &ObjectMethod) -> LLVMType {
+        let mut params = vec![LLVMType::Pointer(Box::new(LLVMType::NamedStruct(object_name.to_string())))];
+        params.extend(method.params.iter().map(|p| infer_to_llvm_type(&self.ast_type_to_infer_type(&p.type_))));
+        let ret = match &method.return_type {
+            Some(ty) => infer_to_llvm_type(&self.ast_type_to_infer_type(ty)),
+            None => LLVMType::Void,
+        };
+        LLVMType::Function { ret: Box::new(ret), params }
+    }
+
+    /// Emit one constant vtable per `object name is trait` declaration
+    pub fn emit_vtables(&mut self, program: &Program) -> Result<(), CodegenError> {
+        for stmt in &program.statements {
+            let (object_name, traits, methods) = match &stmt.kind {
+                StmtKind::Object { name, traits, methods, .. } => (name, traits, methods),
+                _ => continue,
+            };
+
+            for trait_name in traits {
+                let trait_methods = match self.lookup_trait_type(trait_name) {
+                    Some(InferType::Trait { methods, .. }) => methods,
+                    _ => return Err(CodegenError::UndefinedVariable(trait_name.clone())),
+                };
+
+                let mut slots = Vec::new();
+                for (method_name, _) in &trait_methods {
+                    let method = methods.iter().find(|m| &m.name == method_name).ok_or_else(|| {
+                        CodegenError::InvalidOperation(format!(
+                            "Object {} is missing method {} required by trait {}",
+                            object_name, method_name, trait_name
+                        ))
+                    })?;
+                    slots.push(format!(
+                        "i8* bitcast ({}* @{} to i8*)",
+                        self.method_llvm_type(object_name, method),
+                        self.method_symbol(object_name, method_name)
+                    ));
+                }
+                let object_type = LLVMType::Pointer(Box::new(LLVMType::NamedStruct(object_name.clone())));
+                slots.push(format!(
+                    "i8* bitcast (%string* ({})* @{} to i8*)",
+                    object_type,
+                    self.method_symbol(object_name, "to_string")
+                ));
+
+                let vtable = format!(
+                    "@{} = private unnamed_addr constant [{} x i8*] [{}]",
+                    self.vtable_symbol(object_name, trait_name),
+                    slots.len(),
+                    slots.join(", ")
+                );
+                self.module.add_global(vtable);
+            }
+        }
+        Ok(())
+    }
+
+    /// Wrap an object value into a trait fat pointer
+    pub fn compile_trait_coercion(&mut self, value: LLVMValue, trait_type: &InferType) -> Result<LLVMValue, CodegenError> {
+        let (object_name, trait_name, slot_count) = match (&value.type_info, trait_type) {
+            (InferType::Object { name: object_name, .. }, InferType::Trait { name: trait_name, methods }) => {
+                (object_name.clone(), trait_name.clone(), methods.len() + 1)
+            }
+            _ => return Ok(value),
+        };
+
+        // Objects may arrive by value; spill them so the trait value can point at them
+        let object_ptr_type = LLVMType::Pointer(Box::new(LLVMType::NamedStruct(object_name.clone())));
+        let object_ptr = if value.llvm_type == object_ptr_type {
+            value.value_id.clone()
+        } else {
+            let slot = self.alloca(&value.llvm_type);
+            self.emit(&format!("  store {} {}, {}* %{}", value.llvm_type, value.value_id, value.llvm_type, slot));
+            format!("%{}", slot)
+        };
+
+        let data = self.next_temp();
+        self.emit(&format!("  %{} = bitcast {} {} to i8*", data, object_ptr_type, object_ptr));
+
+        let fat_type = infer_to_llvm_type(trait_type);
+        let with_data = self.next_temp();
+        self.emit(&format!("  %{} = insertvalue {} undef, i8* %{}, 0", with_data, fat_type, data));
+        let result = self.next_temp();
+        self.emit(&format!(
+            "  %{} = insertvalue {} %{}, i8** getelementptr inbounds ([{} x i8*], [{} x i8*]* @{}, i64 0, i64 0), 1",
+            result, fat_type, with_data, slot_count, slot_count,
+            self.vtable_symbol(&object_name, &trait_name)
+        ));
+
+        Ok(LLVMValue {
+            type_info: trait_type.clone(),
+            llvm_type: fat_type,
+            value_id: format!("%{}", result),
+        })
+    }
+
+    /// Call a trait method through the vtable of a trait value
+    pub fn compile_dynamic_method_call(&mut self, receiver: LLVMValue, method: &str, args: &[crate::ast::Expr]) -> Result<LLVMValue, CodegenError> {
+        let (slot, signature) = match &receiver.type_info {
+            InferType::Trait { methods, .. } => methods.iter()
+                .enumerate()
+                .find(|(_, (name, _))| name == method)
+                .map(|(slot, (_, sig))| (slot, sig.clone()))
+                .ok_or_else(|| CodegenError::InvalidOperation(format!("Trait has no method {}", method)))?,
+            _ => return Err(CodegenError::InvalidOperation("Dynamic dispatch on non-trait value".to_string())),
+        };
+        let (param_types, return_type) = match signature {
+            InferType::Function { params, return_type, .. } => (params, *return_type),
+            other => return Err(CodegenError::NotCallable(other)),
+        };
+
+        let data = self.next_temp();
+        self.emit(&format!("  %{} = extractvalue {} {}, 0", data, receiver.llvm_type, receiver.value_id));
+        let vtable = self.next_temp();
+        self.emit(&format!("  %{} = extractvalue {} {}, 1", vtable, receiver.llvm_type, receiver.value_id));
+        let slot_ptr = self.next_temp();
+        self.emit(&format!("  %{} = getelementptr inbounds i8*, i8** %{}, i64 {}", slot_ptr, vtable, slot));
+        let raw_fn = self.next_temp();
+        self.emit(&format!("  %{} = load i8*, i8** %{}", raw_fn, slot_ptr));
+
+        let return_llvm_type = infer_to_llvm_type(&return_type);
+        let mut llvm_params = vec![LLVMType::Pointer(Box::new(LLVMType::Int(8)))];
+        llvm_params.extend(param_types.iter().map(infer_to_llvm_type));
+        let fn_type = LLVMType::Function { ret: Box::new(return_llvm_type.clone()), params: llvm_params };
+        let fn_ptr = self.next_temp();
+        self.emit(&format!("  %{} = bitcast i8* %{} to {}*", fn_ptr, raw_fn, fn_type));
+
+        let mut call_args = vec![format!("i8* %{}", data)];
+        for arg in args {
+            let arg_val = self.compile_expression(arg)?;
+            call_args.push(format!("{} {}", arg_val.llvm_type, arg_val.value_id));
+        }
+
+        if return_llvm_type == LLVMType::Void {
+            self.emit(&format!("  call void %{}({})", fn_ptr, call_args.join(", ")));
+            return Ok(LLVMValue {
+                type_info: InferType::Unit,
+                llvm_type: LLVMType::Void,
+                value_id: "".to_string(),
+            });
+        }
+
+        let result = self.next_temp();
+        self.emit(&format!("  %{} = call {} %{}({})", result, return_llvm_type, fn_ptr, call_args.join(", ")));
+        Ok(LLVMValue {
+            type_info: return_type,
+            llvm_type: return_llvm_type,
+            value_id: format!("%{}", result),
+        })
+    }
+
+    /// Build the ordered trait signature table from a trait declaration
+    pub(crate) fn trait_type_from_ast(&self, name: &str, methods: &[crate::ast::TraitMethod]) -> InferType {
+        let methods = methods.iter()
+            .map(|m| {
+                let params = m.params.iter().map(|p| self.ast_type_to_infer_type(&p.type_)).collect();
+                let return_type = m.return_type.as_ref()
+                    .map(|ty| self.ast_type_to_infer_type(ty))
+                    .unwrap_or(InferType::Unit);
+                (m.name.clone(), InferType::Function {
+                    params,
+                    return_type: Box::new(return_type),
+                    effects: Default::default(),
+                })
+            })
+            .collect();
+        InferType::Trait { name: name.to_string(), methods }
+    }
+
+}
@@ -54,6 +54,12 @@ impl std::fmt::Display for LLVMType {
     }
 }
 
+/// A function value: code pointer and environment pointer, both as `i8*`.
+pub fn closure_type() -> LLVMType {
+    let byte_pointer = LLVMType::Pointer(Box::new(LLVMType::Int(8)));
+    LLVMType::Struct(vec![byte_pointer.clone(), byte_pointer])
+}
+
 /// Converts an `InferType` to its corresponding `LLVMType`.
 pub fn infer_to_llvm_type(ty: &InferType) -> LLVMType {
     match ty {
@@ -61,32 +67,24 @@ pub fn infer_to_llvm_type(ty: &InferType) -> LLVMType {
         InferType::Bool => LLVMType::Int(1),
         InferType::Int => LLVMType::Int(64),
         InferType::Float => LLVMType::Double,
-        InferType::String => LLVMType::NamedStruct("string".to_string()),
-        InferType::List(it) => {
-            let inner_type = infer_to_llvm_type(it);
-            LLVMType::Pointer(Box::new(LLVMType::NamedStruct(format!(
-                "list.{}",
-                inner_type
-            ))))
-        }
-        InferType::Map(k, v) => {
-            let key_type = infer_to_llvm_type(k);
-            let val_type = infer_to_llvm_type(v);
-            LLVMType::Pointer(Box::new(LLVMType::NamedStruct(format!(
-                "map.{}.{}",
-                key_type, val_type
-            ))))
-        }
-        InferType::Function {
-            params,
-            return_type,
-            ..
-        } => {
-            let ret = Box::new(infer_to_llvm_type(return_type));
-            let params = params.iter().map(infer_to_llvm_type).collect();
-            LLVMType::Pointer(Box::new(LLVMType::Function { ret, params }))
-        }
-        InferType::Object { name, .. } => LLVMType::NamedStruct(name.clone()),
+        InferType::I8 => LLVMType::Int(8),
+        InferType::I16 => LLVMType::Int(16),
+        InferType::I32 => LLVMType::Int(32),
+        InferType::F32 => LLVMType::Float,
+        // Strings are immutable and shared by reference; see `runtime/runtime.c`
+        InferType::String => LLVMType::Pointer(Box::new(LLVMType::NamedStruct("string".to_string()))),
+        // Lists and maps are runtime handles; see `codegen::collections`
+        InferType::List(_) | InferType::Map(_, _) => LLVMType::Pointer(Box::new(LLVMType::Int(8))),
+        // Function values are closures; see `codegen::closures`
+        InferType::Function { .. } => closure_type(),
+        // Objects live on the heap and are passed around by reference
+        InferType::Object { name, .. } => LLVMType::Pointer(Box::new(LLVMType::NamedStruct(name.clone()))),
+        InferType::Enum { name, .. } => LLVMType::NamedStruct(name.clone()),
+        // Trait values are fat pointers: object data plus vtable
+        InferType::Trait { .. } => LLVMType::Struct(vec![
+            LLVMType::Pointer(Box::new(LLVMType::Int(8))),
+            LLVMType::Pointer(Box::new(LLVMType::Pointer(Box::new(LLVMType::Int(8))))),
+        ]),
         InferType::Store { name, .. } => LLVMType::Pointer(Box::new(LLVMType::NamedStruct(name.clone()))),
         InferType::Actor { name, .. } => LLVMType::Pointer(Box::new(LLVMType::NamedStruct(name.clone()))),
         InferType::Result(ok, err) => {
@@ -0,0 +1,273 @@
+//! Compile-time evaluation of constants.
+//!
+//! Top-level bindings with ALL_CAPS names are constants. Their initializers
+//! must be computable from literals and earlier constants, so codegen can
+//! inline them instead of allocating a variable.
+
+use crate::ast::*;
+use crate::semantic::SemanticError;
+use std::collections::HashMap;
+
+/// Value of a constant expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+}
+
+/// ALL_CAPS names (digits and underscores allowed) are constants
+pub fn is_constant_name(name: &str) -> bool {
+    name.chars().any(|c| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// The top-level assignment that defines a constant, if `stmt` is one
+pub fn constant_binding(stmt: &Stmt) -> Option<(&str, &Expr, &Expr)> {
+    match &stmt.kind {
+        StmtKind::Assignment { target, value } => match &target.kind {
+            ExprKind::Identifier(name) if is_constant_name(name) => Some((name, target, value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Evaluate every constant defined at the top level of `program`, in order.
+/// Later assignments to the same name are left to the semantic checks.
+pub fn program_constants(program: &Program) -> Result<HashMap<String, ConstValue>, Vec<SemanticError>> {
+    let mut constants = HashMap::new();
+    let mut errors = Vec::new();
+    for stmt in &program.statements {
+        let Some((name, _, value)) = constant_binding(stmt) else {
+            continue;
+        };
+        if constants.contains_key(name) {
+            continue;
+        }
+        match evaluate(value, &constants) {
+            Ok(result) => {
+                constants.insert(name.to_string(), result);
+            }
+            Err(message) => errors.push(SemanticError {
+                message: format!("Value of constant '{}' is not known at compile time: {}", name, message),
+                span: value.span.clone(),
+            }),
+        }
+    }
+    if errors.is_empty() {
+        Ok(constants)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Fold `expr` to a value using only literals and the given constants
+pub fn evaluate(expr: &Expr, constants: &HashMap<String, ConstValue>) -> Result<ConstValue, String> {
+    match &expr.kind {
+        ExprKind::Literal(literal) => match literal {
+            Literal::Integer(i) => Ok(ConstValue::Int(*i)),
+            Literal::Float(f) => Ok(ConstValue::Float(*f)),
+            Literal::Bool(b) => Ok(ConstValue::Bool(*b)),
+            Literal::String(s) => Ok(ConstValue::String(s.clone())),
+            other => Err(format!("'{:?}' is not a constant value", other)),
+        },
+        ExprKind::Identifier(name) => constants.get(name).cloned()
+            .ok_or_else(|| format!("'{}' is not a constant", name)),
+        ExprKind::Unary { op, operand } => {
+            let value = evaluate(operand, constants)?;
+            match (op, value) {
+                (UnaryOp::Neg, ConstValue::Int(i)) => i.checked_neg().map(ConstValue::Int).ok_or_else(overflow),
+                (UnaryOp::Neg, ConstValue::Float(f)) => Ok(ConstValue::Float(-f)),
+                (UnaryOp::Not, ConstValue::Bool(b)) => Ok(ConstValue::Bool(!b)),
+                (UnaryOp::BitNot, ConstValue::Int(i)) => Ok(ConstValue::Int(!i)),
+                (op, value) => Err(format!("cannot apply {:?} to {:?}", op, value)),
+            }
+        }
+        ExprKind::Binary { op, left, right } => {
+            let left = evaluate(left, constants)?;
+            let right = evaluate(right, constants)?;
+            binary(op, left, right)
+        }
+        ExprKind::Cast { expr, target } => {
+            let value = evaluate(expr, constants)?;
+            cast(value, target)
+        }
+        ExprKind::StringInterpolation { parts } => {
+            let mut text = String::new();
+            for part in parts {
+                match part {
+                    StringPart::Literal(literal) => text.push_str(literal),
+                    StringPart::Expression(inner) => text.push_str(&display(evaluate(inner, constants)?)),
+                }
+            }
+            Ok(ConstValue::String(text))
+        }
+        other => Err(format!("{} is not a constant expression", describe(other))),
+    }
+}
+
+/// Text of a value inside a string, formatted as the runtime does
+fn display(value: ConstValue) -> String {
+    match value {
+        ConstValue::Int(i) => i.to_string(),
+        ConstValue::Float(f) => format!("{:.6}", f),
+        ConstValue::Bool(b) => b.to_string(),
+        ConstValue::String(s) => s,
+    }
+}
+
+fn describe(kind: &ExprKind) -> &'static str {
+    match kind {
+        ExprKind::Call { .. } | ExprKind::Across { .. } => "a function call",
+        ExprKind::Index { .. } => "an index",
+        ExprKind::FieldAccess { .. } => "a field access",
+        ExprKind::ListLiteral(_) | ExprKind::ListAppend { .. } => "a list",
+        ExprKind::MapLiteral(_) | ExprKind::MapInsert { .. } => "a map",
+        ExprKind::ObjectInstantiation { .. } => "an object",
+        ExprKind::Lambda { .. } => "a function",
+        ExprKind::ErrValue(_) => "an error value",
+        _ => "this expression",
+    }
+}
+
+fn overflow() -> String {
+    "arithmetic overflow".to_string()
+}
+
+fn binary(op: &BinaryOp, left: ConstValue, right: ConstValue) -> Result<ConstValue, String> {
+    use ConstValue::*;
+    match (left, right) {
+        (Int(a), Int(b)) => {
+            let checked = match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div if b == 0 => return Err("division by zero".to_string()),
+                BinaryOp::Div => a.checked_div(b),
+                BinaryOp::Mod if b == 0 => return Err("division by zero".to_string()),
+                BinaryOp::Mod => a.checked_rem(b),
+                BinaryOp::BitAnd => Some(a & b),
+                BinaryOp::BitOr => Some(a | b),
+                BinaryOp::BitXor => Some(a ^ b),
+                BinaryOp::Shl => u32::try_from(b).ok().and_then(|b| a.checked_shl(b)),
+                BinaryOp::Shr => u32::try_from(b).ok().and_then(|b| a.checked_shr(b)),
+                _ => return compare(op, a.cmp(&b)),
+            };
+            checked.map(Int).ok_or_else(overflow)
+        }
+        // Mixed arithmetic promotes to float, as it does at runtime
+        (Int(a), Float(b)) => binary(op, Float(a as f64), Float(b)),
+        (Float(a), Int(b)) => binary(op, Float(a), Float(b as f64)),
+        (Float(a), Float(b)) => match op {
+            BinaryOp::Add => Ok(Float(a + b)),
+            BinaryOp::Sub => Ok(Float(a - b)),
+            BinaryOp::Mul => Ok(Float(a * b)),
+            BinaryOp::Div => Ok(Float(a / b)),
+            BinaryOp::Mod => Ok(Float(a % b)),
+            _ => match a.partial_cmp(&b) {
+                Some(ordering) => compare(op, ordering),
+                None => Ok(Bool(matches!(op, BinaryOp::Ne))),
+            },
+        },
+        (Bool(a), Bool(b)) => match op {
+            BinaryOp::And => Ok(Bool(a && b)),
+            BinaryOp::Or => Ok(Bool(a || b)),
+            BinaryOp::Xor => Ok(Bool(a ^ b)),
+            BinaryOp::Eq | BinaryOp::Is => Ok(Bool(a == b)),
+            BinaryOp::Ne => Ok(Bool(a != b)),
+            _ => Err(format!("cannot apply {:?} to booleans", op)),
+        },
+        (String(a), String(b)) => match op {
+            BinaryOp::Add => Ok(String(a + &b)),
+            _ => compare(op, a.cmp(&b)),
+        },
+        (left, right) => Err(format!("cannot apply {:?} to {:?} and {:?}", op, left, right)),
+    }
+}
+
+fn compare(op: &BinaryOp, ordering: std::cmp::Ordering) -> Result<ConstValue, String> {
+    use std::cmp::Ordering::*;
+    let result = match op {
+        BinaryOp::Eq | BinaryOp::Is => ordering == Equal,
+        BinaryOp::Ne => ordering != Equal,
+        BinaryOp::Lt => ordering == Less,
+        BinaryOp::Le => ordering != Greater,
+        BinaryOp::Gt => ordering == Greater,
+        BinaryOp::Ge => ordering != Less,
+        _ => return Err(format!("{:?} is not a comparison", op)),
+    };
+    Ok(ConstValue::Bool(result))
+}
+
+fn cast(value: ConstValue, target: &Type) -> Result<ConstValue, String> {
+    let int = match value {
+        ConstValue::Int(i) => i,
+        ConstValue::Float(f) => f as i64,
+        other => return Err(format!("cannot cast {:?} to {:?}", other, target)),
+    };
+    match target {
+        Type::I8 => Ok(ConstValue::Int(int as i8 as i64)),
+        Type::I16 => Ok(ConstValue::Int(int as i16 as i64)),
+        Type::I32 => Ok(ConstValue::Int(int as i32 as i64)),
+        Type::I64 => Ok(ConstValue::Int(int)),
+        Type::F32 | Type::F64 => match value {
+            ConstValue::Float(f) if *target == Type::F32 => Ok(ConstValue::Float(f as f32 as f64)),
+            ConstValue::Float(f) => Ok(ConstValue::Float(f)),
+            _ => Ok(ConstValue::Float(int as f64)),
+        },
+        _ => Err(format!("cannot cast to {:?}", target)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn constants_of(input: &str) -> Result<HashMap<String, ConstValue>, Vec<SemanticError>> {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        program_constants(&parser.parse().unwrap())
+    }
+
+    #[test]
+    fn test_folds_arithmetic_and_references() {
+        let constants = constants_of(
+            "ITERATIONS is 100\nHALF is ITERATIONS / 2 - 1\nPI is 3.14\nTAU is PI * 2\nBIG is HALF gt 40\nlocal is 1\n"
+        ).unwrap();
+        assert_eq!(constants["ITERATIONS"], ConstValue::Int(100));
+        assert_eq!(constants["HALF"], ConstValue::Int(49));
+        assert_eq!(constants["TAU"], ConstValue::Float(6.28));
+        assert_eq!(constants["BIG"], ConstValue::Bool(true));
+        assert!(!constants.contains_key("local"));
+    }
+
+    #[test]
+    fn test_rejects_runtime_values() {
+        let errors = constants_of("fn f() -> i64\n    return 1\nLIMIT is f()\nZERO is 1 / 0\n").unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.starts_with("Value of constant 'LIMIT' is not known at compile time"));
+        assert!(errors[1].message.ends_with("division by zero"));
+    }
+
+    #[test]
+    fn test_folds_interpolated_strings() {
+        let constants = constants_of("NAME is 'hi'\nLIMIT is 3\nGREETING is 'say {NAME} {LIMIT} times'\n").unwrap();
+        assert_eq!(constants["NAME"], ConstValue::String("hi".to_string()));
+        assert_eq!(constants["GREETING"], ConstValue::String("say hi 3 times".to_string()));
+
+        let errors = constants_of("fn f() -> i64\n    return 1\nLABEL is 'n{f()}'\n").unwrap_err();
+        assert_eq!(errors[0].message, "Value of constant 'LABEL' is not known at compile time: a function call is not a constant expression");
+    }
+
+    #[test]
+    fn test_casts_truncate() {
+        let constants = constants_of("BYTE is 300 as i8\nRATIO is 7 as f64\n").unwrap();
+        assert_eq!(constants["BYTE"], ConstValue::Int(44));
+        assert_eq!(constants["RATIO"], ConstValue::Float(7.0));
+    }
+}
@@ -1,4 +1,3 @@
-use std::collections::HashMap;
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,6 +15,7 @@ pub enum TokenType {
     Let, Fn, Is, Object, Store, Actor, Use, Mod, If, Then, Else, While,
     For, In, Until, Unless, Iterate, Across, Return, Break, Continue,
     Import, From, To, Nocopy, Err, No, Yes, Empty, Now, As, With, Into, Make,
+    Enum, Trait,
     
     // Operators
     Plus, Minus, Star, Slash, Percent, Equal, EqualEqual, BangEqual,
@@ -55,56 +55,66 @@ pub struct Lexer {
     current_char: Option<char>,
     line: usize,
     column: usize,
-    keywords: HashMap<String, TokenType>,
     indent_stack: Vec<usize>,
     token_buffer: Vec<Token>,
     at_line_start: bool,
     brace_level: usize,
 }
 
+/// Token type of a reserved word, or `None` for an ordinary identifier
+pub fn keyword(word: &str) -> Option<TokenType> {
+    Some(match word {
+        "let" => TokenType::Let,
+        "fn" | "def" => TokenType::Fn,
+        "is" => TokenType::Is,
+        "object" => TokenType::Object,
+        "store" => TokenType::Store,
+        "actor" => TokenType::Actor,
+        "if" => TokenType::If,
+        "else" => TokenType::Else,
+        "while" => TokenType::While,
+        "return" => TokenType::Return,
+        "break" => TokenType::Break,
+        "continue" => TokenType::Continue,
+        "true" => TokenType::True,
+        "false" => TokenType::False,
+        "yes" => TokenType::Yes,
+        "no" => TokenType::No,
+        "now" => TokenType::Now,
+        "and" => TokenType::And,
+        "or" => TokenType::Or,
+        "iterate" => TokenType::Iterate,
+        "as" => TokenType::As,
+        "for" => TokenType::For,
+        "with" => TokenType::With,
+        "into" => TokenType::Into,
+        "make" => TokenType::Make,
+        "equals" => TokenType::Equals,
+        "gt" => TokenType::Gt,
+        "gte" => TokenType::Gte,
+        "lt" => TokenType::Lt,
+        "lte" => TokenType::Lte,
+        "unless" => TokenType::Unless,
+        "until" => TokenType::Until,
+        "across" => TokenType::Across,
+        "enum" => TokenType::Enum,
+        "trait" => TokenType::Trait,
+        "err" => TokenType::Err,
+        _ => return None,
+    })
+}
+
 impl Lexer {
     pub fn new(input: String, _file_name: String) -> Self {
         let chars: Vec<char> = input.chars().collect();
         let current_char = chars.get(0).copied();
         
-        let mut keywords = HashMap::new();
-        keywords.insert("let".to_string(), TokenType::Let);
-        keywords.insert("fn".to_string(), TokenType::Fn);
-        keywords.insert("def".to_string(), TokenType::Fn);
-        keywords.insert("is".to_string(), TokenType::Is);
-        keywords.insert("object".to_string(), TokenType::Object);
-        keywords.insert("store".to_string(), TokenType::Store);
-        keywords.insert("actor".to_string(), TokenType::Actor);
-        keywords.insert("if".to_string(), TokenType::If);
-        keywords.insert("else".to_string(), TokenType::Else);
-        keywords.insert("while".to_string(), TokenType::While);
-        keywords.insert("return".to_string(), TokenType::Return);
-        keywords.insert("true".to_string(), TokenType::True);
-        keywords.insert("false".to_string(), TokenType::False);
-        keywords.insert("and".to_string(), TokenType::And);
-        keywords.insert("or".to_string(), TokenType::Or);
-        keywords.insert("iterate".to_string(), TokenType::Iterate);
-        keywords.insert("as".to_string(), TokenType::As);
-        keywords.insert("for".to_string(), TokenType::For);
-        keywords.insert("with".to_string(), TokenType::With);
-        keywords.insert("into".to_string(), TokenType::Into);
-        keywords.insert("make".to_string(), TokenType::Make);
-        keywords.insert("equals".to_string(), TokenType::Equals);
-        keywords.insert("gt".to_string(), TokenType::Gt);
-        keywords.insert("gte".to_string(), TokenType::Gte);
-        keywords.insert("lt".to_string(), TokenType::Lt);
-        keywords.insert("lte".to_string(), TokenType::Lte);
-        keywords.insert("unless".to_string(), TokenType::Unless);
-        keywords.insert("until".to_string(), TokenType::Until);
-        keywords.insert("across".to_string(), TokenType::Across);
-        
         Self {
             input: chars,
             position: 0,
             current_char,
             line: 1,
             column: 1,
-            keywords,
             indent_stack: vec![0],
             token_buffer: Vec::new(),
             at_line_start: true,
@@ -200,7 +210,12 @@ impl Lexer {
                 break;
             }
         }
-        
+        // Blank lines do not open or close blocks
+        if matches!(self.current_char, None | Some('\n') | Some('\r')) {
+            self.at_line_start = false;
+            return;
+        }
+
         let current_indent = *self.indent_stack.last().unwrap();
         
         if indent_level > current_indent {
@@ -309,7 +324,7 @@ impl Lexer {
                     }
                     _ if ch.is_alphabetic() || ch == '_' => {
                         let lexeme = self.read_identifier();
-                        let tt = self.keywords.get(&lexeme).copied().unwrap_or(TokenType::Identifier);
+                        let tt = keyword(&lexeme).unwrap_or(TokenType::Identifier);
                         Token::new(tt, start_line, start_col, lexeme)
                     }
                     _ => {
@@ -2,8 +2,13 @@ pub mod ast;
 pub mod lexer;
 pub mod parser;
 pub mod semantic;
+pub mod names;
+pub mod cfg;
+pub mod lint;
+pub mod consteval;
 pub mod resolver;
 pub mod codegen;
+pub mod tables;
 
 pub use ast::*;
 pub use lexer::*;
@@ -12,17 +17,17 @@ pub use semantic::*;
 
 
 
-/// Parse and analyze Coral source code
+/// Parse Coral source code, infer its types and run the semantic passes
 pub fn parse_and_analyze(input: &str) -> Result<(Program, Result<(), Vec<SemanticError>>), ParseError> {
     let mut lexer = Lexer::new(input.to_string(), "test.cor".to_string());
     let tokens = lexer.tokenize().map_err(|e| ParseError::InvalidSyntax { message: format!("Lexer error: {}", e), span: SourceSpan::default() })?;
     let mut parser = Parser::new(tokens, "test.cor".to_string());
     
-    // Use the new integrated parse_and_resolve method
-    let mut program = parser.parse_and_resolve()?;
+    let program = parser.parse()?;
     
+    // Type resolution runs inside the analyzer, ahead of its passes
     let mut analyzer = SemanticAnalyzer::new();
-    let analysis_result = analyzer.analyze(&mut program);
+    let analysis_result = analyzer.analyze(&program);
     
     Ok((program, analysis_result))
 }
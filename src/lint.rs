@@ -0,0 +1,452 @@
+//! Configurable lints over the analyzed program.
+//!
+//! Lints run after `SemanticAnalyzer` succeeds and read its side tables: the
+//! def-use links from name resolution and the inferred expression types. Each
+//! lint has a level; `warn` findings are reported, `deny` findings fail the
+//! build. Levels come from the `[lints]` table of `coral.toml` and can be
+//! overridden on the command line.
+
+use crate::ast::*;
+use crate::semantic::{SemanticAnalyzer, SemanticError};
+use crate::consteval::is_constant_name;
+use crate::tables::DefinitionKind;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariables,
+    UnusedParameters,
+    UnusedImports,
+    Shadowing,
+    ConstantReassignment,
+    AlwaysTrueComparisons,
+    UnreachableCode,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [
+        Lint::UnusedVariables,
+        Lint::UnusedParameters,
+        Lint::UnusedImports,
+        Lint::Shadowing,
+        Lint::ConstantReassignment,
+        Lint::AlwaysTrueComparisons,
+        Lint::UnreachableCode,
+    ];
+
+    /// Name used in `coral.toml` and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::UnusedParameters => "unused_parameters",
+            Lint::UnusedImports => "unused_imports",
+            Lint::Shadowing => "shadowing",
+            Lint::ConstantReassignment => "constant_reassignment",
+            Lint::AlwaysTrueComparisons => "always_true_comparisons",
+            Lint::UnreachableCode => "unreachable_code",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+}
+
+/// Level of every lint; anything not configured warns
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    levels: HashMap<Lint, Level>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self { levels: HashMap::new() }
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or(Level::Warn)
+    }
+
+    pub fn set(&mut self, lint: Lint, level: Level) {
+        self.levels.insert(lint, level);
+    }
+
+    /// Set a level by lint name, as given on the command line
+    pub fn set_by_name(&mut self, name: &str, level: Level) -> Result<(), String> {
+        let lint = Lint::from_name(name).ok_or_else(|| format!("Unknown lint '{}'", name))?;
+        self.set(lint, level);
+        Ok(())
+    }
+
+    /// Read the `[lints]` table of a `coral.toml`, e.g. `shadowing = "deny"`
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        let mut config = Self::new();
+        let mut in_lints = false;
+        for (index, raw_line) in source.lines().enumerate() {
+            let line = raw_line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_lints = line == "[lints]";
+                continue;
+            }
+            if !in_lints {
+                continue;
+            }
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("coral.toml:{}: expected `lint = \"level\"`", index + 1))?;
+            let value = value.trim().trim_matches('"');
+            let level = Level::from_name(value)
+                .ok_or_else(|| format!("coral.toml:{}: unknown lint level '{}'", index + 1, value))?;
+            config.set_by_name(key.trim(), level)
+                .map_err(|err| format!("coral.toml:{}: {}", index + 1, err))?;
+        }
+        Ok(config)
+    }
+}
+
+/// Lint findings, split by the level they were reported at
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub warnings: Vec<SemanticError>,
+    pub errors: Vec<SemanticError>,
+}
+
+/// Run every enabled lint over an analyzed program
+pub fn check(program: &Program, analyzer: &SemanticAnalyzer, config: &LintConfig) -> LintReport {
+    let mut linter = Linter { analyzer, config, report: LintReport::default() };
+    linter.unused_definitions();
+    linter.shadowed_bindings();
+    linter.reassigned_constants();
+    linter.unreachable_code();
+    linter.visit_block(&program.statements);
+    linter.report
+}
+
+struct Linter<'a> {
+    analyzer: &'a SemanticAnalyzer,
+    config: &'a LintConfig,
+    report: LintReport,
+}
+
+impl Linter<'_> {
+    fn emit(&mut self, lint: Lint, message: String, span: &SourceSpan) {
+        let error = SemanticError {
+            message: format!("{} [{}]", message, lint.name()),
+            span: span.clone(),
+        };
+        match self.config.level(lint) {
+            Level::Allow => {}
+            Level::Warn => self.report.warnings.push(error),
+            Level::Deny => self.report.errors.push(error),
+        }
+    }
+
+    fn unused_definitions(&mut self) {
+        let resolutions = self.analyzer.resolutions();
+        for definition in resolutions.definitions() {
+            // A leading underscore marks a binding as intentionally unused
+            if definition.name.starts_with('_') || definition.name == "self" || definition.name == "$" {
+                continue;
+            }
+            let (lint, what) = match definition.kind {
+                DefinitionKind::Variable => (Lint::UnusedVariables, "variable"),
+                DefinitionKind::Parameter => (Lint::UnusedParameters, "parameter"),
+                DefinitionKind::Module => (Lint::UnusedImports, "import"),
+                _ => continue,
+            };
+            if resolutions.reads_of(definition).is_empty() {
+                self.emit(lint, format!("Unused {} '{}'", what, definition.name), &definition.span);
+            }
+        }
+    }
+
+    fn shadowed_bindings(&mut self) {
+        for (inner, outer) in self.analyzer.resolutions().shadowed() {
+            let message = format!(
+                "'{}' shadows the definition at line {}",
+                inner.name, outer.span.start_line
+            );
+            self.emit(Lint::Shadowing, message, &inner.span);
+        }
+    }
+
+    /// ALL_CAPS names are constants and may only be assigned once
+    fn reassigned_constants(&mut self) {
+        let resolutions = self.analyzer.resolutions();
+        let mut reassigned = Vec::new();
+        for definition in resolutions.definitions() {
+            if definition.kind != DefinitionKind::Variable || !is_constant_name(&definition.name) {
+                continue;
+            }
+            if resolutions.writes_of(definition).len() > 1 {
+                reassigned.push(definition.clone());
+            }
+        }
+        for definition in reassigned {
+            self.emit(
+                Lint::ConstantReassignment,
+                format!("Constant '{}' is reassigned", definition.name),
+                &definition.span,
+            );
+        }
+    }
+
+    /// Statements after a `return`, `break` or `continue` that no path reaches
+    fn unreachable_code(&mut self) {
+        for span in self.analyzer.unreachable_code() {
+            self.emit(Lint::UnreachableCode, "Unreachable code".to_string(), span);
+        }
+    }
+
+    fn visit_block(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Expression(expr) | StmtKind::Return(Some(expr)) => self.visit_expr(expr),
+            StmtKind::Assignment { target, value } => {
+                self.visit_expr(target);
+                self.visit_expr(value);
+            }
+            StmtKind::Function { body, .. } => self.visit_block(body),
+            StmtKind::Object { methods, .. } | StmtKind::Store { methods, .. } => {
+                for method in methods {
+                    self.visit_block(&method.body);
+                }
+            }
+            StmtKind::Actor { handlers, .. } => {
+                for handler in handlers {
+                    self.visit_block(&handler.body);
+                }
+            }
+            StmtKind::If { condition, then_branch, else_branch } => {
+                self.visit_expr(condition);
+                self.visit_block(then_branch);
+                if let Some(else_stmts) = else_branch {
+                    self.visit_block(else_stmts);
+                }
+            }
+            StmtKind::Unless { condition, body }
+            | StmtKind::While { condition, body }
+            | StmtKind::Until { condition, body } => {
+                self.visit_expr(condition);
+                self.visit_block(body);
+            }
+            StmtKind::Iterate { iterable, body } => {
+                self.visit_expr(iterable);
+                self.visit_block(body);
+            }
+            StmtKind::ErrorHandler { handler, inner } => {
+                self.visit_stmt(inner);
+                for action in &handler.actions {
+                    if let ErrorAction::Log(Some(expr)) | ErrorAction::Return(Some(expr)) | ErrorAction::Custom(expr) = action {
+                        self.visit_expr(expr);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Binary { op, left, right } => {
+                if self.always_true(op, left, right) {
+                    self.emit(Lint::AlwaysTrueComparisons, "Comparison is always true".to_string(), &expr.span);
+                }
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            ExprKind::Literal(_) | ExprKind::Identifier(_) => {}
+            ExprKind::Unary { operand, .. } => self.visit_expr(operand),
+            ExprKind::Call { callee, args } => {
+                self.visit_expr(callee);
+                for arg in args {
+                    self.visit_expr(&arg.value);
+                }
+            }
+            ExprKind::Index { object, index } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
+            ExprKind::FieldAccess { object, .. } => self.visit_expr(object),
+            ExprKind::ListLiteral(elements) => {
+                for element in elements {
+                    self.visit_expr(element);
+                }
+            }
+            ExprKind::MapLiteral(pairs) => {
+                for (key, value) in pairs {
+                    self.visit_expr(key);
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::ListAppend { list, element } => {
+                self.visit_expr(list);
+                self.visit_expr(element);
+            }
+            ExprKind::MapInsert { map, key, value } => {
+                self.visit_expr(map);
+                self.visit_expr(key);
+                self.visit_expr(value);
+            }
+            ExprKind::Across { callee, iterable, .. } => {
+                self.visit_expr(callee);
+                self.visit_expr(iterable);
+            }
+            ExprKind::StringInterpolation { parts } => {
+                for part in parts {
+                    if let StringPart::Expression(inner) = part {
+                        self.visit_expr(inner);
+                    }
+                }
+            }
+            ExprKind::If { condition, then_branch, else_branch } => {
+                self.visit_expr(condition);
+                self.visit_expr(then_branch);
+                if let Some(else_expr) = else_branch {
+                    self.visit_expr(else_expr);
+                }
+            }
+            ExprKind::Block(stmts) => self.visit_block(stmts),
+            ExprKind::Lambda { body, .. } => self.visit_expr(body),
+            ExprKind::ObjectInstantiation { fields, .. } => {
+                for (_, value) in fields {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::ErrValue(inner) | ExprKind::Cast { expr: inner, .. } => self.visit_expr(inner),
+        }
+    }
+
+    /// `x equals x` and comparisons between literals that hold
+    fn always_true(&self, op: &BinaryOp, left: &Expr, right: &Expr) -> bool {
+        match (&left.kind, &right.kind) {
+            (ExprKind::Identifier(a), ExprKind::Identifier(b)) if a == b => {
+                // NaN is not equal to itself, so floats are left alone
+                let operand_type = self.analyzer.types().type_of(left.id);
+                matches!(op, BinaryOp::Eq | BinaryOp::Le | BinaryOp::Ge) && !operand_type.is_float()
+            }
+            (ExprKind::Literal(a), ExprKind::Literal(b)) => match (a, b) {
+                (Literal::Integer(a), Literal::Integer(b)) => compare(op, a, b),
+                (Literal::String(a), Literal::String(b)) => compare(op, a, b),
+                (Literal::Bool(a), Literal::Bool(b)) => compare(op, a, b),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+fn compare<T: PartialOrd>(op: &BinaryOp, a: &T, b: &T) -> bool {
+    match op {
+        BinaryOp::Eq => a == b,
+        BinaryOp::Ne => a != b,
+        BinaryOp::Lt => a < b,
+        BinaryOp::Le => a <= b,
+        BinaryOp::Gt => a > b,
+        BinaryOp::Ge => a >= b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn lint(input: &str, config: &LintConfig) -> LintReport {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        let program = parser.parse().unwrap();
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze(&program).unwrap();
+        check(&program, &analyzer, config)
+    }
+
+    fn warnings(input: &str) -> Vec<String> {
+        lint(input, &LintConfig::new()).warnings.into_iter().map(|w| w.message).collect()
+    }
+
+    #[test]
+    fn test_unused_bindings() {
+        assert_eq!(
+            warnings("fn f(a: i64, _b: i64) -> i64\n    c is 1\n    d is 2\n    return d\n"),
+            vec!["Unused parameter 'a' [unused_parameters]", "Unused variable 'c' [unused_variables]"]
+        );
+    }
+
+    #[test]
+    fn test_shadowing_and_constants() {
+        assert_eq!(
+            warnings("limit is 10\nfn f(limit: i64) -> i64\n    return limit\n"),
+            vec!["Unused variable 'limit' [unused_variables]", "'limit' shadows the definition at line 1 [shadowing]"]
+        );
+        assert_eq!(
+            warnings("fn f() -> i64\n    ITERATIONS is 10\n    ITERATIONS is 20\n    return ITERATIONS\n"),
+            vec!["Constant 'ITERATIONS' is reassigned [constant_reassignment]"]
+        );
+    }
+
+    #[test]
+    fn test_always_true_comparisons() {
+        assert_eq!(
+            warnings("fn f(n: i64) -> bool\n    return n equals n\n"),
+            vec!["Comparison is always true [always_true_comparisons]"]
+        );
+        assert!(warnings("fn f(x: f64) -> bool\n    return x equals x\n").is_empty());
+        assert_eq!(warnings("fn f() -> bool\n    return 1 lt 2\n").len(), 1);
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        assert_eq!(
+            warnings("fn f(n: i64) -> i64\n    return n\n    n + 1\n"),
+            vec!["Unreachable code [unreachable_code]"]
+        );
+        assert_eq!(
+            warnings("fn f(xs: (i64))\n    iterate xs\n        break\n        log xs\n"),
+            vec!["Unreachable code [unreachable_code]"]
+        );
+        let config = LintConfig::from_toml("[lints]\nunreachable_code = \"deny\"\n").unwrap();
+        let report = lint("fn f() -> i64\n    return 1\n    _y is 2\n", &config);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_levels() {
+        let config = LintConfig::from_toml("[package]\nname = \"demo\"\n\n[lints]\nunused_variables = \"deny\" # strict\nunused_parameters = \"allow\"\n").unwrap();
+        let report = lint("fn f(a: i64)\n    c is 1\n", &config);
+        assert!(report.warnings.is_empty());
+        assert_eq!(report.errors.len(), 1);
+
+        assert!(LintConfig::from_toml("[lints]\nunused = \"warn\"\n").is_err());
+        assert!(LintConfig::from_toml("[lints]\nshadowing = \"loud\"\n").is_err());
+    }
+}
@@ -1,44 +1,131 @@
 use std::fs;
 use std::path::Path;
 
+use coral::lint::{Level, LintConfig};
+use coral::semantic::SemanticError;
+
+const USAGE: &str = "Usage: coral [types] [-A|-W|-D <lint>]... <file>";
+
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: coral <file>");
-        return;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut show_types = false;
+    let mut lint_flags = Vec::new();
+    let mut file_path = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        let level = match arg.as_str() {
+            "-A" => Level::Allow,
+            "-W" => Level::Warn,
+            "-D" => Level::Deny,
+            "types" if file_path.is_none() && !show_types => {
+                show_types = true;
+                continue;
+            }
+            _ if file_path.is_none() => {
+                file_path = Some(arg.clone());
+                continue;
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        };
+        match rest.next() {
+            Some(lint) => lint_flags.push((lint.clone(), level)),
+            None => {
+                eprintln!("{}", USAGE);
+                return;
+            }
+        }
     }
+    let Some(file_path) = file_path else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+    let file_path = &file_path;
+
+    let lint_config = load_lint_config(file_path, &lint_flags).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    });
 
-    let file_path = &args[1];
     let code = fs::read_to_string(file_path).expect("Failed to read file");
 
     let mut lexer = coral::lexer::Lexer::new(code, file_path.clone());
     let tokens = lexer.tokenize().unwrap();
 
     let mut parser = coral::parser::Parser::new(tokens, file_path.clone());
-    let mut ast = parser.parse().unwrap();
+    let ast = parser.parse().unwrap();
+
+    let mut analyzer = coral::semantic::SemanticAnalyzer::new();
+    if let Err(errors) = analyzer.analyze(&ast) {
+        for error in &errors {
+            report("error", error);
+        }
+        std::process::exit(1);
+    }
+
+    let lints = coral::lint::check(&ast, &analyzer, &lint_config);
+    for warning in &lints.warnings {
+        report("warning", warning);
+    }
+    for error in &lints.errors {
+        report("error", error);
+    }
+    if !lints.errors.is_empty() {
+        std::process::exit(1);
+    }
 
-    let mut resolver = coral::resolver::TypeResolver::new();
-    resolver.resolve_program(&mut ast).unwrap();
+    if show_types {
+        for (name, ty) in analyzer.resolver().function_types(&ast) {
+            println!("{}: {}", name, ty);
+        }
+        return;
+    }
 
-    let mut codegen = coral::codegen::LLVMCodegen::new(file_path.clone());
-    let llvm_ir = codegen.compile_program(&ast).unwrap();
+    let mut codegen = coral::codegen::LLVMCodegen::new(file_path.clone()).with_types(analyzer.types().clone());
+    let llvm_ir = codegen.compile_program(&ast).unwrap_or_else(|err| {
+        eprintln!("error: {:?}", err);
+        std::process::exit(1);
+    });
 
     println!("{}", llvm_ir);
 }
 
+/// Lint levels from a `coral.toml` next to the source file, then the command line
+fn load_lint_config(file_path: &str, flags: &[(String, Level)]) -> Result<LintConfig, String> {
+    let manifest = Path::new(file_path).with_file_name("coral.toml");
+    let mut config = match fs::read_to_string(&manifest) {
+        Ok(source) => LintConfig::from_toml(&source)?,
+        Err(_) => LintConfig::new(),
+    };
+    for (lint, level) in flags {
+        config.set_by_name(lint, *level)?;
+    }
+    Ok(config)
+}
+
+fn report(severity: &str, error: &SemanticError) {
+    eprintln!(
+        "{}: {}\n  --> {}:{}:{}",
+        severity, error.message, error.span.file, error.span.start_line, error.span.start_col
+    );
+}
+
 #[cfg(test)]
 mod tests {
     use std::process::Command;
     use std::fs;
 
-    #[test]
-    fn test_full_features() {
-        let test_name = "full_features";
+    /// Compile `tests/<name>.cor`, link it with the runtime, run it and
+    /// compare what it prints with `tests/<name>.expected`
+    fn run_test(test_name: &str) {
         let coral_file = format!("tests/{}.cor", test_name);
         let expected_file = format!("tests/{}.expected", test_name);
         let ir_file = format!("tests/{}.ll", test_name);
         let obj_file = format!("tests/{}.o", test_name);
-        let runtime_obj_file = "runtime/runtime.o";
+        // One runtime object per test, since tests run in parallel
+        let runtime_obj_file = format!("tests/{}.runtime.o", test_name);
         let executable_file = format!("tests/{}", test_name);
 
         // Compile Coral to LLVM IR
@@ -50,17 +137,18 @@ mod tests {
         assert!(output.status.success(), "Coral compiler failed: {}", String::from_utf8_lossy(&output.stderr));
         fs::write(&ir_file, output.stdout).expect("Failed to write LLVM IR file");
 
-        // Compile LLVM IR to object file
+        // Compile LLVM IR to object file; position independent, since the
+        // system linker may produce a PIE
         let llc_output = Command::new("llc")
-            .args(&["-filetype=obj", &ir_file, "-o", &obj_file])
+            .args(&["-filetype=obj", "-relocation-model=pic", &ir_file, "-o", &obj_file])
             .output()
             .expect("Failed to run llc");
         
-        assert!(llc_output.status.success(), "llc failed");
+        assert!(llc_output.status.success(), "llc failed: {}", String::from_utf8_lossy(&llc_output.stderr));
 
         // Compile C runtime to object file
         let cc_output = Command::new("cc")
-            .args(&["-c", "runtime/runtime.c", "-o", runtime_obj_file])
+            .args(&["-c", "runtime/runtime.c", "-o", &runtime_obj_file])
             .output()
             .expect("Failed to compile runtime");
 
@@ -68,7 +156,7 @@ mod tests {
 
         // Link object files
         let link_output = Command::new("cc")
-            .args(&[&obj_file, runtime_obj_file, "-o", &executable_file])
+            .args(&[&obj_file, &runtime_obj_file, "-o", &executable_file])
             .output()
             .expect("Failed to link object files");
 
@@ -79,7 +167,33 @@ mod tests {
             .output()
             .expect("Failed to run executable");
 
+        assert!(run_output.status.success(), "{} exited with {}", executable_file, run_output.status);
         let expected_output = fs::read_to_string(expected_file).expect("Failed to read expected output file");
         assert_eq!(String::from_utf8_lossy(&run_output.stdout), expected_output);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_full_features() {
+        run_test("full_features");
+    }
+
+    #[test]
+    fn test_enum_payloads() {
+        run_test("enum_payloads");
+    }
+
+    #[test]
+    fn test_inferred_functions() {
+        run_test("inferred_functions");
+    }
+
+    #[test]
+    fn test_string_indexing() {
+        run_test("string_indexing");
+    }
+
+    #[test]
+    fn test_formatting() {
+        run_test("formatting");
+    }
+}
@@ -0,0 +1,468 @@
+//! Name resolution: links every identifier to the definition it refers to.
+//!
+//! The module, each function and method, each `iterate` body and each lambda
+//! open a lexical scope; `if` and loop bodies share the enclosing one.
+//! Functions, types, imports and module-level variables are visible throughout
+//! their scope, other variables from their first assignment on. A method's
+//! fields sit in a scope around its parameters, so a parameter may shadow one.
+
+use crate::ast::*;
+use crate::semantic::SemanticError;
+use crate::tables::{Definition, DefinitionKind, ResolutionTable};
+use std::collections::HashMap;
+
+/// Names the runtime provides without a definition in source
+const BUILTINS: &[&str] = &["log", "print", "now", "empty", "hash"];
+
+pub struct NameResolver {
+    scopes: Vec<HashMap<String, Definition>>,
+    resolutions: ResolutionTable,
+    errors: Vec<SemanticError>,
+}
+
+impl Default for NameResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NameResolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()], // Module scope
+            resolutions: ResolutionTable::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The def-use links found so far
+    pub fn resolutions(&self) -> &ResolutionTable {
+        &self.resolutions
+    }
+
+    pub fn into_resolutions(self) -> ResolutionTable {
+        self.resolutions
+    }
+
+    pub fn resolve_program(&mut self, program: &Program) -> Result<(), Vec<SemanticError>> {
+        self.declare_items(&program.statements);
+        self.declare_module_variables(&program.statements);
+        self.visit_block(&program.statements);
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors.clone())
+        }
+    }
+
+    fn define(&mut self, name: &str, kind: DefinitionKind, span: &SourceSpan) {
+        let scope = self.scopes.last_mut().unwrap();
+        if scope.contains_key(name) {
+            self.errors.push(SemanticError {
+                message: format!("Symbol '{}' already defined in this scope", name),
+                span: span.clone(),
+            });
+            return;
+        }
+        let definition = Definition {
+            name: name.to_string(),
+            kind,
+            span: span.clone(),
+        };
+        scope.insert(name.to_string(), definition.clone());
+
+        let binding = matches!(kind, DefinitionKind::Variable | DefinitionKind::Parameter);
+        if binding && name != "$" && name != "self" {
+            let outer = self.scopes.iter().rev().skip(1).find_map(|scope| scope.get(name));
+            if let Some(outer) = outer.cloned() {
+                self.resolutions.shadow(definition.clone(), outer);
+            }
+        }
+        self.resolutions.introduce(definition);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Definition> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// Run `visit` inside a fresh scope
+    fn scoped(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.scopes.push(HashMap::new());
+        visit(self);
+        self.scopes.pop();
+    }
+
+    /// Functions, types and imports are visible before their definition
+    fn declare_items(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Function { name, .. } => self.define(name, DefinitionKind::Function, &stmt.span),
+                StmtKind::Object { name, .. }
+                | StmtKind::Store { name, .. }
+                | StmtKind::Actor { name, .. }
+                | StmtKind::Enum { name, .. }
+                | StmtKind::Trait { name, .. } => self.define(name, DefinitionKind::Type, &stmt.span),
+                StmtKind::Import { items: Some(items), .. } => {
+                    for item in items {
+                        self.define(item, DefinitionKind::Module, &stmt.span);
+                    }
+                }
+                StmtKind::Import { module, items: None } => {
+                    // `import 'lib/geometry.cor'` brings `geometry` into scope
+                    let file = module.rsplit('/').next().unwrap_or(module);
+                    let stem = file.split('.').next().unwrap_or(file);
+                    self.define(stem, DefinitionKind::Module, &stmt.span);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Module-level variables, so functions can read ones assigned later in the file
+    fn declare_module_variables(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match &stmt.kind {
+                StmtKind::Assignment { target, .. } => {
+                    if let ExprKind::Identifier(name) = &target.kind {
+                        if self.lookup(name).is_none() {
+                            self.define(name, DefinitionKind::Variable, &target.span);
+                        }
+                    }
+                }
+                StmtKind::If { then_branch, else_branch, .. } => {
+                    self.declare_module_variables(then_branch);
+                    if let Some(else_stmts) = else_branch {
+                        self.declare_module_variables(else_stmts);
+                    }
+                }
+                StmtKind::While { body, .. } | StmtKind::Unless { body, .. } | StmtKind::Until { body, .. } => {
+                    self.declare_module_variables(body);
+                }
+                StmtKind::ErrorHandler { inner, .. } => self.declare_module_variables(std::slice::from_ref(inner)),
+                _ => {}
+            }
+        }
+    }
+
+    fn visit_block(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.visit_stmt(stmt);
+        }
+    }
+
+    /// A function or method body: parameters, then hoisted items, then statements
+    fn visit_callable(&mut self, receiver: Option<&[Field]>, params: &[Parameter], body: &[Stmt], span: &SourceSpan) {
+        let Some(fields) = receiver else {
+            self.scoped(|names| names.visit_params_and_body(params, body));
+            return;
+        };
+        self.scoped(|names| {
+            names.define("self", DefinitionKind::Parameter, span);
+            for field in fields {
+                names.define(&field.name, DefinitionKind::Field, &field.span);
+            }
+            names.scoped(|names| names.visit_params_and_body(params, body));
+        });
+    }
+
+    fn visit_params_and_body(&mut self, params: &[Parameter], body: &[Stmt]) {
+        for param in params {
+            if let Some(default) = &param.default_value {
+                self.visit_expr(default);
+            }
+            self.define(&param.name, DefinitionKind::Parameter, &param.span);
+        }
+        self.declare_items(body);
+        self.visit_block(body);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Function { params, body, .. } => self.visit_callable(None, params, body, &stmt.span),
+
+            StmtKind::Object { fields, methods, .. } | StmtKind::Store { fields, methods, .. } => {
+                for field in fields {
+                    if let Some(default) = &field.default_value {
+                        self.visit_expr(default);
+                    }
+                }
+                for method in methods {
+                    self.visit_callable(Some(fields), &method.params, &method.body, &method.span);
+                }
+            }
+
+            StmtKind::Actor { fields, handlers, .. } => {
+                for handler in handlers {
+                    self.visit_callable(Some(fields), &[], &handler.body, &handler.span);
+                }
+            }
+
+            StmtKind::Expression(expr) => self.visit_expr(expr),
+
+            StmtKind::Assignment { target, value } => {
+                self.visit_expr(value);
+                // The first assignment to a name defines it
+                if let ExprKind::Identifier(name) = &target.kind {
+                    if self.lookup(name).is_none() {
+                        self.define(name, DefinitionKind::Variable, &target.span);
+                    }
+                }
+                if matches!(target.kind, ExprKind::Identifier(_)) {
+                    self.resolutions.mark_write(target.id);
+                }
+                self.visit_expr(target);
+            }
+
+            StmtKind::If { condition, then_branch, else_branch } => {
+                self.visit_expr(condition);
+                self.visit_block(then_branch);
+                if let Some(else_stmts) = else_branch {
+                    self.visit_block(else_stmts);
+                }
+            }
+
+            StmtKind::While { condition, body }
+            | StmtKind::Unless { condition, body }
+            | StmtKind::Until { condition, body } => {
+                self.visit_expr(condition);
+                self.visit_block(body);
+            }
+
+            StmtKind::Iterate { iterable, body } => {
+                self.visit_expr(iterable);
+                self.scoped(|names| {
+                    // `$` names the current element inside the loop body
+                    names.define("$", DefinitionKind::Variable, &iterable.span);
+                    names.declare_items(body);
+                    names.visit_block(body);
+                });
+            }
+
+            StmtKind::Return(value) => {
+                if let Some(expr) = value {
+                    self.visit_expr(expr);
+                }
+            }
+
+            StmtKind::ErrorHandler { handler, inner } => {
+                self.visit_stmt(inner);
+                for action in &handler.actions {
+                    match action {
+                        ErrorAction::Log(Some(expr)) | ErrorAction::Return(Some(expr)) | ErrorAction::Custom(expr) => {
+                            self.visit_expr(expr);
+                        }
+                        ErrorAction::Log(None) | ErrorAction::Return(None) => {}
+                    }
+                }
+            }
+
+            StmtKind::Enum { .. }
+            | StmtKind::Trait { .. }
+            | StmtKind::Import { .. }
+            | StmtKind::Break
+            | StmtKind::Continue => {}
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.resolve(name, expr),
+            ExprKind::Literal(_) => {}
+            ExprKind::Binary { left, right, .. } => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            ExprKind::Unary { operand, .. } => self.visit_expr(operand),
+            ExprKind::Call { callee, args } => {
+                self.visit_expr(callee);
+                for arg in args {
+                    self.visit_expr(&arg.value);
+                }
+            }
+            ExprKind::Index { object, index } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
+            ExprKind::FieldAccess { object, .. } => self.visit_expr(object),
+            ExprKind::ListLiteral(elements) => {
+                for element in elements {
+                    self.visit_expr(element);
+                }
+            }
+            ExprKind::MapLiteral(pairs) => {
+                for (key, value) in pairs {
+                    self.visit_expr(key);
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::ListAppend { list, element } => {
+                self.visit_expr(list);
+                self.visit_expr(element);
+            }
+            ExprKind::MapInsert { map, key, value } => {
+                self.visit_expr(map);
+                self.visit_expr(key);
+                self.visit_expr(value);
+            }
+            ExprKind::Across { callee, iterable, .. } => {
+                self.visit_expr(callee);
+                self.visit_expr(iterable);
+            }
+            ExprKind::StringInterpolation { parts } => {
+                for part in parts {
+                    if let StringPart::Expression(inner) = part {
+                        self.visit_expr(inner);
+                    }
+                }
+            }
+            ExprKind::If { condition, then_branch, else_branch } => {
+                self.visit_expr(condition);
+                self.visit_expr(then_branch);
+                if let Some(else_expr) = else_branch {
+                    self.visit_expr(else_expr);
+                }
+            }
+            ExprKind::Block(stmts) => self.visit_block(stmts),
+            ExprKind::Lambda { params, body } => {
+                self.scoped(|names| {
+                    for param in params {
+                        names.define(&param.name, DefinitionKind::Parameter, &param.span);
+                    }
+                    names.visit_expr(body);
+                });
+            }
+            ExprKind::ObjectInstantiation { fields, .. } => {
+                for (_, value) in fields {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::ErrValue(inner) | ExprKind::Cast { expr: inner, .. } => self.visit_expr(inner),
+        }
+    }
+
+    fn resolve(&mut self, name: &str, expr: &Expr) {
+        if let Some(definition) = self.lookup(name) {
+            let definition = definition.clone();
+            self.resolutions.record_use(expr.id, definition);
+        } else if !BUILTINS.contains(&name) {
+            let message = match self.suggest(name) {
+                Some(suggestion) => format!("Unknown name '{}', did you mean '{}'?", name, suggestion),
+                None => format!("Unknown name '{}'", name),
+            };
+            self.errors.push(SemanticError { message, span: expr.span.clone() });
+        }
+    }
+
+    /// Closest visible name, if one is within a typo's distance of `name`
+    fn suggest(&self, name: &str) -> Option<String> {
+        let max_distance = (name.chars().count() / 3).max(1);
+        self.scopes.iter()
+            .flat_map(|scope| scope.keys().map(String::as_str))
+            .chain(BUILTINS.iter().copied())
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min()
+            .map(|(_, candidate)| candidate.to_string())
+    }
+}
+
+/// Edit distance between two names, counting a swap of neighbours as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in distances[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = best;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn resolve(input: &str) -> (Program, NameResolver, Result<(), Vec<SemanticError>>) {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        let program = parser.parse().unwrap();
+        let mut names = NameResolver::new();
+        let result = names.resolve_program(&program);
+        (program, names, result)
+    }
+
+    #[test]
+    fn test_undefined_name_suggestion() {
+        let (_, _, result) = resolve("fn main()\n    count is 1\n    log(cout)\n");
+        let errors = result.unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unknown name 'cout', did you mean 'count'?");
+
+        let (_, _, result) = resolve("fn main()\n    log(zebra)\n");
+        assert_eq!(result.unwrap_err()[0].message, "Unknown name 'zebra'");
+    }
+
+    #[test]
+    fn test_scopes() {
+        // Parameters and iterate bodies are local to their scope
+        assert!(resolve("fn f(x)\n    return x\nfn g()\n    return x\n").2.is_err());
+        assert!(resolve("fn f(xs)\n    iterate xs\n        item is $\n    return item\n").2.is_err());
+        // If bodies share the function scope
+        assert!(resolve("fn f(flag)\n    if flag\n        y is 1\n    return y\n").2.is_ok());
+        // Methods see their object's fields, and parameters may shadow them
+        assert!(resolve("object point\n    x\n    y\n    sum() -> i64:\n        return x + y\n").2.is_ok());
+        assert!(resolve("object point\n    x: i64\n    set(x: i64):\n        self.x is x\n").2.is_ok());
+        // Functions read module variables assigned later in the file
+        assert!(resolve("fn report() -> i64\n    return total\ntotal is 3\n").2.is_ok());
+    }
+
+    #[test]
+    fn test_def_use_links() {
+        let (program, names, result) = resolve("fn main()\n    total is helper(2)\n    return total\nfn helper(n)\n    return n\n");
+        assert!(result.is_ok());
+        let StmtKind::Function { body, .. } = &program.statements[0].kind else {
+            panic!("Expected function");
+        };
+        let StmtKind::Assignment { target, value } = &body[0].kind else {
+            panic!("Expected assignment");
+        };
+        let ExprKind::Call { callee, .. } = &value.kind else {
+            panic!("Expected call");
+        };
+        let helper = names.resolutions().get(callee.id).unwrap();
+        assert_eq!(helper.kind, DefinitionKind::Function);
+        assert_eq!(helper.span, program.statements[1].span);
+
+        let StmtKind::Return(Some(returned)) = &body[1].kind else {
+            panic!("Expected return");
+        };
+        let total = names.resolutions().get(target.id).unwrap().clone();
+        assert_eq!(names.resolutions().uses_of(&total), vec![target.id, returned.id]);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("count", "cout"), 1);
+        assert_eq!(edit_distance("count", "cuont"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+}
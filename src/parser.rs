@@ -1,9 +1,9 @@
 use crate::ast::{
     Program, Stmt, StmtKind, Expr, ExprKind, Type, 
     BinaryOp, UnaryOp, Literal, Parameter, Field, MessageHandler, ObjectMethod, Argument,
-    SourceSpan
+    EnumVariant, TraitMethod, SourceSpan, NodeIdGen, conversion_method_name
 };
-use crate::lexer::{Token, TokenType};
+use crate::lexer::{keyword, Token, TokenType};
 use std::collections::HashMap;
 
 /// Parser errors with source location information
@@ -61,6 +61,9 @@ pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
     file_name: std::sync::Arc<str>, // Shared to avoid cloning
+    ids: NodeIdGen,
+    /// Type parameters declared by the enclosing `of T` clause
+    type_params: Vec<String>,
 }
 
 impl Parser {
@@ -69,6 +72,8 @@ impl Parser {
             tokens,
             current: 0,
             file_name: file_name.into(), // Convert to Arc<str>
+            ids: NodeIdGen::new(),
+            type_params: Vec::new(),
         }
     }
     
@@ -117,9 +122,19 @@ impl Parser {
         self.skip_newlines();
         match self.peek().token_type {
             TokenType::Fn => self.parse_function_statement(),
+            TokenType::Identifier if self.peek().lexeme == "pure" && self.check_at_offset(1, TokenType::Fn) => {
+                self.advance(); // consume 'pure'
+                let mut stmt = self.parse_function_statement()?;
+                if let StmtKind::Function { is_pure, .. } = &mut stmt.kind {
+                    *is_pure = true;
+                }
+                Ok(stmt)
+            }
             TokenType::Object => self.parse_object_statement(),
             TokenType::Store => self.parse_store_statement(),
             TokenType::Actor => self.parse_actor_statement(),
+            TokenType::Enum => self.parse_enum_statement(),
+            TokenType::Trait => self.parse_trait_statement(),
             TokenType::If => self.parse_if_statement(),
             TokenType::Unless => self.parse_unless_statement(),
             TokenType::While => self.parse_while_statement(),
@@ -130,14 +145,21 @@ impl Parser {
             TokenType::Continue => self.parse_continue_statement(),
             TokenType::Import => self.parse_import_statement(),
             _ => {
-                let expr = self.parse_expression()?;
+                let mut expr = self.parse_expression()?;
+
+                // Command call: a name followed by its arguments, as in `log p`
+                if matches!(expr.kind, ExprKind::Identifier(_)) && self.starts_bare_argument() {
+                    let args = self.parse_argument_list()?;
+                    let span = self.span_between(&expr.span, &self.token_to_span(&self.previous()));
+                    expr = Expr::new(self.ids.fresh(), span, ExprKind::call(expr, args));
+                }
 
                 // Postfix unless
                 if self.match_token(TokenType::Unless) {
                     let condition = self.parse_expression()?;
-                    let body_stmt = Stmt::new(expr.span.clone(), StmtKind::Expression(expr));
+                    let body_stmt = Stmt::new(self.ids.fresh(), expr.span.clone(), StmtKind::Expression(expr));
                     let span = self.span_between(&body_stmt.span, &condition.span);
-                    return Ok(Stmt::new(span, StmtKind::Unless {
+                    return Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Unless {
                         condition,
                         body: vec![body_stmt],
                     }));
@@ -148,12 +170,12 @@ impl Parser {
                     self.advance(); // consume 'is'
                     let value = self.parse_expression()?;
                     let span = self.span_between(&expr.span, &value.span);
-                    return Ok(Stmt::new(span, StmtKind::Assignment { target: expr, value }));
+                    return Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Assignment { target: expr, value }));
                 }
 
                 // Regular expression statement
                 let span = expr.span.clone();
-                let stmt = Stmt::new(span, StmtKind::Expression(expr));
+                let stmt = Stmt::new(self.ids.fresh(), span, StmtKind::Expression(expr));
                 self.skip_newlines();
                 return Ok(stmt);
             }
@@ -163,6 +185,25 @@ impl Parser {
     fn parse_function_statement(&mut self) -> ParseResult<Stmt> {
         let start = self.advance(); // consume 'fn'
         let name_token = self.consume(TokenType::Identifier, "Expected function name")?;
+        // Generic parameters: fn first of T(xs: (T)) -> T
+        let type_params = self.parse_type_params()?;
+        let outer_type_params = std::mem::replace(&mut self.type_params, type_params);
+        let signature = self.parse_function_signature_and_body();
+        self.type_params = outer_type_params;
+        let (params, return_type, body) = signature?;
+
+        let span = self.span_from_token(&start);
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Function {
+            name: name_token.lexeme,
+            params,
+            return_type,
+            body,
+            is_pure: false,
+        }))
+    }
+    
+    /// Parameter list, return type and body of a function after its name
+    fn parse_function_signature_and_body(&mut self) -> ParseResult<(Vec<Parameter>, Option<Type>, Vec<Stmt>)> {
         self.consume(TokenType::LeftParen, "Expected '(' after function name")?;
         let params = self.parse_parameter_list()?;
         self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
@@ -176,26 +217,55 @@ impl Parser {
 
         // The parse_block_statements function will handle consuming the Indent and Dedent
         let body = self.parse_block_statements()?;
-
-        let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Function {
-            name: name_token.lexeme,
-            params,
-            return_type,
-            body,
-        }))
+        Ok((params, return_type, body))
+    }
+    
+    /// An optional `of T, U` clause declaring type parameters
+    fn parse_type_params(&mut self) -> ParseResult<Vec<String>> {
+        let mut type_params = Vec::new();
+        if self.check(TokenType::Identifier) && self.peek().lexeme == "of" {
+            self.advance(); // consume 'of'
+            loop {
+                let param_token = self.consume(TokenType::Identifier, "Expected type parameter after 'of'")?;
+                type_params.push(param_token.lexeme);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
+        Ok(type_params)
     }
     
     fn parse_object_statement(&mut self) -> ParseResult<Stmt> {
         let start = self.advance(); // consume 'object'
         let name_token = self.consume(TokenType::Identifier, "Expected object name")?;
+        
+        // Generic parameters: object stack of T
+        let type_params = self.parse_type_params()?;
+        
+        // Trait conformance: object circle is shape, printable
+        let mut traits = Vec::new();
+        if self.match_token(TokenType::Is) {
+            loop {
+                let trait_token = self.consume(TokenType::Identifier, "Expected trait name after 'is'")?;
+                traits.push(trait_token.lexeme);
+                if !self.match_token(TokenType::Comma) {
+                    break;
+                }
+            }
+        }
         self.consume(TokenType::Newline, "Expected newline after object name")?;
         
-        let (fields, methods) = self.parse_object_body()?;
+        let outer_type_params = std::mem::replace(&mut self.type_params, type_params.clone());
+        let body = self.parse_object_body();
+        self.type_params = outer_type_params;
+        let (fields, methods) = body?;
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Object { 
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Object { 
             name: name_token.lexeme,
+            type_params,
+            traits,
             fields, 
             methods 
         }))
@@ -212,7 +282,7 @@ impl Parser {
         let (fields, methods) = self.parse_object_body()?;
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Store {
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Store {
             name,
             fields,
             methods,
@@ -230,7 +300,115 @@ impl Parser {
         let (fields, _methods, handlers) = self.parse_actor_body()?;
 
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Actor { name, fields, handlers }))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Actor { name, fields, handlers }))
+    }
+
+    fn parse_enum_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance(); // consume 'enum'
+        let name_token = self.consume(TokenType::Identifier, "Expected enum name")?;
+        self.consume(TokenType::Newline, "Expected newline after enum name")?;
+
+        if !self.match_token(TokenType::Indent) {
+            return Err(ParseError::UnexpectedToken {
+                expected: "indented block of enum variants".to_string(),
+                found: self.peek().clone(),
+            });
+        }
+
+        let mut variants = Vec::new();
+        while !self.check(TokenType::Dedent) && !self.is_at_end() {
+            self.skip_newlines();
+            if self.check(TokenType::Dedent) || self.is_at_end() {
+                break;
+            }
+
+            let variant_token = self.consume(TokenType::Identifier, "Expected variant name")?;
+            let mut payload = Vec::new();
+            if self.match_token(TokenType::LeftParen) {
+                if !self.check(TokenType::RightParen) {
+                    loop {
+                        payload.push(self.parse_type()?);
+                        if !self.match_token(TokenType::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(TokenType::RightParen, "Expected ')' after variant payload types")?;
+            }
+
+            if variants.iter().any(|v: &EnumVariant| v.name == variant_token.lexeme) {
+                return Err(ParseError::DuplicateDefinition {
+                    name: variant_token.lexeme.clone(),
+                    span: self.token_to_span(&variant_token),
+                });
+            }
+
+            let span = self.span_between(&self.token_to_span(&variant_token), &self.token_to_span(&self.previous()));
+            variants.push(EnumVariant { name: variant_token.lexeme, payload, span });
+            self.skip_newlines();
+        }
+
+        if self.check(TokenType::Dedent) {
+            self.advance();
+        }
+
+        let span = self.span_from_token(&start);
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Enum { name: name_token.lexeme, variants }))
+    }
+
+    fn parse_trait_statement(&mut self) -> ParseResult<Stmt> {
+        let start = self.advance(); // consume 'trait'
+        let name_token = self.consume(TokenType::Identifier, "Expected trait name")?;
+        self.consume(TokenType::Newline, "Expected newline after trait name")?;
+
+        if !self.match_token(TokenType::Indent) {
+            return Err(ParseError::UnexpectedToken {
+                expected: "indented block of method signatures".to_string(),
+                found: self.peek().clone(),
+            });
+        }
+
+        let mut methods = Vec::new();
+        while !self.check(TokenType::Dedent) && !self.is_at_end() {
+            self.skip_newlines();
+            if self.check(TokenType::Dedent) || self.is_at_end() {
+                break;
+            }
+
+            // Signatures may be written with or without a leading 'fn'
+            self.match_token(TokenType::Fn);
+            let method_token = self.consume(TokenType::Identifier, "Expected method name")?;
+            let params = if self.match_token(TokenType::LeftParen) {
+                let params = self.parse_parameter_list()?;
+                self.consume(TokenType::RightParen, "Expected ')' after parameters")?;
+                params
+            } else {
+                Vec::new()
+            };
+            let return_type = if self.match_token(TokenType::Arrow) {
+                Some(self.parse_type()?)
+            } else {
+                None
+            };
+
+            if methods.iter().any(|m: &TraitMethod| m.name == method_token.lexeme) {
+                return Err(ParseError::DuplicateDefinition {
+                    name: method_token.lexeme.clone(),
+                    span: self.token_to_span(&method_token),
+                });
+            }
+
+            let span = self.span_between(&self.token_to_span(&method_token), &self.token_to_span(&self.previous()));
+            methods.push(TraitMethod { name: method_token.lexeme, params, return_type, span });
+            self.skip_newlines();
+        }
+
+        if self.check(TokenType::Dedent) {
+            self.advance();
+        }
+
+        let span = self.span_from_token(&start);
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Trait { name: name_token.lexeme, methods }))
     }
 
     fn parse_actor_body(&mut self) -> ParseResult<(Vec<Field>, Vec<ObjectMethod>, Vec<MessageHandler>)> {
@@ -275,7 +453,12 @@ impl Parser {
         let mut fields = Vec::new();
         let mut methods = Vec::new();
 
-        let name_token = self.consume(TokenType::Identifier, "Expected field or method name")?;
+        if self.check(TokenType::As) {
+            methods.push(self.parse_conversion_method()?);
+            return Ok((fields, methods));
+        }
+
+        let name_token = self.consume_member_name()?;
         let name = name_token.lexeme.clone();
         let start_span_for_member = self.token_to_span(&name_token);
 
@@ -330,6 +513,12 @@ impl Parser {
 
             let span = self.span_between(&start_span_for_member, &body.last().map(|s| &s.span).unwrap_or(&start_span_for_member));
             methods.push(ObjectMethod { name, params, return_type, body, span });
+        } else if self.starts_bare_method() {
+            // A bare name over an indented block is a method without parameters
+            self.skip_newlines();
+            let body = self.parse_block_statements()?;
+            let span = self.span_between(&start_span_for_member, body.last().map(|s| &s.span).unwrap_or(&start_span_for_member));
+            methods.push(ObjectMethod { name, params: Vec::new(), return_type: None, body, span });
         } else {
             let type_ = Type::Unknown;
             let default_value = None;
@@ -374,7 +563,7 @@ impl Parser {
         };
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::If {
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::If {
             condition,
             then_branch,
             else_branch,
@@ -387,7 +576,7 @@ impl Parser {
         self.skip_newlines();
         let body = self.parse_block_statements()?;
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Unless { condition, body }))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Unless { condition, body }))
     }
 
     fn parse_while_statement(&mut self) -> ParseResult<Stmt> {
@@ -396,7 +585,7 @@ impl Parser {
         self.skip_newlines();
         let body = self.parse_block_statements()?;
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::While { condition, body }))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::While { condition, body }))
     }
 
     fn parse_until_statement(&mut self) -> ParseResult<Stmt> {
@@ -405,7 +594,7 @@ impl Parser {
         self.skip_newlines();
         let body = self.parse_block_statements()?;
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Until { condition, body }))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Until { condition, body }))
     }
 
     fn parse_iterate_statement(&mut self) -> ParseResult<Stmt> {
@@ -415,6 +604,7 @@ impl Parser {
         let body = self.parse_block_statements()?;
         let span = self.span_from_token(&start);
         Ok(Stmt::new(
+            self.ids.fresh(),
             span,
             StmtKind::Iterate {
                 iterable,
@@ -433,21 +623,21 @@ impl Parser {
         };
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Return(value)))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Return(value)))
     }
     
     fn parse_break_statement(&mut self) -> ParseResult<Stmt> {
         let start = self.advance(); // consume 'break'
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Break))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Break))
     }
     
     fn parse_continue_statement(&mut self) -> ParseResult<Stmt> {
         let start = self.advance(); // consume 'continue'
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Continue))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Continue))
     }
     
     fn parse_import_statement(&mut self) -> ParseResult<Stmt> {
@@ -477,7 +667,7 @@ impl Parser {
         };
         
         let span = self.span_from_token(&start);
-        Ok(Stmt::new(span, StmtKind::Import { module, items }))
+        Ok(Stmt::new(self.ids.fresh(), span, StmtKind::Import { module, items }))
     }
 
     
@@ -498,7 +688,7 @@ impl Parser {
             let else_expr = self.parse_ternary()?; // Allow nested ternary (right-associative)
             
             let span = self.span_between(&expr.span, &else_expr.span);
-            expr = Expr::new(span, ExprKind::If {
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::If {
                 condition: Box::new(expr),
                 then_branch: Box::new(then_expr),
                 else_branch: Some(Box::new(else_expr)),
@@ -514,7 +704,7 @@ impl Parser {
         while self.match_token(TokenType::Or) || self.match_token(TokenType::LogicalOr) {
             let right = self.parse_logical_and()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(BinaryOp::Or, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(BinaryOp::Or, expr, right));
         }
         
         Ok(expr)
@@ -526,7 +716,7 @@ impl Parser {
         while self.match_token(TokenType::And) || self.match_token(TokenType::LogicalAnd) {
             let right = self.parse_equality()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(BinaryOp::And, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(BinaryOp::And, expr, right));
         }
         
         Ok(expr)
@@ -547,7 +737,7 @@ impl Parser {
             if let Some(op) = op {
                 let right = self.parse_comparison()?;
                 let span = self.span_between(&expr.span, &right.span);
-                expr = Expr::new(span, ExprKind::binary(op, expr, right));
+                expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(op, expr, right));
             } else {
                 break;
             }
@@ -562,7 +752,7 @@ impl Parser {
         while let Some(op) = self.match_comparison_op() {
             let right = self.parse_bitwise_or()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(op, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(op, expr, right));
         }
         
         Ok(expr)
@@ -574,7 +764,7 @@ impl Parser {
         while self.match_token(TokenType::Pipe) {
             let right = self.parse_bitwise_xor()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(BinaryOp::BitOr, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(BinaryOp::BitOr, expr, right));
         }
         
         Ok(expr)
@@ -586,7 +776,7 @@ impl Parser {
         while self.match_token(TokenType::Caret) {
             let right = self.parse_bitwise_and()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(BinaryOp::BitXor, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(BinaryOp::BitXor, expr, right));
         }
         
         Ok(expr)
@@ -598,7 +788,7 @@ impl Parser {
         while self.match_token(TokenType::Ampersand) {
             let right = self.parse_shift()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(BinaryOp::BitAnd, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(BinaryOp::BitAnd, expr, right));
         }
         
         Ok(expr)
@@ -610,7 +800,7 @@ impl Parser {
         while let Some(op) = self.match_shift_op() {
             let right = self.parse_term()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(op, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(op, expr, right));
         }
         
         Ok(expr)
@@ -622,19 +812,34 @@ impl Parser {
         while let Some(op) = self.match_term_op() {
             let right = self.parse_factor()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(op, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(op, expr, right));
         }
         
         Ok(expr)
     }
     
     fn parse_factor(&mut self) -> ParseResult<Expr> {
-        let mut expr = self.parse_unary()?;
+        let mut expr = self.parse_cast()?;
         
         while let Some(op) = self.match_factor_op() {
-            let right = self.parse_unary()?;
+            let right = self.parse_cast()?;
             let span = self.span_between(&expr.span, &right.span);
-            expr = Expr::new(span, ExprKind::binary(op, expr, right));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::binary(op, expr, right));
+        }
+        
+        Ok(expr)
+    }
+    
+    fn parse_cast(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.parse_unary()?;
+        
+        while self.match_token(TokenType::As) {
+            let target = self.parse_type()?;
+            let span = self.span_between(&expr.span, &self.token_to_span(&self.previous()));
+            expr = Expr::new(self.ids.fresh(), span, ExprKind::Cast {
+                expr: Box::new(expr),
+                target,
+            });
         }
         
         Ok(expr)
@@ -644,7 +849,7 @@ impl Parser {
         if let Some(op) = self.match_unary_op() {
             let operand = self.parse_unary()?;
             let span = self.span_from_current();
-            return Ok(Expr::new(span, ExprKind::Unary {
+            return Ok(Expr::new(self.ids.fresh(), span, ExprKind::Unary {
                 op,
                 operand: Box::new(operand),
             }));
@@ -663,27 +868,32 @@ impl Parser {
                 self.consume(TokenType::RightParen, "Expected ')' after arguments")?;
                 
                 let span = self.span_from_current();
-                expr = Expr::new(span, ExprKind::call(expr, args));
+                expr = Expr::new(self.ids.fresh(), span, ExprKind::call(expr, args));
             } else if self.match_token(TokenType::LeftBracket) {
                 // Index access
                 let index = self.parse_expression()?;
                 self.consume(TokenType::RightBracket, "Expected ']' after index")?;
                 
                 let span = self.span_between(&expr.span, &index.span);
-                expr = Expr::new(span, ExprKind::Index {
+                expr = Expr::new(self.ids.fresh(), span, ExprKind::Index {
                     object: Box::new(expr),
                     index: Box::new(index),
                 });
             } else if self.match_token(TokenType::Dot) {
                 // Field access or list append
-                let field_token = self.consume(TokenType::Identifier, "Expected field name or 'put' for list append")?;
+                // `make` is a keyword but also the constructor reached as `type.make`
+                let field_token = if self.check(TokenType::Make) {
+                    self.advance()
+                } else {
+                    self.consume(TokenType::Identifier, "Expected field name or 'put' for list append")?
+                };
                 let field_name = field_token.lexeme.clone();
 
                 if field_name == "put" {
                     // List append: list.put item
                     let element = self.parse_expression()?;
                     let span = self.span_between(&expr.span, &element.span);
-                    expr = Expr::new(span, ExprKind::ListAppend {
+                    expr = Expr::new(self.ids.fresh(), span, ExprKind::ListAppend {
                         list: Box::new(expr),
                         element: Box::new(element),
                     });
@@ -704,7 +914,7 @@ impl Parser {
                     };
 
                     let span = self.span_between(&expr.span, &self.token_to_span(&self.previous()));
-                    expr = Expr::new(span, ExprKind::Across {
+                    expr = Expr::new(self.ids.fresh(), span, ExprKind::Across {
                         callee: Box::new(expr),
                         iterable: Box::new(iterable),
                         into,
@@ -712,10 +922,17 @@ impl Parser {
                 } else {
                     // Regular field access
                     let span = self.span_from_token(&field_token);
-                    expr = Expr::new(span, ExprKind::FieldAccess {
+                    expr = Expr::new(self.ids.fresh(), span, ExprKind::FieldAccess {
                         object: Box::new(expr),
-                        field: field_name,
+                        field: field_name.clone(),
                     });
+                    // `type.make 1, 2` passes constructor arguments without
+                    // parentheses, and a bare `type.make` passes none
+                    if field_name == "make" && !self.check(TokenType::LeftParen) {
+                        let args = if self.starts_bare_argument() { self.parse_argument_list()? } else { Vec::new() };
+                        let span = self.span_between(&expr.span, &self.token_to_span(&self.previous()));
+                        expr = Expr::new(self.ids.fresh(), span, ExprKind::call(expr, args));
+                    }
                 }
             } else {
                 break;
@@ -744,7 +961,7 @@ impl Parser {
                     message: format!("Invalid integer literal '{}': {}", lexeme_str, e),
                     span: span.clone(),
                 })?;
-                Ok(Expr::new(span, ExprKind::literal(Literal::Integer(value))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Integer(value))))
             }
             TokenType::Float => {
                 self.advance();
@@ -753,51 +970,52 @@ impl Parser {
                         message: format!("Invalid float literal '{}': {}", token.lexeme, e),
                         span: span.clone(),
                     })?;
-                Ok(Expr::new(span, ExprKind::literal(Literal::Float(value))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Float(value))))
             }
             TokenType::String => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::String(token.lexeme.clone()))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::String(token.lexeme.clone()))))
             }
             TokenType::InterpolatedString => {
                 self.advance();
                 let parts = self.parse_string_interpolation_from_content(&token.lexeme)?;
-                Ok(Expr::new(span, ExprKind::StringInterpolation { parts }))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::StringInterpolation { parts }))
             }
             TokenType::True => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::Bool(true))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Bool(true))))
             }
             TokenType::False => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::Bool(false))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Bool(false))))
             }
             // Coral-specific literals
             TokenType::No => {
+                // ... and 'no' is equivalent to 'false'
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::No)))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Bool(false))))
             }
             TokenType::Yes => {
                 // In Coral, 'yes' is equivalent to 'true'
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::Bool(true))))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Bool(true))))
             }
             TokenType::Empty => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::Empty)))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Empty)))
             }
             TokenType::Now => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::literal(Literal::Now)))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Now)))
             }
             TokenType::Identifier => {
                 self.advance();
-                Ok(Expr::new(span, ExprKind::identifier(token.lexeme.clone())))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::identifier(token.lexeme.clone())))
             }
             TokenType::Dollar => {
                 // $ refers to the current iteration item in Coral
                 self.advance();
-                Ok(Expr::new(span, ExprKind::identifier("$".to_string())))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::identifier("$".to_string())))
             }
             TokenType::LeftParen => {
                 self.advance();
@@ -806,7 +1024,7 @@ impl Parser {
                 if self.check(TokenType::RightParen) {
                     // Empty list
                     self.advance(); // consume ')'
-                    return Ok(Expr::new(span, ExprKind::ListLiteral(Vec::new())));
+                    return Ok(Expr::new(self.ids.fresh(), span, ExprKind::ListLiteral(Vec::new())));
                 }
                 
                 // Parse first expression
@@ -826,7 +1044,7 @@ impl Parser {
                     }
                     
                     self.consume(TokenType::RightParen, "Expected ')' after map pairs")?;
-                    Ok(Expr::new(span, ExprKind::MapLiteral(pairs)))
+                    Ok(Expr::new(self.ids.fresh(), span, ExprKind::MapLiteral(pairs)))
                 } else if self.match_token(TokenType::Comma) {
                     // This is a list literal - parse remaining elements
                     let mut elements = vec![first_expr];
@@ -841,7 +1059,7 @@ impl Parser {
                     }
                     
                     self.consume(TokenType::RightParen, "Expected ')' after list elements")?;
-                    Ok(Expr::new(span, ExprKind::ListLiteral(elements)))
+                    Ok(Expr::new(self.ids.fresh(), span, ExprKind::ListLiteral(elements)))
                 } else {
                     // This is a parenthesized expression
                     self.consume(TokenType::RightParen, "Expected ')' after expression")?;
@@ -856,12 +1074,24 @@ impl Parser {
                     self.parse_expression_list()?
                 };
                 self.consume(TokenType::RightBracket, "Expected ']' after list elements")?;
-                Ok(Expr::new(span, ExprKind::ListLiteral(elements)))
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::ListLiteral(elements)))
             }
             TokenType::LeftBrace => {
                 // Only parse map literals, no braced statement blocks
                 self.parse_map_literal()
             }
+            TokenType::Err => {
+                self.advance();
+                // A bare `err` is the untyped error literal; `err value` carries a payload
+                if self.check(TokenType::Newline) || self.check(TokenType::Dedent) || self.check(TokenType::RightParen)
+                    || self.check(TokenType::Comma) || self.is_at_end()
+                {
+                    return Ok(Expr::new(self.ids.fresh(), span, ExprKind::literal(Literal::Err)));
+                }
+                let payload = self.parse_unary()?;
+                let span = self.span_between(&span, &payload.span);
+                Ok(Expr::new(self.ids.fresh(), span, ExprKind::ErrValue(Box::new(payload))))
+            }
             TokenType::If => self.parse_if_expression(),
             TokenType::Fn => self.parse_lambda_expression(),
             _ => Err(ParseError::UnexpectedToken {
@@ -888,16 +1118,16 @@ impl Parser {
                 let else_stmts = self.parse_block_statements()?;
                 
                 let span = self.span_from_current();
-                Some(Box::new(Expr::new(span, ExprKind::Block(else_stmts))))
+                Some(Box::new(Expr::new(self.ids.fresh(), span, ExprKind::Block(else_stmts))))
             }
         } else {
             None
         };
         
         let span = self.span_from_token(&start);
-        Ok(Expr::new(span.clone(), ExprKind::If {
+        Ok(Expr::new(self.ids.fresh(), span.clone(), ExprKind::If {
             condition: Box::new(condition),
-            then_branch: Box::new(Expr::new(span.clone(), ExprKind::Block(then_stmts))),
+            then_branch: Box::new(Expr::new(self.ids.fresh(), span.clone(), ExprKind::Block(then_stmts))),
             else_branch,
         }))
     }
@@ -912,7 +1142,7 @@ impl Parser {
         if self.match_token(TokenType::Arrow) {
             let body = self.parse_expression()?;
             let span = self.span_from_token(&start);
-            Ok(Expr::new(span, ExprKind::Lambda {
+            Ok(Expr::new(self.ids.fresh(), span, ExprKind::Lambda {
                 params,
                 body: Box::new(body),
             }))
@@ -921,9 +1151,9 @@ impl Parser {
             let stmts = self.parse_block_statements()?;
             
             let span = self.span_from_token(&start);
-            Ok(Expr::new(span.clone(), ExprKind::Lambda {
+            Ok(Expr::new(self.ids.fresh(), span.clone(), ExprKind::Lambda {
                 params,
-                body: Box::new(Expr::new(span.clone(), ExprKind::Block(stmts))),
+                body: Box::new(Expr::new(self.ids.fresh(), span.clone(), ExprKind::Block(stmts))),
             }))
         }
     }
@@ -948,7 +1178,7 @@ impl Parser {
         self.consume(TokenType::RightBrace, "Expected '}' after map pairs")?;
         
         let span = self.span_from_token(&start);
-        Ok(Expr::new(span, ExprKind::MapLiteral(pairs)))
+        Ok(Expr::new(self.ids.fresh(), span, ExprKind::MapLiteral(pairs)))
     }
     
     // Helper methods for parsing components
@@ -992,6 +1222,42 @@ impl Parser {
 
     
     
+    /// Object member names may be the `make` keyword, naming the constructor body
+    fn consume_member_name(&mut self) -> ParseResult<Token> {
+        if self.check(TokenType::Make) {
+            Ok(self.advance())
+        } else {
+            self.consume(TokenType::Identifier, "Expected field or method name")
+        }
+    }
+
+    /// `as <type>` over an indented block: how the object converts to that type
+    fn parse_conversion_method(&mut self) -> ParseResult<ObjectMethod> {
+        let start = self.advance(); // consume 'as'
+        let target = self.parse_type()?;
+        self.skip_newlines();
+        let body = self.parse_block_statements()?;
+        let span = self.token_to_span(&start);
+        Ok(ObjectMethod { name: conversion_method_name(&target), params: Vec::new(), return_type: Some(target), body, span })
+    }
+
+    /// Whether a member name is followed by an indented block on the next line
+    fn starts_bare_method(&self) -> bool {
+        self.check(TokenType::Newline)
+            && self.tokens.get(self.current + 1).is_some_and(|t| t.token_type == TokenType::Indent)
+    }
+
+    /// Whether the next token can open an argument written without parentheses
+    fn starts_bare_argument(&self) -> bool {
+        matches!(
+            self.peek().token_type,
+            TokenType::Integer | TokenType::Float | TokenType::String | TokenType::InterpolatedString
+                | TokenType::True | TokenType::False | TokenType::Yes | TokenType::No
+                | TokenType::Empty | TokenType::Now | TokenType::Identifier | TokenType::Dollar
+                | TokenType::LeftBracket | TokenType::LeftBrace
+        )
+    }
+
     fn parse_argument_list(&mut self) -> ParseResult<Vec<Argument>> {
         let mut args = Vec::new();
         if self.check(TokenType::RightParen) {
@@ -1054,7 +1320,7 @@ impl Parser {
 
             if self.check(TokenType::Fn) {
                 let func_stmt = self.parse_function_statement()?;
-                if let StmtKind::Function { name, params, return_type, body } = func_stmt.kind {
+                if let StmtKind::Function { name, params, return_type, body, .. } = func_stmt.kind {
                     methods.push(ObjectMethod {
                         name,
                         params,
@@ -1066,7 +1332,12 @@ impl Parser {
                 continue;
             }
 
-            let name_token = self.consume(TokenType::Identifier, "Expected field or method name")?;
+            if self.check(TokenType::As) {
+                methods.push(self.parse_conversion_method()?);
+                continue;
+            }
+
+            let name_token = self.consume_member_name()?;
             let name = name_token.lexeme.clone();
 
             if self.check(TokenType::Colon) {
@@ -1099,6 +1370,11 @@ impl Parser {
                 let body = self.parse_block_statements()?;
                 let span = self.token_to_span(&name_token);
                 methods.push(ObjectMethod { name, params, return_type, body, span });
+            } else if self.starts_bare_method() {
+                self.skip_newlines();
+                let body = self.parse_block_statements()?;
+                let span = self.token_to_span(&name_token);
+                methods.push(ObjectMethod { name, params: Vec::new(), return_type: None, body, span });
             } else {
                 let type_ = Type::Unknown;
                 let default_value = None;
@@ -1185,6 +1461,18 @@ impl Parser {
     }
     
     fn parse_type(&mut self) -> ParseResult<Type> {
+        let ok_type = self.parse_base_type()?;
+
+        // `T ! E` is a result whose error side has type E
+        if self.match_token(TokenType::Bang) {
+            let err_type = self.parse_base_type()?;
+            return Ok(Type::Result(Box::new(ok_type), Box::new(err_type)));
+        }
+
+        Ok(ok_type)
+    }
+
+    fn parse_base_type(&mut self) -> ParseResult<Type> {
         let token = self.peek().clone();
         
         match &token.token_type {
@@ -1200,7 +1488,27 @@ impl Parser {
                     "bool" => Ok(Type::Bool),
                     "string" => Ok(Type::String),
                     "unit" => Ok(Type::Unit),
+                    // Parameters declared by the enclosing `of T, Key`
+                    name if self.type_params.iter().any(|param| param == name) => {
+                        Ok(Type::Param(name.to_string()))
+                    }
                     name => {
+                        // Applied generic: stack of i64, pair of (i64, string)
+                        if self.check(TokenType::Identifier) && self.peek().lexeme == "of" {
+                            self.advance(); // consume 'of'
+                            let args = if self.match_token(TokenType::LeftParen) {
+                                let mut args = vec![self.parse_type()?];
+                                while self.match_token(TokenType::Comma) {
+                                    args.push(self.parse_type()?);
+                                }
+                                self.consume(TokenType::RightParen, "Expected ')' after type arguments")?;
+                                args
+                            } else {
+                                vec![self.parse_type()?]
+                            };
+                            return Ok(Type::Generic { name: name.to_string(), args });
+                        }
+                        
                         // User-defined type - for now just create an object type
                         Ok(Type::Object {
                             name: name.to_string(),
@@ -1402,7 +1710,7 @@ impl Parser {
         self.tokens[self.current - 1].clone()
     }
     
-    fn _check_at_offset(&self, offset: usize, token_type: TokenType) -> bool {
+    fn check_at_offset(&self, offset: usize, token_type: TokenType) -> bool {
         if self.current + offset >= self.tokens.len() {
             false
         } else {
@@ -1562,6 +1870,7 @@ impl Parser {
         let trimmed = expr_text.trim();
         if self.is_simple_identifier(trimmed) {
             return Ok(Expr::new(
+                self.ids.fresh(),
                 self.span_from_current(),
                 ExprKind::identifier(trimmed.to_string())
             ));
@@ -1571,9 +1880,12 @@ impl Parser {
         self.parse_expression_from_string(&expr_text)
     }
     
-    /// Check if a string is a simple identifier (performance optimization)
+    /// Check if a string is a simple identifier (performance optimization).
+    /// Numbers and reserved words like `yes` take the full parse.
     fn is_simple_identifier(&self, s: &str) -> bool {
-        !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+        s.chars().next().is_some_and(|c| !c.is_ascii_digit())
+            && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+            && keyword(s).is_none()
     }
 
     fn handle_escape_sequence(&mut self, current_text: &mut String, chars: &mut std::iter::Peekable<std::str::Chars>) {
@@ -1607,8 +1919,12 @@ impl Parser {
             span: self.span_from_current(),
         })?;
         
+        // The nested parser keeps allocating from our IDs so nodes stay unique
         let mut expr_parser = Parser::new(tokens, self.file_name.to_string());
-        expr_parser.parse_expression().map_err(|e| {
+        expr_parser.ids = std::mem::take(&mut self.ids);
+        let result = expr_parser.parse_expression();
+        self.ids = expr_parser.ids;
+        result.map_err(|e| {
             // Preserve the original error type and provide better context
             match e {
                 ParseError::UnexpectedToken { expected, found } => ParseError::InvalidSyntax {
@@ -1798,6 +2114,14 @@ mod tests {
         } else {
             panic!("Expected string interpolation expression");
         }
+
+        // Literals inside braces are parsed as literals, not names
+        let expr = parse_expression("'{yes} {2}'").unwrap();
+        let ExprKind::StringInterpolation { parts } = expr.kind else {
+            panic!("Expected string interpolation expression");
+        };
+        assert!(matches!(&parts[0], StringPart::Expression(e) if matches!(e.kind, ExprKind::Literal(Literal::Bool(true)))));
+        assert!(matches!(&parts[2], StringPart::Expression(e) if matches!(e.kind, ExprKind::Literal(Literal::Integer(2)))));
     }
     
     #[test]
@@ -1829,4 +2153,149 @@ mod tests {
             panic!("Expected function definition");
         }
     }
+
+    #[test]
+    fn test_enum_definition() {
+        let code = "enum shape\n    circle(f64)\n    rect(f64, f64)\n    point";
+        let stmt = parse_statement(code).unwrap();
+        if let StmtKind::Enum { name, variants } = stmt.kind {
+            assert_eq!(name, "shape");
+            assert_eq!(variants.len(), 3);
+            assert_eq!(variants[0].payload, vec![Type::F64]);
+            assert_eq!(variants[1].payload.len(), 2);
+            assert!(variants[2].payload.is_empty());
+        } else {
+            panic!("Expected enum definition");
+        }
+    }
+
+    #[test]
+    fn test_result_return_type_and_err() {
+        let code = "fn parse_num(s: string) -> i64 ! string\n    err 'bad number'";
+        let stmt = parse_statement(code).unwrap();
+        if let StmtKind::Function { return_type, body, .. } = stmt.kind {
+            assert!(matches!(return_type, Some(Type::Result(_, _))));
+            assert!(matches!(&body[0].kind, StmtKind::Expression(e) if matches!(e.kind, ExprKind::ErrValue(_))));
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+
+    #[test]
+    fn test_trait_definition_and_conformance() {
+        let stmt = parse_statement("trait shape\n    area() -> f64\n    scale(factor: f64)").unwrap();
+        if let StmtKind::Trait { name, methods } = stmt.kind {
+            assert_eq!(name, "shape");
+            assert_eq!(methods.len(), 2);
+            assert!(matches!(methods[0].return_type, Some(Type::F64)));
+            assert_eq!(methods[1].params.len(), 1);
+        } else {
+            panic!("Expected trait definition");
+        }
+
+        let stmt = parse_statement("object circle is shape, printable\n    r: f64").unwrap();
+        if let StmtKind::Object { traits, .. } = stmt.kind {
+            assert_eq!(traits, vec!["shape".to_string(), "printable".to_string()]);
+        } else {
+            panic!("Expected object definition");
+        }
+    }
+
+    #[test]
+    fn test_generic_type_parameters() {
+        let stmt = parse_statement("object pair of K, V\n    key: K\n    value: V").unwrap();
+        if let StmtKind::Object { type_params, fields, .. } = stmt.kind {
+            assert_eq!(type_params, vec!["K".to_string(), "V".to_string()]);
+            assert_eq!(fields[0].type_, Type::Param("K".to_string()));
+        } else {
+            panic!("Expected object definition");
+        }
+
+        let stmt = parse_statement("fn first of T(p: pair of (T, string)) -> T\n    return p.key").unwrap();
+        if let StmtKind::Function { params, return_type, .. } = stmt.kind {
+            assert_eq!(params[0].type_, Type::Generic {
+                name: "pair".to_string(),
+                args: vec![Type::Param("T".to_string()), Type::String],
+            });
+            assert_eq!(return_type, Some(Type::Param("T".to_string())));
+        } else {
+            panic!("Expected function definition");
+        }
+
+        // Only declared names are parameters; anything else names a type
+        let stmt = parse_statement("fn norm(p: Point) -> f64\n    return p.x").unwrap();
+        if let StmtKind::Function { params, .. } = stmt.kind {
+            assert!(matches!(&params[0].type_, Type::Object { name, .. } if name == "Point"));
+        } else {
+            panic!("Expected function definition");
+        }
+    }
+
+    #[test]
+    fn test_pure_function() {
+        let stmt = parse_statement("pure fn add(a: i64, b: i64) -> i64\n    return a + b").unwrap();
+        assert!(matches!(stmt.kind, StmtKind::Function { is_pure: true, .. }));
+
+        let stmt = parse_statement("fn add(a: i64, b: i64) -> i64\n    return a + b").unwrap();
+        assert!(matches!(stmt.kind, StmtKind::Function { is_pure: false, .. }));
+    }
+
+    #[test]
+    fn test_cast_expression() {
+        let expr = parse_expression("x as i32 + 1").unwrap();
+        if let ExprKind::Binary { op: BinaryOp::Add, left, .. } = expr.kind {
+            assert!(matches!(left.kind, ExprKind::Cast { target: Type::I32, .. }));
+        } else {
+            panic!("Expected binary expression");
+        }
+    }
+
+    #[test]
+    fn test_make_arguments_and_bodies() {
+        let expr = parse_expression("datapoint.make 100, no").unwrap();
+        if let ExprKind::Call { callee, args } = expr.kind {
+            assert!(matches!(callee.kind, ExprKind::FieldAccess { ref field, .. } if field == "make"));
+            assert_eq!(args.len(), 2);
+            assert!(matches!(args[1].value.kind, ExprKind::Literal(Literal::Bool(false))));
+        } else {
+            panic!("Expected constructor call");
+        }
+        let expr = parse_expression("datapoint.make").unwrap();
+        assert!(matches!(expr.kind, ExprKind::Call { ref args, .. } if args.is_empty()));
+
+        let stmt = parse_statement("object user
+    name
+    seen ? no
+    make
+        seen is yes
+").unwrap();
+        if let StmtKind::Object { fields, methods, .. } = stmt.kind {
+            assert_eq!(fields.len(), 2);
+            assert_eq!(methods[0].name, "make");
+            assert!(methods[0].params.is_empty());
+        } else {
+            panic!("Expected object definition");
+        }
+    }
+
+    #[test]
+    fn test_conversions_and_command_calls() {
+        // The blank line stays inside the object body
+        let stmt = parse_statement("object point\n    x\n    y\n\n    as string\n        '({x}, {y})'\n").unwrap();
+        if let StmtKind::Object { fields, methods, .. } = stmt.kind {
+            assert_eq!(fields.len(), 2);
+            assert_eq!(methods[0].name, "as_string");
+            assert_eq!(methods[0].return_type, Some(Type::String));
+        } else {
+            panic!("Expected object definition");
+        }
+
+        let stmt = parse_statement("log p\n").unwrap();
+        if let StmtKind::Expression(Expr { kind: ExprKind::Call { callee, args }, .. }) = stmt.kind {
+            assert!(matches!(callee.kind, ExprKind::Identifier(ref name) if name == "log"));
+            assert!(matches!(args[0].value.kind, ExprKind::Identifier(ref name) if name == "p"));
+        } else {
+            panic!("Expected command call");
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::resolver::types::InferType;
+use crate::resolver::types::{InferType, TypeVar};
 use std::collections::HashMap;
 
 /// Type environment for scoped type checking
@@ -31,4 +31,16 @@ impl TypeEnv {
         self.bindings.get(name).cloned()
             .or_else(|| self.parent.as_ref()?.lookup(name))
     }
+    
+    /// Type variables free in any binding; these must not be generalized
+    pub fn free_vars(&self) -> Vec<TypeVar> {
+        let mut vars = match &self.parent {
+            Some(parent) => parent.free_vars(),
+            None => Vec::new(),
+        };
+        for ty in self.bindings.values() {
+            ty.free_vars(&mut vars);
+        }
+        vars
+    }
 }
@@ -1,4 +1,5 @@
-use crate::resolver::types::{Constraint, InferType, TypeVar};
+use crate::ast::SourceSpan;
+use crate::resolver::types::{Constraint, EffectSet, InferType, TypeVar};
 
 /// Error type for type inference failures
 #[derive(Debug, Clone)]
@@ -8,6 +9,14 @@ pub enum TypeError {
     ArityMismatch(usize, usize),
     FieldNotFound(String),
     MethodNotFound(String),
+    VariantNotFound(String, String),
+    UnknownTrait(String),
+    TraitNotImplemented(String, String),
+    UnknownTypeParam(String),
+    TypeParamConstrained(String, InferType),
+    ImpureFunction(String, EffectSet),
+    ImplicitNarrowing(InferType, InferType),
+    InvalidCast(InferType, InferType),
     NotAnObject(InferType),
     NotCallable(InferType),
     NotIterable(InferType),
@@ -33,6 +42,30 @@ impl std::fmt::Display for TypeError {
             TypeError::MethodNotFound(method) => {
                 write!(f, "Method '{}' not found", method)
             }
+            TypeError::VariantNotFound(enum_name, variant) => {
+                write!(f, "Enum '{}' has no variant '{}'", enum_name, variant)
+            }
+            TypeError::UnknownTrait(name) => {
+                write!(f, "Unknown trait: {}", name)
+            }
+            TypeError::TraitNotImplemented(object, trait_name) => {
+                write!(f, "Object '{}' does not implement trait '{}'", object, trait_name)
+            }
+            TypeError::UnknownTypeParam(name) => {
+                write!(f, "Unknown type parameter: {}", name)
+            }
+            TypeError::TypeParamConstrained(name, ty) => {
+                write!(f, "Type parameter {} is used as {:?}, so it is not generic", name, ty)
+            }
+            TypeError::ImpureFunction(name, effects) => {
+                write!(f, "Function '{}' is declared pure but performs {} effects", name, effects)
+            }
+            TypeError::ImplicitNarrowing(from, to) => {
+                write!(f, "Cannot implicitly convert {:?} to {:?}; use an explicit `as` conversion", from, to)
+            }
+            TypeError::InvalidCast(from, to) => {
+                write!(f, "Cannot convert {:?} to {:?} with `as`", from, to)
+            }
             TypeError::NotAnObject(ty) => {
                 write!(f, "Type {:?} is not an object", ty)
             }
@@ -51,3 +84,16 @@ impl std::fmt::Display for TypeError {
         }
     }
 }
+
+/// A type error with the statement or expression it was found in, when known
+#[derive(Debug, Clone)]
+pub struct LocatedTypeError {
+    pub error: TypeError,
+    pub span: Option<SourceSpan>,
+}
+
+impl std::fmt::Display for LocatedTypeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
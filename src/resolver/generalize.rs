@@ -0,0 +1,358 @@
+//! Let-polymorphism for top-level functions.
+//!
+//! Functions are grouped into strongly connected components of the call graph
+//! and inferred callees-first. Each group is solved on its own, then every
+//! member is generalized over the type variables that are not free in the
+//! environment. Uses instantiate the resulting scheme with fresh variables.
+//!
+//! Members of a group call each other, so they share one effect set: the
+//! union of everything their bodies do, including calls to earlier groups.
+
+use crate::ast::*;
+use crate::resolver::{
+    error::TypeError,
+    types::{Constraint, EffectSet, InferType, Substitution, TypeVar},
+    TypeResolver,
+};
+use std::collections::{HashMap, HashSet};
+
+impl TypeResolver {
+    /// Infer every top-level function, one mutually recursive group at a time
+    pub(super) fn infer_function_groups(&mut self, program: &Program) -> Result<(), TypeError> {
+        let functions: Vec<&Stmt> = program.statements.iter()
+            .filter(|stmt| matches!(stmt.kind, StmtKind::Function { .. }))
+            .collect();
+        for group in function_groups(&functions) {
+            let members: Vec<&Stmt> = group.into_iter().map(|index| functions[index]).collect();
+            self.infer_function_group(&members)?;
+        }
+        Ok(())
+    }
+
+    /// Infer one group against shared monomorphic signatures, then generalize each member
+    fn infer_function_group(&mut self, members: &[&Stmt]) -> Result<(), TypeError> {
+        let env_vars = self.env.free_vars();
+
+        // Members see each other monomorphically while the group is being inferred
+        let mut signatures = Vec::new();
+        for stmt in members {
+            if let StmtKind::Function { name, params, return_type, .. } = &stmt.kind {
+                self.current_span = Some(stmt.span.clone());
+                let bound = self.bind_type_params(&signature_type_params(params, return_type.as_ref()));
+                let signature = self.function_signature(params, return_type.as_ref());
+                self.type_params.clear();
+                let signature = signature?;
+                self.env.bind(name.clone(), signature.clone());
+                signatures.push((bound, signature));
+            }
+        }
+
+        let outer_constraints = std::mem::take(&mut self.constraints);
+        let mut inferred = Ok(());
+        let mut group_effects = EffectSet::pure();
+        for (stmt, (bound, signature)) in members.iter().zip(&signatures) {
+            if let StmtKind::Function { name, params, return_type, body, .. } = &stmt.kind {
+                self.type_params = bound.iter().cloned().collect();
+                self.current_span = Some(stmt.span.clone());
+                match self.infer_function(name, params, return_type.as_ref(), body) {
+                    Ok(func_type) => {
+                        if let InferType::Function { effects, .. } = &func_type {
+                            group_effects = group_effects.union(effects);
+                        }
+                        self.constrain(Constraint::Equal(signature.clone(), func_type));
+                    }
+                    Err(e) => {
+                        inferred = Err(e);
+                        break;
+                    }
+                }
+            }
+        }
+        self.type_params.clear();
+
+        // Solve the group in isolation, but keep its constraints for the global pass
+        let group_constraints = self.constraints.clone();
+        let local = inferred.and_then(|_| self.solve_constraints());
+        self.constraints = outer_constraints;
+        self.constraints.extend(group_constraints);
+        let local = local?;
+
+        // Quantified variables keep their Numeric/Addable requirements
+        for class in std::mem::take(&mut self.pending_classes) {
+            if let Constraint::Numeric(InferType::Var(v)) | Constraint::Addable(InferType::Var(v)) = &class {
+                self.class_bounds.entry(*v).or_default().push(class);
+            }
+        }
+
+        let mut fixed = Vec::new();
+        for var in env_vars {
+            self.apply_substitution(&InferType::Var(var), &local).free_vars(&mut fixed);
+        }
+
+        for (stmt, (bound, signature)) in members.iter().zip(&signatures) {
+            if let StmtKind::Function { name, is_pure, .. } = &stmt.kind {
+                self.current_span = Some(stmt.span.clone());
+                self.check_type_params_abstract(bound, &local)?;
+                self.check_purity(name, *is_pure, &group_effects)?;
+                let solved = with_effects(self.apply_substitution(signature, &local), &group_effects);
+                let scheme = generalize(solved, &fixed);
+                self.env.bind(name.clone(), scheme);
+            }
+        }
+        self.current_span = None;
+        Ok(())
+    }
+
+    /// Signature from annotations, with fresh variables where a type is left out
+    fn function_signature(&mut self, params: &[Parameter], return_type: Option<&Type>) -> Result<InferType, TypeError> {
+        let mut param_types = Vec::new();
+        for param in params {
+            param_types.push(self.ast_type_to_infer_type(&param.type_)?);
+        }
+        let return_type = match return_type {
+            Some(ty) => self.ast_type_to_infer_type(ty)?,
+            None => InferType::Var(self.var_gen.fresh()),
+        };
+        Ok(InferType::Function {
+            params: param_types,
+            return_type: Box::new(return_type),
+            effects: EffectSet::pure(), // Effects will be inferred later
+        })
+    }
+
+    /// User-written type parameters must stay distinct and unconstrained
+    fn check_type_params_abstract(&self, bound: &[(String, TypeVar)], subst: &Substitution) -> Result<(), TypeError> {
+        let mut seen = Vec::new();
+        for (param_name, var) in bound {
+            match self.apply_substitution(&InferType::Var(*var), subst) {
+                InferType::Var(v) if !seen.contains(&v) => seen.push(v),
+                other => return Err(TypeError::TypeParamConstrained(param_name.clone(), other)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replace the effects recorded on a function type
+fn with_effects(ty: InferType, effects: &EffectSet) -> InferType {
+    match ty {
+        InferType::Function { params, return_type, .. } => InferType::Function {
+            params,
+            return_type,
+            effects: effects.clone(),
+        },
+        other => other,
+    }
+}
+
+/// Quantify over every free variable of `ty` not pinned by the environment
+fn generalize(ty: InferType, fixed: &[TypeVar]) -> InferType {
+    let mut vars = Vec::new();
+    ty.free_vars(&mut vars);
+    vars.retain(|v| !fixed.contains(v));
+    if vars.is_empty() {
+        ty
+    } else {
+        InferType::Forall(vars, Box::new(ty))
+    }
+}
+
+/// Strongly connected components of the call graph, callees before callers.
+/// Members of each component stay in source order.
+fn function_groups(functions: &[&Stmt]) -> Vec<Vec<usize>> {
+    let indices: HashMap<&str, usize> = functions.iter()
+        .enumerate()
+        .filter_map(|(index, stmt)| match &stmt.kind {
+            StmtKind::Function { name, .. } => Some((name.as_str(), index)),
+            _ => None,
+        })
+        .collect();
+
+    let edges: Vec<Vec<usize>> = functions.iter()
+        .map(|stmt| {
+            let mut names = HashSet::new();
+            if let StmtKind::Function { params, body, .. } = &stmt.kind {
+                collect_block_references(body, &mut names);
+                // Parameters shadow functions of the same name
+                for param in params {
+                    names.remove(&param.name);
+                }
+            }
+            let mut callees: Vec<usize> = names.iter().filter_map(|name| indices.get(name.as_str()).copied()).collect();
+            callees.sort_unstable();
+            callees
+        })
+        .collect();
+
+    let mut tarjan = Tarjan {
+        edges: &edges,
+        index: vec![None; functions.len()],
+        lowlink: vec![0; functions.len()],
+        on_stack: vec![false; functions.len()],
+        stack: Vec::new(),
+        next_index: 0,
+        groups: Vec::new(),
+    };
+    for node in 0..functions.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    tarjan.groups
+}
+
+/// Tarjan's algorithm; components come out in reverse topological order
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    lowlink: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    groups: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.lowlink[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in &self.edges[node] {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.lowlink[node] = self.lowlink[node].min(self.lowlink[next]);
+                }
+                Some(next_index) if self.on_stack[next] => {
+                    self.lowlink[node] = self.lowlink[node].min(next_index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.lowlink[node]) == self.index[node] {
+            let mut group = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                group.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            group.sort_unstable();
+            self.groups.push(group);
+        }
+    }
+}
+
+/// Names referenced anywhere in a block
+fn collect_block_references(stmts: &[Stmt], out: &mut HashSet<String>) {
+    for stmt in stmts {
+        collect_stmt_references(stmt, out);
+    }
+}
+
+fn collect_stmt_references(stmt: &Stmt, out: &mut HashSet<String>) {
+    match &stmt.kind {
+        StmtKind::Expression(expr) => collect_expr_references(expr, out),
+        StmtKind::Assignment { target, value } => {
+            collect_expr_references(target, out);
+            collect_expr_references(value, out);
+        }
+        StmtKind::If { condition, then_branch, else_branch } => {
+            collect_expr_references(condition, out);
+            collect_block_references(then_branch, out);
+            if let Some(else_branch) = else_branch {
+                collect_block_references(else_branch, out);
+            }
+        }
+        StmtKind::Unless { condition, body }
+        | StmtKind::While { condition, body }
+        | StmtKind::Until { condition, body } => {
+            collect_expr_references(condition, out);
+            collect_block_references(body, out);
+        }
+        StmtKind::Iterate { iterable, body } => {
+            collect_expr_references(iterable, out);
+            collect_block_references(body, out);
+        }
+        StmtKind::Return(Some(expr)) => collect_expr_references(expr, out),
+        StmtKind::Function { body, .. } => collect_block_references(body, out),
+        _ => {}
+    }
+}
+
+/// Names referenced anywhere in an expression, including nested lambdas
+pub(crate) fn collect_expr_references(expr: &Expr, out: &mut HashSet<String>) {
+    match &expr.kind {
+        ExprKind::Identifier(name) => {
+            out.insert(name.clone());
+        }
+        ExprKind::Binary { left, right, .. } => {
+            collect_expr_references(left, out);
+            collect_expr_references(right, out);
+        }
+        ExprKind::Unary { operand, .. } => collect_expr_references(operand, out),
+        ExprKind::Call { callee, args } => {
+            collect_expr_references(callee, out);
+            for arg in args {
+                collect_expr_references(&arg.value, out);
+            }
+        }
+        ExprKind::Index { object, index } => {
+            collect_expr_references(object, out);
+            collect_expr_references(index, out);
+        }
+        ExprKind::FieldAccess { object, .. } => collect_expr_references(object, out),
+        ExprKind::ListLiteral(elements) => {
+            for element in elements {
+                collect_expr_references(element, out);
+            }
+        }
+        ExprKind::MapLiteral(pairs) => {
+            for (key, value) in pairs {
+                collect_expr_references(key, out);
+                collect_expr_references(value, out);
+            }
+        }
+        ExprKind::ListAppend { list, element } => {
+            collect_expr_references(list, out);
+            collect_expr_references(element, out);
+        }
+        ExprKind::MapInsert { map, key, value } => {
+            collect_expr_references(map, out);
+            collect_expr_references(key, out);
+            collect_expr_references(value, out);
+        }
+        ExprKind::Across { callee, iterable, .. } => {
+            collect_expr_references(callee, out);
+            collect_expr_references(iterable, out);
+        }
+        ExprKind::StringInterpolation { parts } => {
+            for part in parts {
+                if let StringPart::Expression(expr) = part {
+                    collect_expr_references(expr, out);
+                }
+            }
+        }
+        ExprKind::If { condition, then_branch, else_branch } => {
+            collect_expr_references(condition, out);
+            collect_expr_references(then_branch, out);
+            if let Some(else_branch) = else_branch {
+                collect_expr_references(else_branch, out);
+            }
+        }
+        ExprKind::Block(stmts) => collect_block_references(stmts, out),
+        ExprKind::Lambda { body, .. } => collect_expr_references(body, out),
+        ExprKind::ObjectInstantiation { fields, .. } => {
+            for (_, value) in fields {
+                collect_expr_references(value, out);
+            }
+        }
+        ExprKind::ErrValue(payload) | ExprKind::Cast { expr: payload, .. } => collect_expr_references(payload, out),
+        ExprKind::Literal(_) => {}
+    }
+}
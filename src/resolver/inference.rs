@@ -1,7 +1,7 @@
 use crate::ast::*;
 use crate::resolver::{
     error::TypeError,
-    types::{Constraint, EffectSet, InferType},
+    types::{Constraint, EffectSet, InferType, Substitution, TypeVar},
     TypeResolver,
 };
 use std::collections::HashMap;
@@ -16,12 +16,13 @@ impl TypeResolver {
         self.builtin_types.insert("bool".to_string(), InferType::Bool);
         
         // Built-in functions with polymorphic types
+        let logged = self.var_gen.fresh();
         let log_type = InferType::Function {
-            params: vec![InferType::Var(self.var_gen.fresh())],
+            params: vec![InferType::Var(logged)],
             return_type: Box::new(InferType::Unit),
             effects: EffectSet::io(),
         };
-        self.env.bind("log".to_string(), log_type);
+        self.env.bind("log".to_string(), InferType::Forall(vec![logged], Box::new(log_type)));
         
         // Hash functions
         let hash_type = InferType::Function {
@@ -46,44 +47,72 @@ impl TypeResolver {
         self.env.bind("print".to_string(), print_type);
     }
     
-    /// Collect function signatures for forward references
-    pub(super) fn collect_function_signatures(&mut self, program: &Program) -> Result<(), TypeError> {
-        for stmt in &program.statements {
-            if let StmtKind::Function { name, params, return_type, .. } = &stmt.kind {
-                let mut param_types = Vec::new();
-                for param in params {
-                    let param_type = match &param.type_ {
-                        Type::Unknown => InferType::Var(self.var_gen.fresh()),
-                        _ => self.ast_type_to_infer_type(&param.type_)?,
-                    };
-                    param_types.push(param_type);
+    /// Bind fresh type variables for the named type parameters in the current scope
+    pub(super) fn bind_type_params(&mut self, names: &[String]) -> Vec<(String, TypeVar)> {
+        names.iter()
+            .map(|name| {
+                let var = self.var_gen.fresh();
+                self.type_params.insert(name.clone(), var);
+                (name.clone(), var)
+            })
+            .collect()
+    }
+    
+    /// Replace the quantified variables of a polymorphic type with fresh ones
+    pub(super) fn instantiate(&mut self, ty: &InferType) -> InferType {
+        match ty {
+            InferType::Forall(vars, body) => {
+                let fresh: Substitution = vars.iter()
+                    .map(|v| (*v, InferType::Var(self.var_gen.fresh())))
+                    .collect();
+                for var in vars {
+                    for class in self.class_bounds.get(var).cloned().unwrap_or_default() {
+                        let class = self.apply_subst_to_constraint(&class, &fresh);
+                        self.constrain(class);
+                    }
                 }
-                
-                let return_infer_type = if let Some(ty) = return_type {
-                    self.ast_type_to_infer_type(ty)?
-                } else {
-                    InferType::Var(self.var_gen.fresh())
-                };
-                
-                let func_type = InferType::Function {
-                    params: param_types,
-                    return_type: Box::new(return_infer_type),
-                    effects: EffectSet::pure(), // Effects will be inferred later
-                };
-                self.env.bind(name.clone(), func_type);
+                self.apply_substitution(body, &fresh)
             }
+            _ => ty.clone(),
         }
-        Ok(())
     }
     
     /// Collect object, store, and actor definitions for forward references
     pub(super) fn collect_type_definitions(&mut self, program: &Program) -> Result<(), TypeError> {
+        // Enums first so object fields and other enums can name them
+        for stmt in &program.statements {
+            if let StmtKind::Enum { name, .. } = &stmt.kind {
+                self.enum_definitions.insert(name.clone(), InferType::Enum {
+                    name: name.clone(),
+                    variants: Vec::new(),
+                });
+            }
+        }
+        for stmt in &program.statements {
+            if let StmtKind::Enum { name, variants } = &stmt.kind {
+                self.current_span = Some(stmt.span.clone());
+                let enum_type = self.create_enum_type(name, variants)?;
+                self.enum_definitions.insert(name.clone(), enum_type.clone());
+                self.env.bind(name.clone(), enum_type);
+            }
+        }
+        
+        // Traits before objects so conformance can be checked
+        for stmt in &program.statements {
+            if let StmtKind::Trait { name, methods } = &stmt.kind {
+                self.current_span = Some(stmt.span.clone());
+                let trait_type = self.create_trait_type(name, methods)?;
+                self.trait_definitions.insert(name.clone(), trait_type);
+            }
+        }
+        
         for stmt in &program.statements {
+            self.current_span = Some(stmt.span.clone());
             match &stmt.kind {
-                StmtKind::Object { name, fields, methods } => {
-                    let obj_type = self.create_object_type(name, fields, methods, false, false)?;
-                    self.object_definitions.insert(name.clone(), obj_type.clone());
-                    self.env.bind(name.clone(), obj_type);
+                StmtKind::Object { name, type_params, traits, fields, methods } => {
+                    let obj_type = self.define_object(name, type_params, fields, methods)?;
+                    let instance = self.instantiate(&obj_type);
+                    self.check_trait_conformance(name, traits, &instance)?;
                 }
                 
                 StmtKind::Store { name, fields, methods } => {
@@ -101,9 +130,121 @@ impl TypeResolver {
                 _ => {}
             }
         }
+        self.current_span = None;
+        Ok(())
+    }
+    
+    /// Create enum type from AST definition
+    pub(super) fn create_enum_type(
+        &mut self,
+        name: &str,
+        variants: &[EnumVariant],
+    ) -> Result<InferType, TypeError> {
+        let mut variant_types = Vec::new();
+        for variant in variants {
+            let payload: Result<Vec<_>, _> = variant.payload.iter()
+                .map(|ty| self.ast_type_to_infer_type(ty))
+                .collect();
+            variant_types.push((variant.name.clone(), payload?));
+        }
+        
+        Ok(InferType::Enum {
+            name: name.to_string(),
+            variants: variant_types,
+        })
+    }
+    
+    /// Create trait type from AST definition
+    pub(super) fn create_trait_type(
+        &mut self,
+        name: &str,
+        methods: &[TraitMethod],
+    ) -> Result<InferType, TypeError> {
+        let mut method_types = Vec::new();
+        for method in methods {
+            let mut param_types = Vec::new();
+            for param in &method.params {
+                let param_type = match &param.type_ {
+                    Type::Unknown => InferType::Var(self.var_gen.fresh()),
+                    _ => self.ast_type_to_infer_type(&param.type_)?,
+                };
+                param_types.push(param_type);
+            }
+            let return_type = match &method.return_type {
+                Some(ty) => self.ast_type_to_infer_type(ty)?,
+                None => InferType::Unit,
+            };
+            method_types.push((method.name.clone(), InferType::Function {
+                params: param_types,
+                return_type: Box::new(return_type),
+                effects: EffectSet::pure(),
+            }));
+        }
+        
+        Ok(InferType::Trait {
+            name: name.to_string(),
+            methods: method_types,
+        })
+    }
+    
+    /// Record `object name is traits..` and require every trait method via HasMethod
+    fn check_trait_conformance(
+        &mut self,
+        name: &str,
+        traits: &[String],
+        obj_type: &InferType,
+    ) -> Result<(), TypeError> {
+        for trait_name in traits {
+            let trait_type = self.trait_definitions.get(trait_name)
+                .cloned()
+                .ok_or_else(|| TypeError::UnknownTrait(trait_name.clone()))?;
+            
+            if let InferType::Trait { methods, .. } = &trait_type {
+                for (method_name, _) in methods {
+                    // The object's own method types hold its receiver as a fresh variable
+                    let receiver = InferType::Var(self.var_gen.fresh());
+                    if let Some(expected) = trait_type.trait_method(method_name, &receiver) {
+                        self.constrain(Constraint::HasMethod(obj_type.clone(), method_name.clone(), expected));
+                    }
+                }
+            }
+            
+            self.trait_impls.entry(name.to_string()).or_default().push(trait_name.clone());
+        }
         Ok(())
     }
     
+    /// Look up the enum named by an expression, if it is a bare enum identifier
+    fn enum_for_expr(&self, expr: &Expr) -> Option<InferType> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.enum_definitions.get(name).cloned(),
+            _ => None,
+        }
+    }
+    
+    /// Create and register an object type, generalized over its `of T` parameters
+    fn define_object(
+        &mut self,
+        name: &str,
+        type_params: &[String],
+        fields: &[Field],
+        methods: &[ObjectMethod],
+    ) -> Result<InferType, TypeError> {
+        let bound = self.bind_type_params(type_params);
+        let result = self.create_object_type(name, fields, methods, false, false);
+        self.type_params.clear();
+        
+        let obj_type = result?;
+        let defined = if bound.is_empty() {
+            obj_type
+        } else {
+            InferType::Forall(bound.iter().map(|(_, v)| *v).collect(), Box::new(obj_type))
+        };
+        self.object_definitions.insert(name.to_string(), defined.clone());
+        self.env.bind(name.to_string(), defined.clone());
+        Ok(defined)
+    }
+    
     /// Create object type from AST definition
     pub(super) fn create_object_type(
         &mut self,
@@ -116,26 +257,46 @@ impl TypeResolver {
         let mut field_types = HashMap::new();
         let mut method_types = HashMap::new();
         
-        // Infer field types
+        // Infer field types; a default must fit the annotation when there is one
         for field in fields {
-            let field_type = match &field.default_value {
-                Some(expr) => self.infer_expression(expr)?,
-                None => {
-                    // Convert AST Type to InferType
-                    self.ast_type_to_infer_type(&field.type_)?
+            let field_type = match (&field.default_value, &field.type_) {
+                (Some(expr), Type::Unknown) => self.infer_expression(expr)?,
+                (Some(expr), annotated) => {
+                    let default_type = self.infer_expression(expr)?;
+                    let field_type = self.ast_type_to_infer_type(annotated)?;
+                    self.constrain(Constraint::Widens(default_type, field_type.clone()));
+                    field_type
                 }
+                (None, annotated) => self.ast_type_to_infer_type(annotated)?,
             };
             field_types.insert(field.name.clone(), field_type);
         }
+        self.object_fields.insert(
+            name.to_string(),
+            fields.iter().map(|field| (field.name.clone(), field.default_value.is_some())).collect(),
+        );
+        let make_params: Vec<InferType> = fields.iter().map(|field| field_types[&field.name].clone()).collect();
         
-        // Infer method types
+        // Methods see the object as `self`; its methods are found by name
+        // once the definition is complete
+        let self_type = InferType::Object {
+            name: name.to_string(),
+            fields: field_types.clone(),
+            methods: HashMap::new(),
+            is_actor,
+            is_store,
+        };
         for method in methods {
-            let method_type = self.infer_method_type(method)?;
+            // A user `make` finishes construction once the fields are filled
+            if method.name == "make" && !method.params.is_empty() {
+                return Err(TypeError::ArityMismatch(0, method.params.len()));
+            }
+            let method_type = self.infer_method_type(method, &self_type, &field_types)?;
             method_types.insert(method.name.clone(), method_type);
         }
         
         // Add built-in methods for all objects
-        self.add_builtin_object_methods(&mut method_types, &field_types)?;
+        self.add_builtin_object_methods(&mut method_types, &make_params)?;
         
         // Add store-specific methods
         if is_store {
@@ -156,7 +317,6 @@ impl TypeResolver {
             is_store,
         };
         if let InferType::Object { methods, .. } = &mut object_type_for_make {
-            let make_params: Vec<InferType> = field_types.values().cloned().collect();
             let make_return = InferType::Object {
                 name: name.to_string(),
                 fields: field_types.clone(),
@@ -178,10 +338,10 @@ impl TypeResolver {
     fn add_builtin_object_methods(
         &mut self,
         method_types: &mut HashMap<String, InferType>,
-        field_types: &HashMap<String, InferType>,
+        make_params: &[InferType],
     ) -> Result<(), TypeError> {
         // make method - constructor
-        let make_params: Vec<InferType> = field_types.values().cloned().collect();
+        let make_params = make_params.to_vec();
         let make_return = InferType::Var(self.var_gen.fresh()); // Self type
         
         method_types.insert("make".to_string(), InferType::Function {
@@ -238,43 +398,48 @@ impl TypeResolver {
         Ok(())
     }
     
-    /// Infer method type from method definition
-    fn infer_method_type(&mut self, method: &ObjectMethod) -> Result<InferType, TypeError> {
-        let mut param_types = Vec::new();
-        
-        // Add implicit self parameter
-        param_types.push(InferType::Var(self.var_gen.fresh()));
-        
-        // Add explicit parameters
+    /// Infer method type from method definition; the receiver is its first parameter
+    fn infer_method_type(
+        &mut self,
+        method: &ObjectMethod,
+        self_type: &InferType,
+        field_types: &HashMap<String, InferType>,
+    ) -> Result<InferType, TypeError> {
+        // Fields are in scope by bare name, unless a parameter shadows them
+        let mut method_env = self.env.extend();
+        let mut method_fields = field_types.clone();
         for param in &method.params {
-            let param_type = match &param.type_ {
-                Type::Unknown => InferType::Var(self.var_gen.fresh()),
-                _ => self.ast_type_to_infer_type(&param.type_)?,
-            };
-            param_types.push(param_type);
+            method_fields.remove(&param.name);
+        }
+        for (field, field_type) in &method_fields {
+            method_env.bind(field.clone(), field_type.clone());
+        }
+        method_env.bind("self".to_string(), self_type.clone());
+
+        let outer_env = std::mem::replace(&mut self.env, method_env);
+        let outer_fields = std::mem::replace(&mut self.method_fields, method_fields);
+        let method_type = self.infer_function(&method.name, &method.params, method.return_type.as_ref(), &method.body);
+        self.method_fields = outer_fields;
+        self.env = outer_env;
+
+        match method_type? {
+            InferType::Function { mut params, return_type, effects } => {
+                params.insert(0, self_type.clone());
+                Ok(InferType::Function { params, return_type, effects })
+            }
+            other => Ok(other),
         }
-        
-        // Infer return type from body
-        let return_type = if let Some(ret_type) = &method.return_type {
-            self.ast_type_to_infer_type(ret_type)?
-        } else if method.body.is_empty() {
-            InferType::Unit
-        } else {
-            self.infer_block(&method.body)?
-        };
-        
-        Ok(InferType::Function {
-            params: param_types,
-            return_type: Box::new(return_type),
-            effects: EffectSet::pure(), // Will be inferred from body
-        })
     }
     
     /// Convert AST type to inference type
     pub(super) fn ast_type_to_infer_type(&mut self, ast_type: &Type) -> Result<InferType, TypeError> {
         match ast_type {
-            Type::I8 | Type::I16 | Type::I32 | Type::I64 => Ok(InferType::Int),
-            Type::F32 | Type::F64 => Ok(InferType::Float),
+            Type::I8 => Ok(InferType::I8),
+            Type::I16 => Ok(InferType::I16),
+            Type::I32 => Ok(InferType::I32),
+            Type::I64 => Ok(InferType::Int),
+            Type::F32 => Ok(InferType::F32),
+            Type::F64 => Ok(InferType::Float),
             Type::String => Ok(InferType::String),
             Type::Bool => Ok(InferType::Bool),
             Type::Unit => Ok(InferType::Unit),
@@ -299,10 +464,15 @@ impl TypeResolver {
                     effects: EffectSet::pure(),
                 })
             }
-            Type::Object { name, .. } | Type::Store { name, .. } | Type::Actor { name, .. } => {
+            Type::Object { name, .. } | Type::Store { name, .. } | Type::Actor { name, .. } |
+            Type::Enum { name, .. } | Type::Trait { name } => {
                 // Look up in our type definitions
-                if let Some(obj_type) = self.object_definitions.get(name) {
-                    Ok(obj_type.clone())
+                if let Some(enum_type) = self.enum_definitions.get(name) {
+                    Ok(enum_type.clone())
+                } else if let Some(trait_type) = self.trait_definitions.get(name) {
+                    Ok(trait_type.clone())
+                } else if let Some(obj_type) = self.object_definitions.get(name).cloned() {
+                    Ok(self.instantiate(&obj_type))
                 } else if let Some(store_type) = self.store_types.get(name) {
                     Ok(store_type.clone())
                 } else if let Some(actor_type) = self.actor_types.get(name) {
@@ -312,6 +482,26 @@ impl TypeResolver {
                     Ok(InferType::Var(self.var_gen.fresh()))
                 }
             }
+            Type::Param(name) => self.type_params.get(name)
+                .map(|v| InferType::Var(*v))
+                .ok_or_else(|| TypeError::UnknownTypeParam(name.clone())),
+            Type::Generic { name, args } => {
+                let arg_types: Result<Vec<_>, _> = args.iter()
+                    .map(|a| self.ast_type_to_infer_type(a))
+                    .collect();
+                let arg_types = arg_types?;
+                match self.object_definitions.get(name).cloned() {
+                    Some(InferType::Forall(vars, body)) => {
+                        if vars.len() != arg_types.len() {
+                            return Err(TypeError::ArityMismatch(vars.len(), arg_types.len()));
+                        }
+                        let applied: Substitution = vars.into_iter().zip(arg_types).collect();
+                        Ok(self.apply_substitution(&body, &applied))
+                    }
+                    Some(_) => Err(TypeError::ArityMismatch(0, arg_types.len())),
+                    None => Ok(InferType::Var(self.var_gen.fresh())),
+                }
+            }
             Type::TypeVar(id) => Ok(InferType::Var(crate::resolver::types::TypeVar(*id as usize))),
             Type::Result(ok, err) => {
                 let ok_type = self.ast_type_to_infer_type(ok)?;
@@ -324,39 +514,62 @@ impl TypeResolver {
 
     /// Convert InferType back to AST Type for updating the AST
     pub(super) fn infer_type_to_ast_type(&self, infer_type: &InferType) -> Type {
+        self.to_ast_type(infer_type, false)
+    }
+
+    /// Like `infer_type_to_ast_type`, but open variables stay `Type::TypeVar`,
+    /// so a generalized signature still says which positions share a type
+    pub(super) fn signature_to_ast_type(&self, signature: &InferType) -> Type {
+        self.to_ast_type(signature, true)
+    }
+
+    fn to_ast_type(&self, infer_type: &InferType, keep_vars: bool) -> Type {
         match infer_type {
             InferType::Unit => Type::Unit,
             InferType::Bool => Type::Bool,
-            InferType::Int => Type::I32, // Default to I32 for integers
-            InferType::Float => Type::F64, // Default to F64 for floats
+            InferType::Int => Type::I64,
+            InferType::Float => Type::F64,
+            InferType::I8 => Type::I8,
+            InferType::I16 => Type::I16,
+            InferType::I32 => Type::I32,
+            InferType::F32 => Type::F32,
             InferType::String => Type::String,
-            InferType::List(inner) => Type::List(Box::new(self.infer_type_to_ast_type(inner))),
+            InferType::List(inner) => Type::List(Box::new(self.to_ast_type(inner, keep_vars))),
             InferType::Map(key, value) => Type::Map(
-                Box::new(self.infer_type_to_ast_type(key)),
-                Box::new(self.infer_type_to_ast_type(value)),
+                Box::new(self.to_ast_type(key, keep_vars)),
+                Box::new(self.to_ast_type(value, keep_vars)),
             ),
             InferType::Function { params, return_type, .. } => Type::Function {
-                params: params.iter().map(|p| self.infer_type_to_ast_type(p)).collect(),
-                return_type: Box::new(self.infer_type_to_ast_type(return_type)),
+                params: params.iter().map(|p| self.to_ast_type(p, keep_vars)).collect(),
+                return_type: Box::new(self.to_ast_type(return_type, keep_vars)),
             },
             InferType::Object { name, fields, .. } => Type::Object { 
                 name: name.clone(),
                 fields: fields.iter()
-                    .map(|(k, v)| (k.clone(), self.infer_type_to_ast_type(v)))
+                    .map(|(k, v)| (k.clone(), self.to_ast_type(v, keep_vars)))
                     .collect(),
             },
             InferType::Store { name, value_type, .. } => Type::Store { 
                 name: name.clone(),
-                value_type: Box::new(self.infer_type_to_ast_type(value_type)),
+                value_type: Box::new(self.to_ast_type(value_type, keep_vars)),
             },
             InferType::Actor { name, .. } => Type::Actor { 
                 name: name.clone(),
                 message_types: vec![], // Simplified for now
             },
             InferType::Result(ok, err) => Type::Result(
-                Box::new(self.infer_type_to_ast_type(ok)),
-                Box::new(self.infer_type_to_ast_type(err)),
+                Box::new(self.to_ast_type(ok, keep_vars)),
+                Box::new(self.to_ast_type(err, keep_vars)),
             ),
+            InferType::Enum { name, variants } => Type::Enum {
+                name: name.clone(),
+                variants: variants.iter()
+                    .map(|(v, payload)| (v.clone(), payload.iter().map(|p| self.to_ast_type(p, keep_vars)).collect()))
+                    .collect(),
+            },
+            InferType::Trait { name, .. } => Type::Trait { name: name.clone() },
+            InferType::Forall(_, body) => self.to_ast_type(body, keep_vars),
+            InferType::Var(v) if keep_vars => Type::TypeVar(v.0 as u32),
             InferType::Var(_) | InferType::Unknown => Type::Unknown,
             _ => Type::Unknown,
         }
@@ -365,38 +578,75 @@ impl TypeResolver {
     /// Convert InferType to a readable string for debugging and error reporting
     #[allow(dead_code)]
     pub(super) fn type_to_string(&self, ty: &InferType) -> String {
+        self.render_type(ty, &HashMap::new())
+    }
+
+    /// Render a type, printing quantified variables by the names given them
+    fn render_type(&self, ty: &InferType, names: &HashMap<TypeVar, String>) -> String {
         match ty {
             InferType::Unit => "unit".to_string(),
             InferType::Bool => "bool".to_string(),
             InferType::Int => "int".to_string(),
             InferType::Float => "float".to_string(),
+            InferType::I8 => "i8".to_string(),
+            InferType::I16 => "i16".to_string(),
+            InferType::I32 => "i32".to_string(),
+            InferType::F32 => "f32".to_string(),
             InferType::String => "string".to_string(),
-            InferType::List(inner) => format!("List[{}]", self.type_to_string(inner)),
+            InferType::List(inner) => format!("List[{}]", self.render_type(inner, names)),
             InferType::Map(key, value) => format!("Map[{}, {}]", 
-                self.type_to_string(key), self.type_to_string(value)),
-            InferType::Function { params, return_type, .. } => {
-                let param_strs: Vec<String> = params.iter().map(|p| self.type_to_string(p)).collect();
-                format!("({}) -> {}", param_strs.join(", "), self.type_to_string(return_type))
+                self.render_type(key, names), self.render_type(value, names)),
+            InferType::Function { params, return_type, effects } => {
+                let param_strs: Vec<String> = params.iter().map(|p| self.render_type(p, names)).collect();
+                let signature = format!("({}) -> {}", param_strs.join(", "), self.render_type(return_type, names));
+                if effects.is_pure() {
+                    signature
+                } else {
+                    format!("{} with {}", signature, effects)
+                }
             },
+            InferType::Forall(bound, body) => {
+                // Quantified variables are named a, b, ... in order of appearance
+                let mut vars = Vec::new();
+                body.free_vars(&mut vars);
+                let mut names = names.clone();
+                for var in vars.into_iter().filter(|var| bound.contains(var)) {
+                    let index = names.len();
+                    names.insert(var, type_var_name(index));
+                }
+                self.render_type(body, &names)
+            }
             InferType::Object { name, .. } => format!("object {}", name),
             InferType::Store { name, .. } => format!("store {}", name),
             InferType::Actor { name, .. } => format!("actor {}", name),
+            InferType::Enum { name, .. } => format!("enum {}", name),
+            InferType::Trait { name, .. } => format!("trait {}", name),
             InferType::Result(ok, err) => format!("Result[{}, {}]", 
-                self.type_to_string(ok), self.type_to_string(err)),
-            InferType::Var(v) => format!("?{}", v),
+                self.render_type(ok, names), self.render_type(err, names)),
+            InferType::Var(v) => names.get(v).cloned().unwrap_or_else(|| format!("?{}", v)),
             InferType::Unknown => "?".to_string(),
             _ => "?".to_string(),
         }
     }
 
-    /// Main expression inference method - dispatches to specific expression types
+    /// Infer an expression and remember its type for the node
     pub(super) fn infer_expression(&mut self, expr: &Expr) -> Result<InferType, TypeError> {
+        // Left in place on failure, so the error points at the innermost expression
+        let outer_span = self.current_span.replace(expr.span.clone());
+        let ty = self.infer_expression_kind(expr)?;
+        self.current_span = outer_span;
+        self.expr_types.insert(expr.id, ty.clone());
+        Ok(ty)
+    }
+
+    /// Main expression inference method - dispatches to specific expression types
+    fn infer_expression_kind(&mut self, expr: &Expr) -> Result<InferType, TypeError> {
         match &expr.kind {
             ExprKind::Literal(lit) => self.infer_literal(lit),
             
             ExprKind::Identifier(name) => {
                 if let Some(ty) = self.env.lookup(name) {
-                    Ok(ty)
+                    Ok(self.instantiate(&ty))
                 } else {
                     Err(TypeError::UnknownVariable(name.clone()))
                 }
@@ -415,26 +665,7 @@ impl TypeResolver {
                 self.infer_call_expression(callee, &arg_exprs)
             }
             
-            ExprKind::Index { object, index } => {
-                let object_type = self.infer_expression(object)?;
-                let index_type = self.infer_expression(index)?;
-                
-                // For now, assume it's a list or map access
-                let element_type = InferType::Var(self.var_gen.fresh());
-                
-                // Add constraints based on container type
-                match &object_type {
-                    InferType::List(_) => {
-                        self.constraints.push(Constraint::Equal(index_type, InferType::Int));
-                    }
-                    InferType::Map(key_type, _) => {
-                        self.constraints.push(Constraint::Equal(index_type, *key_type.clone()));
-                    }
-                    _ => {}
-                }
-                
-                Ok(element_type)
-            }
+            ExprKind::Index { object, index } => self.infer_element_access(object, index),
             
             ExprKind::FieldAccess { object, field } => {
                 self.infer_field_access(object, field)
@@ -445,7 +676,7 @@ impl TypeResolver {
                 if !elements.is_empty() {
                     for elem in elements {
                         let elem_type = self.infer_expression(elem)?;
-                        self.constraints.push(Constraint::Equal(element_type_var.clone(), elem_type));
+                        self.constrain(Constraint::Equal(element_type_var.clone(), elem_type));
                     }
                 }
                 Ok(InferType::List(Box::new(element_type_var)))
@@ -458,8 +689,8 @@ impl TypeResolver {
                     for (key, value) in pairs {
                         let k_type = self.infer_expression(key)?;
                         let v_type = self.infer_expression(value)?;
-                        self.constraints.push(Constraint::Equal(key_type_var.clone(), k_type));
-                        self.constraints.push(Constraint::Equal(value_type_var.clone(), v_type));
+                        self.constrain(Constraint::Equal(key_type_var.clone(), k_type));
+                        self.constrain(Constraint::Equal(value_type_var.clone(), v_type));
                     }
                 }
                 Ok(InferType::Map(Box::new(key_type_var), Box::new(value_type_var)))
@@ -477,7 +708,7 @@ impl TypeResolver {
             
             ExprKind::If { condition, then_branch, else_branch } => {
                 let cond_type = self.infer_expression(condition)?;
-                self.constraints.push(Constraint::Equal(cond_type, InferType::Bool));
+                self.constrain(Constraint::Equal(cond_type, InferType::Bool));
                 
                 let then_type = self.infer_expression(then_branch)?;
                 
@@ -488,7 +719,7 @@ impl TypeResolver {
                 };
                 
                 // Both branches must have same type
-                self.constraints.push(Constraint::Equal(then_type.clone(), else_type));
+                self.constrain(Constraint::Equal(then_type.clone(), else_type));
                 
                 Ok(then_type)
             }
@@ -511,15 +742,20 @@ impl TypeResolver {
                     param_types.push(param_type);
                 }
                 
-                // Infer body type in new scope
+                // Infer body type in new scope; its effects happen when the lambda is called
                 let old_env = std::mem::replace(&mut self.env, lambda_env);
-                let return_type = self.infer_expression(body)?;
+                let outer_effects = std::mem::take(&mut self.current_effects);
+                // A `return` inside the lambda leaves the lambda, not the enclosing function
+                let outer_return = self.current_return.take();
+                let return_type = self.infer_expression(body);
+                self.current_return = outer_return;
+                let effects = std::mem::replace(&mut self.current_effects, outer_effects);
                 self.env = old_env;
                 
                 Ok(InferType::Function {
                     params: param_types,
-                    return_type: Box::new(return_type),
-                    effects: EffectSet::pure(),
+                    return_type: Box::new(return_type?),
+                    effects,
                 })
             },
 
@@ -530,7 +766,7 @@ impl TypeResolver {
                         for (field_name, field_expr) in fields {
                             let field_type = self.infer_expression(field_expr)?;
                             if let Some(obj_field_type) = obj_fields.get(field_name) {
-                                self.constraints.push(Constraint::Equal(field_type, obj_field_type.clone()));
+                                self.constrain(Constraint::Equal(field_type, obj_field_type.clone()));
                             } else {
                                 return Err(TypeError::FieldNotFound(field_name.clone()));
                             }
@@ -548,8 +784,9 @@ impl TypeResolver {
 
                 // Ensure list_type is a List and its inner type matches element_type
                 let list_element_type = InferType::Var(self.var_gen.fresh());
-                self.constraints.push(Constraint::Equal(list_type, InferType::List(Box::new(list_element_type.clone()))));
-                self.constraints.push(Constraint::Equal(list_element_type, element_type));
+                self.constrain(Constraint::Equal(list_type, InferType::List(Box::new(list_element_type.clone()))));
+                self.constrain(Constraint::Equal(list_element_type, element_type));
+                self.current_effects.mutation = true;
 
                 Ok(InferType::Unit)
             }
@@ -562,19 +799,34 @@ impl TypeResolver {
                 // Ensure map_type is a Map and its key/value types match
                 let map_key_type = InferType::Var(self.var_gen.fresh());
                 let map_value_type = InferType::Var(self.var_gen.fresh());
-                self.constraints.push(Constraint::Equal(
+                self.constrain(Constraint::Equal(
                     map_type,
                     InferType::Map(Box::new(map_key_type.clone()), Box::new(map_value_type.clone())),
                 ));
-                self.constraints.push(Constraint::Equal(map_key_type, key_type));
-                self.constraints.push(Constraint::Equal(map_value_type, value_type));
+                self.constrain(Constraint::Equal(map_key_type, key_type));
+                self.constrain(Constraint::Equal(map_value_type, value_type));
+                self.current_effects.mutation = true;
 
                 Ok(InferType::Unit)
             }
+            ExprKind::ErrValue(payload) => {
+                let err_type = self.infer_expression(payload)?;
+                let ok_type = InferType::Var(self.var_gen.fresh());
+                Ok(InferType::Result(Box::new(ok_type), Box::new(err_type)))
+            }
+            ExprKind::Cast { expr, target } => self.infer_cast_expression(expr, target),
             _ => Ok(InferType::Unknown),
         }
     }
 
+    /// Type a returned value must widen to: a `T ! E` function may return a plain `T`
+    fn expected_return(&self, return_type: &InferType, value_type: &InferType) -> InferType {
+        match (return_type, value_type) {
+            (InferType::Result(ok, _), value) if !matches!(value, InferType::Result(..)) => (**ok).clone(),
+            _ => return_type.clone(),
+        }
+    }
+
     /// Infer function type from definition
     pub(super) fn infer_function(
         &mut self,
@@ -596,62 +848,105 @@ impl TypeResolver {
             param_types.push(param_type);
         }
         
-        // Infer return type from body
+        // Every `return` is checked against the declared type, or else against
+        // a variable standing for the inferred one
+        let declared_type = return_type.map(|ty| self.ast_type_to_infer_type(ty)).transpose()?;
+        let return_slot = declared_type.clone().unwrap_or_else(|| InferType::Var(self.var_gen.fresh()));
+        let outer_return = self.current_return.replace((return_slot.clone(), false));
+        
+        // Infer return type from body, collecting the effects it performs
         let old_env = std::mem::replace(&mut self.env, func_env);
+        let outer_effects = std::mem::take(&mut self.current_effects);
         let inferred_return = if body.is_empty() {
-            InferType::Unit
+            Ok(InferType::Unit)
         } else {
-            self.infer_block(body)?
+            self.infer_block(body)
         };
+        let effects = std::mem::replace(&mut self.current_effects, outer_effects);
         self.env = old_env;
+        let saw_return = std::mem::replace(&mut self.current_return, outer_return).is_some_and(|(_, seen)| seen);
+        let inferred_return = inferred_return?;
         
-        // Check against declared return type
-        let final_return = if let Some(declared) = return_type {
-            let declared_type = self.ast_type_to_infer_type(declared)?;
-            self.constraints.push(Constraint::Equal(inferred_return, declared_type.clone()));
+        // A body ending in a loop or assignment has no value to check; flow
+        // analysis reports it if the end is reachable. Returns were checked as
+        // they were inferred.
+        let tail = body.last().filter(|stmt| matches!(stmt.kind, StmtKind::Expression(_) | StmtKind::If { .. }));
+        let final_return = if let Some(declared_type) = declared_type {
+            if let Some(last) = tail {
+                let expected = self.expected_return(&declared_type, &inferred_return);
+                let outer_span = self.current_span.replace(last.span.clone());
+                self.constrain(Constraint::Widens(inferred_return, expected));
+                self.current_span = outer_span;
+            }
             declared_type
         } else {
-            inferred_return
+            // Falling off the end without a value means the function returns unit
+            if tail.is_some() || !saw_return {
+                self.constrain(Constraint::Equal(return_slot.clone(), inferred_return));
+            }
+            return_slot
         };
         
         Ok(InferType::Function {
             params: param_types,
             return_type: Box::new(final_return),
-            effects: EffectSet::pure(), // Will be inferred from body
+            effects,
         })
     }
 
     /// Infer type for a statement
     pub(super) fn infer_statement(&mut self, stmt: &Stmt) -> Result<InferType, TypeError> {
+        let outer_span = self.current_span.replace(stmt.span.clone());
+        let ty = self.infer_statement_kind(stmt)?;
+        self.current_span = outer_span;
+        Ok(ty)
+    }
+    
+    fn infer_statement_kind(&mut self, stmt: &Stmt) -> Result<InferType, TypeError> {
         match &stmt.kind {
             StmtKind::Expression(expr) => self.infer_expression(expr),
             
             StmtKind::Assignment { target, value } => {
                 let value_type = self.infer_expression(value)?;
                 
-                // For now, treat target as a variable binding
-                if let ExprKind::Identifier(name) = &target.kind {
-                    self.env.bind(name.clone(), value_type);
-                }
+                let slot_type = match &target.kind {
+                    ExprKind::Identifier(name) => match self.method_fields.get(name) {
+                        // Inside a method a bare field name writes the receiver's field
+                        Some(field_type) => field_type.clone(),
+                        None => {
+                            self.env.bind(name.clone(), value_type);
+                            return Ok(InferType::Unit);
+                        }
+                    },
+                    ExprKind::FieldAccess { object, field } => self.infer_field_access(object, field)?,
+                    ExprKind::Index { object, index } => self.infer_element_access(object, index)?,
+                    ExprKind::Call { callee, args } if args.len() == 1 => {
+                        self.infer_element_access(callee, &args[0].value)?
+                    }
+                    _ => self.infer_expression(target)?,
+                };
+                // Writing through a field or element mutates shared state
+                self.constrain(Constraint::Widens(value_type, slot_type));
+                self.current_effects.mutation = true;
                 Ok(InferType::Unit)
             }
             
-            StmtKind::Function { name, params, return_type, body } => {
+            StmtKind::Function { name, params, return_type, body, is_pure } => {
                 // Bind function name in current scope before inferring body
                 let func_type_var = InferType::Var(self.var_gen.fresh());
                 self.env.bind(name.clone(), func_type_var.clone());
 
                 let func_type = self.infer_function(name, params, return_type.as_ref(), body)?;
-                self.constraints.push(Constraint::Equal(func_type_var, func_type));
+                if let InferType::Function { effects, .. } = &func_type {
+                    self.check_purity(name, *is_pure, effects)?;
+                }
+                self.constrain(Constraint::Equal(func_type_var, func_type));
                 Ok(InferType::Unit)
             },
             
-            StmtKind::Object { name, fields, methods } => {
-                let obj_type = self.create_object_type(name, fields, methods, false, false)?;
-                self.object_definitions.insert(name.clone(), obj_type.clone());
-                self.env.bind(name.clone(), obj_type);
-                Ok(InferType::Unit)
-            }
+            // Objects are defined with the other types up front; defining one
+            // again would detach its field types from the uses already inferred
+            StmtKind::Object { .. } => Ok(InferType::Unit),
             
             StmtKind::Store { name, fields, methods } => {
                 let store_type = self.create_object_type(name, fields, methods, false, true)?;
@@ -666,34 +961,79 @@ impl TypeResolver {
                 Ok(InferType::Unit)
             }
             
+            StmtKind::Enum { name, variants } => {
+                let enum_type = self.create_enum_type(name, variants)?;
+                self.enum_definitions.insert(name.clone(), enum_type.clone());
+                self.env.bind(name.clone(), enum_type);
+                Ok(InferType::Unit)
+            }
+            
+            StmtKind::Trait { .. } => Ok(InferType::Unit),
+            
             StmtKind::If { condition, then_branch, else_branch } => {
                 let cond_type = self.infer_expression(condition)?;
-                self.constraints.push(Constraint::Equal(cond_type, InferType::Bool));
+                self.constrain(Constraint::Equal(cond_type, InferType::Bool));
                 
                 let then_type = self.infer_block(then_branch)?;
                 
                 if let Some(else_stmts) = else_branch {
                     let else_type = self.infer_block(else_stmts)?;
-                    self.constraints.push(Constraint::Equal(then_type.clone(), else_type));
+                    self.constrain(Constraint::Equal(then_type.clone(), else_type));
                 }
                 
                 Ok(then_type)
             }
             
-            StmtKind::While { condition, body } => {
+            StmtKind::While { condition, body }
+            | StmtKind::Unless { condition, body }
+            | StmtKind::Until { condition, body } => {
                 let cond_type = self.infer_expression(condition)?;
-                self.constraints.push(Constraint::Equal(cond_type, InferType::Bool));
+                self.constrain(Constraint::Equal(cond_type, InferType::Bool));
                 
                 self.infer_block(body)?;
                 Ok(InferType::Unit)
             }
             
+            StmtKind::Iterate { iterable, body } => {
+                let iterable_type = self.infer_expression(iterable)?;
+                let element_type = InferType::Var(self.var_gen.fresh());
+                self.constrain(Constraint::IsIterable(iterable_type, element_type.clone()));
+                
+                // The current element is `$` inside the body
+                let mut body_env = self.env.extend();
+                body_env.bind("$".to_string(), element_type);
+                let outer_env = std::mem::replace(&mut self.env, body_env);
+                let body_type = self.infer_block(body);
+                self.env = outer_env;
+                body_type?;
+                Ok(InferType::Unit)
+            }
+            
+            StmtKind::ErrorHandler { handler, inner } => {
+                self.infer_statement(inner)?;
+                for action in &handler.actions {
+                    match action {
+                        ErrorAction::Log(Some(expr)) | ErrorAction::Return(Some(expr)) | ErrorAction::Custom(expr) => {
+                            self.infer_expression(expr)?;
+                        }
+                        ErrorAction::Log(None) | ErrorAction::Return(None) => {}
+                    }
+                }
+                Ok(InferType::Unit)
+            }
+            
             StmtKind::Return(value) => {
-                if let Some(val) = value {
-                    self.infer_expression(val)
-                } else {
-                    Ok(InferType::Unit)
+                let value_type = match value {
+                    Some(val) => self.infer_expression(val)?,
+                    None => InferType::Unit,
+                };
+                if let Some((return_type, seen)) = &mut self.current_return {
+                    *seen = true;
+                    let return_type = return_type.clone();
+                    let expected = self.expected_return(&return_type, &value_type);
+                    self.constrain(Constraint::Widens(value_type.clone(), expected));
                 }
+                Ok(value_type)
             }
             _ => Ok(InferType::Unit),
         }
@@ -711,7 +1051,7 @@ impl TypeResolver {
         // Validate initial value if provided
         if let Some(init_expr) = initial_value {
             let init_type = self.infer_expression(init_expr)?;
-            self.constraints.push(Constraint::Equal(stored_type.clone(), init_type));
+            self.constrain(Constraint::Equal(stored_type.clone(), init_type));
         }
         
         let mut methods = HashMap::new();
@@ -819,8 +1159,9 @@ impl TypeResolver {
     /// Infer literal types
     fn infer_literal(&mut self, lit: &Literal) -> Result<InferType, TypeError> {
         match lit {
-            Literal::Integer(_) => Ok(InferType::Int),
-            Literal::Float(_) => Ok(InferType::Float),
+            // Numeric literals take their type from context, defaulting to int/float
+            Literal::Integer(_) => Ok(self.numeric_literal(InferType::Int)),
+            Literal::Float(_) => Ok(self.numeric_literal(InferType::Float)),
             Literal::String(_) => Ok(InferType::String),
             Literal::Bool(_) => Ok(InferType::Bool),
             Literal::Unit => Ok(InferType::Unit), // null/nil
@@ -833,6 +1174,29 @@ impl TypeResolver {
         }
     }
 
+    /// A fresh variable for a numeric literal, remembered so it can be defaulted
+    fn numeric_literal(&mut self, default: InferType) -> InferType {
+        let var = self.var_gen.fresh();
+        self.numeric_literals.push((var, default, self.current_span.clone()));
+        InferType::Var(var)
+    }
+    
+    /// Require a constraint of the statement or expression being inferred
+    pub(super) fn constrain(&mut self, constraint: Constraint) {
+        self.constraints.push((constraint, self.current_span.clone()));
+    }
+
+    /// Infer an explicit `expr as type` conversion between numeric types
+    fn infer_cast_expression(&mut self, expr: &Expr, target: &Type) -> Result<InferType, TypeError> {
+        let source = self.infer_expression(expr)?;
+        let target = self.ast_type_to_infer_type(target)?;
+        let convertible = |ty: &InferType| ty.is_numeric() || matches!(ty, InferType::Var(_));
+        if !target.is_numeric() || !convertible(&source) {
+            return Err(TypeError::InvalidCast(source, target));
+        }
+        Ok(target)
+    }
+
     /// Infer binary expression types with operator overloading support
     fn infer_binary_expression(
         &mut self,
@@ -845,63 +1209,72 @@ impl TypeResolver {
         
         match op {
             // Arithmetic operators
-            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
-                // Both operands must be numeric and same type
-                self.constraints.push(Constraint::Equal(left_type.clone(), right_type.clone()));
-                
-                // Result type is same as operands
-                match &left_type {
-                    InferType::Int | InferType::Float => Ok(left_type),
-                    InferType::Var(_) => {
-                        // Could be int or float
-                        let numeric_type = InferType::Var(self.var_gen.fresh());
-                        self.constraints.push(Constraint::Equal(left_type, numeric_type.clone()));
-                        Ok(numeric_type)
-                    }
-                    _ => Err(TypeError::TypeMismatch(InferType::Int, left_type)),
-                }
+            // `+` also concatenates strings
+            BinaryOp::Add => {
+                self.constrain(Constraint::Addable(left_type.clone()));
+                self.constrain(Constraint::Addable(right_type.clone()));
+                Ok(self.promoted(left_type, right_type))
+            }
+            
+            // Arithmetic operators; mixed operands promote to the wider type
+            BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
+                self.constrain(Constraint::Numeric(left_type.clone()));
+                self.constrain(Constraint::Numeric(right_type.clone()));
+                Ok(self.promoted(left_type, right_type))
             }
             
             // Comparison operators
             BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
                 // Operands must be comparable (numeric)
-                self.constraints.push(Constraint::Equal(left_type, right_type));
+                self.constrain(Constraint::Numeric(left_type.clone()));
+                self.constrain(Constraint::Numeric(right_type.clone()));
+                self.promoted(left_type, right_type);
                 Ok(InferType::Bool)
             }
             
             // Equality operators
             BinaryOp::Eq | BinaryOp::Ne => {
                 // Any types can be compared for equality
-                self.constraints.push(Constraint::Equal(left_type, right_type));
+                self.constrain(Constraint::Equal(left_type, right_type));
                 Ok(InferType::Bool)
             }
             
             // Logical operators
             BinaryOp::And | BinaryOp::Or => {
-                self.constraints.push(Constraint::Equal(left_type, InferType::Bool));
-                self.constraints.push(Constraint::Equal(right_type, InferType::Bool));
+                self.constrain(Constraint::Equal(left_type, InferType::Bool));
+                self.constrain(Constraint::Equal(right_type, InferType::Bool));
                 Ok(InferType::Bool)
             }
             
             // Bitwise operators
             BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor | BinaryOp::Shl | BinaryOp::Shr => {
                 // Both operands must be integers and same type
-                self.constraints.push(Constraint::Equal(left_type.clone(), InferType::Int));
-                self.constraints.push(Constraint::Equal(right_type.clone(), InferType::Int));
-                Ok(InferType::Int)
+                self.constrain(Constraint::Equal(left_type.clone(), right_type));
+                match &left_type {
+                    ty if ty.is_integer() => Ok(left_type),
+                    InferType::Var(_) => Ok(left_type),
+                    _ => Err(TypeError::TypeMismatch(InferType::Int, left_type)),
+                }
             }
             BinaryOp::Is => {
-                self.constraints.push(Constraint::Equal(left_type, right_type));
+                self.constrain(Constraint::Equal(left_type, right_type));
                 Ok(InferType::Bool)
             }
             BinaryOp::Xor => {
-                self.constraints.push(Constraint::Equal(left_type, InferType::Bool));
-                self.constraints.push(Constraint::Equal(right_type, InferType::Bool));
+                self.constrain(Constraint::Equal(left_type, InferType::Bool));
+                self.constrain(Constraint::Equal(right_type, InferType::Bool));
                 Ok(InferType::Bool)
             }
         }
     }
 
+    /// Type of a binary operation on `left` and `right`, left open until the operands are known
+    fn promoted(&mut self, left: InferType, right: InferType) -> InferType {
+        let result = InferType::Var(self.var_gen.fresh());
+        self.constrain(Constraint::Promotes(left, right, result.clone()));
+        result
+    }
+
     /// Infer unary expression types
     fn infer_unary_expression(&mut self, op: &UnaryOp, operand: &Expr) -> Result<InferType, TypeError> {
         let operand_type = self.infer_expression(operand)?;
@@ -909,47 +1282,37 @@ impl TypeResolver {
         match op {
             UnaryOp::Neg => {
                 // Operand must be numeric
-                match &operand_type {
-                    InferType::Int | InferType::Float => Ok(operand_type),
-                    InferType::Var(_) => {
-                        // Constrain to be numeric
-                        Ok(operand_type)
-                    }
-                    _ => Err(TypeError::TypeMismatch(InferType::Int, operand_type)),
-                }
+                self.constrain(Constraint::Numeric(operand_type.clone()));
+                Ok(operand_type)
             }
             
             UnaryOp::Not => {
-                self.constraints.push(Constraint::Equal(operand_type, InferType::Bool));
+                self.constrain(Constraint::Equal(operand_type, InferType::Bool));
                 Ok(InferType::Bool)
             }
             
-            UnaryOp::BitNot => {
-                self.constraints.push(Constraint::Equal(operand_type.clone(), InferType::Int));
-                Ok(InferType::Int)
-            }
+            UnaryOp::BitNot => match &operand_type {
+                ty if ty.is_integer() => Ok(operand_type),
+                InferType::Var(_) => Ok(operand_type),
+                _ => Err(TypeError::TypeMismatch(InferType::Int, operand_type)),
+            },
         }
     }
 
     /// Infer function call types - handles Coral's flexible call syntax  
     fn infer_call_expression(&mut self, callee: &Expr, args: &[Expr]) -> Result<InferType, TypeError> {
+        // `primes(0)` reads an element when the callee is known to be a collection
+        if let (Some(InferType::List(_) | InferType::Map(_, _) | InferType::String), [index]) = (self.known_type(callee), args) {
+            return self.infer_element_access(callee, index);
+        }
         if let ExprKind::FieldAccess { object, field } = &callee.kind {
+            if let Some(enum_type) = self.enum_for_expr(object) {
+                return self.infer_variant_construction(&enum_type, field, args);
+            }
             if field == "make" {
                 let object_type = self.infer_expression(object)?;
-                if let InferType::Object { fields, .. } = &object_type {
-                    let make_params: Vec<InferType> = fields.values().cloned().collect();
-                    let make_return = object_type.clone();
-                    let func_type = InferType::Function {
-                        params: make_params,
-                        return_type: Box::new(make_return),
-                        effects: EffectSet::pure(),
-                    };
-                    let mut arg_types = Vec::new();
-                    for arg in args {
-                        arg_types.push(self.infer_expression(arg)?);
-                    }
-                    self.constraints.push(Constraint::IsCallable(func_type, arg_types, object_type.clone()));
-                    return Ok(object_type);
+                if let InferType::Object { .. } = &object_type {
+                    return self.infer_construction(object_type, args);
                 }
             }
             if field == "with_id" {
@@ -964,7 +1327,8 @@ impl TypeResolver {
                     for arg in args {
                         arg_types.push(self.infer_expression(arg)?);
                     }
-                    self.constraints.push(Constraint::IsCallable(func_type, arg_types, *value_type.clone()));
+                    self.constrain(Constraint::IsCallable(func_type, arg_types, *value_type.clone()));
+                    self.current_effects = self.current_effects.union(&EffectSet::store());
                     return Ok(*value_type.clone());
                 }
             }
@@ -972,6 +1336,15 @@ impl TypeResolver {
         }
 
         let callee_type = self.infer_expression(callee)?;
+        // `point(1, 2)` is shorthand for `point.make(1, 2)`
+        if let ExprKind::Identifier(name) = &callee.kind {
+            if self.object_definitions.contains_key(name) && matches!(callee_type, InferType::Object { .. }) {
+                return self.infer_construction(callee_type, args);
+            }
+        }
+        if let InferType::Function { effects, .. } = &callee_type {
+            self.current_effects = self.current_effects.union(effects);
+        }
         let mut arg_types = Vec::new();
         
         for arg in args {
@@ -981,22 +1354,97 @@ impl TypeResolver {
         let return_type = InferType::Var(self.var_gen.fresh());
         
         // Callee must be callable with these arguments
-        self.constraints.push(Constraint::IsCallable(callee_type, arg_types, return_type.clone()));
+        self.constrain(Constraint::IsCallable(callee_type, arg_types, return_type.clone()));
         
         Ok(return_type)
     }
     
+    /// Infer `object.make(args)` - arguments fill fields in declaration order,
+    /// and trailing fields with defaults may be left out
+    fn infer_construction(&mut self, object_type: InferType, args: &[Expr]) -> Result<InferType, TypeError> {
+        let (name, fields) = match &object_type {
+            InferType::Object { name, fields, .. } => (name, fields),
+            _ => return Err(TypeError::NotAnObject(object_type)),
+        };
+        let declared = self.object_fields.get(name).cloned().unwrap_or_default();
+        let required = declared.iter().rposition(|(_, has_default)| !has_default).map_or(0, |i| i + 1);
+        if args.len() < required || args.len() > declared.len() {
+            return Err(TypeError::ArityMismatch(declared.len(), args.len()));
+        }
+        for (arg, (field, _)) in args.iter().zip(&declared) {
+            let arg_type = self.infer_expression(arg)?;
+            self.constrain(Constraint::Widens(arg_type, fields[field].clone()));
+        }
+        Ok(object_type)
+    }
+
+    /// Element type at `collection(index)`: a list or string position, or a map entry
+    fn infer_element_access(&mut self, collection: &Expr, index: &Expr) -> Result<InferType, TypeError> {
+        let collection_type = self.infer_expression(collection)?;
+        let index_type = self.infer_expression(index)?;
+        match collection_type {
+            InferType::List(element_type) => {
+                self.constrain(Constraint::Widens(index_type, InferType::Int));
+                Ok(*element_type)
+            }
+            InferType::Map(key_type, value_type) => {
+                self.constrain(Constraint::Widens(index_type, *key_type));
+                Ok(*value_type)
+            }
+            InferType::String => {
+                self.constrain(Constraint::Widens(index_type, InferType::Int));
+                Ok(InferType::String)
+            }
+            InferType::Var(_) => Ok(InferType::Var(self.var_gen.fresh())),
+            other => Err(TypeError::NotIterable(other)),
+        }
+    }
+
     /// Infer field access types
     fn infer_field_access(&mut self, object: &Expr, field: &str) -> Result<InferType, TypeError> {
+        if let Some(enum_type) = self.enum_for_expr(object) {
+            return self.infer_variant_construction(&enum_type, field, &[]);
+        }
+        
         let object_type = self.infer_expression(object)?;
         let field_type = InferType::Var(self.var_gen.fresh());
         
         // Object must have this field
-        self.constraints.push(Constraint::HasField(object_type, field.to_string(), field_type.clone()));
+        self.constrain(Constraint::HasField(object_type, field.to_string(), field_type.clone()));
         
         Ok(field_type)
     }
     
+    /// Infer `enum_name.variant(args)` - payload arity and types must match the definition
+    fn infer_variant_construction(
+        &mut self,
+        enum_type: &InferType,
+        variant: &str,
+        args: &[Expr],
+    ) -> Result<InferType, TypeError> {
+        let payload = match enum_type.enum_variant(variant) {
+            Some((_, payload)) => payload.to_vec(),
+            None => {
+                let enum_name = match enum_type {
+                    InferType::Enum { name, .. } => name.clone(),
+                    _ => String::new(),
+                };
+                return Err(TypeError::VariantNotFound(enum_name, variant.to_string()));
+            }
+        };
+        
+        if payload.len() != args.len() {
+            return Err(TypeError::ArityMismatch(payload.len(), args.len()));
+        }
+        
+        for (arg, expected) in args.iter().zip(payload) {
+            let arg_type = self.infer_expression(arg)?;
+            self.constrain(Constraint::Equal(arg_type, expected));
+        }
+        
+        Ok(enum_type.clone())
+    }
+    
     /// Infer method call types
     fn infer_method_call(
         &mut self,
@@ -1005,6 +1453,9 @@ impl TypeResolver {
         args: &[Expr],
     ) -> Result<InferType, TypeError> {
         let object_type = self.infer_expression(object)?;
+        if let Some(InferType::Function { effects, .. }) = self.known_method_type(&object_type, method) {
+            self.current_effects = self.current_effects.union(&effects);
+        }
         let mut arg_types = Vec::new();
 
         // Static methods like 'make' and 'with_id' don't have an implicit 'self'
@@ -1020,11 +1471,49 @@ impl TypeResolver {
         let return_type = InferType::Var(self.var_gen.fresh());
         
         // Object must have this method
-        self.constraints.push(Constraint::HasMethod(object_type, method.to_string(), method_type.clone()));
+        self.constrain(Constraint::HasMethod(object_type, method.to_string(), method_type.clone()));
         
         // Method must be callable with these arguments
-        self.constraints.push(Constraint::IsCallable(method_type, arg_types, return_type.clone()));
+        self.constrain(Constraint::IsCallable(method_type, arg_types, return_type.clone()));
         
         Ok(return_type)
     }
+    
+    /// Method signature when the receiver's type is already known during inference
+    /// Type of a variable or a chain of fields on one, as far as it is already known
+    fn known_type(&self, expr: &Expr) -> Option<InferType> {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.method_fields.get(name).cloned().or_else(|| self.env.lookup(name)),
+            ExprKind::FieldAccess { object, field } => match self.known_type(object)? {
+                InferType::Object { fields, .. } => fields.get(field).cloned(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn known_method_type(&self, object_type: &InferType, method: &str) -> Option<InferType> {
+        match object_type {
+            InferType::Object { methods, .. } | InferType::Store { methods, .. } => methods.get(method).cloned(),
+            InferType::Trait { .. } => object_type.trait_method(method, object_type),
+            _ => None,
+        }
+    }
+    
+    /// A `pure fn` may not perform any effect, directly or through its callees
+    pub(super) fn check_purity(&self, name: &str, is_pure: bool, effects: &EffectSet) -> Result<(), TypeError> {
+        if is_pure && !effects.is_pure() {
+            return Err(TypeError::ImpureFunction(name.to_string(), effects.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// `a` through `z`, then `a1`, `b1`, ... for the variable at `index`
+fn type_var_name(index: usize) -> String {
+    let letter = (b'a' + (index % 26) as u8) as char;
+    match index / 26 {
+        0 => letter.to_string(),
+        round => format!("{}{}", letter, round),
+    }
 }
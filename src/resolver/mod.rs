@@ -2,25 +2,52 @@ pub mod error;
 pub mod types;
 pub mod env;
 pub mod inference;
+pub mod generalize;
 pub mod unification;
-pub mod visitor;
 
 use crate::ast::*;
+use crate::consteval::{is_constant_name, ConstValue};
+use crate::tables::TypeTable;
 use std::collections::HashMap;
 
-use self::error::TypeError;
-pub use self::types::{Constraint, InferType, TypeVar, TypeVarGen};
+use self::error::{LocatedTypeError, TypeError};
+pub use self::types::{Constraint, EffectSet, InferType, TypeVar, TypeVarGen};
 use self::env::TypeEnv;
 
 /// The main type resolver - this is where the magic happens
 pub struct TypeResolver {
     var_gen: TypeVarGen,
-    constraints: Vec<Constraint>,
+    /// Constraints to solve, with the source each was generated for
+    constraints: Vec<(Constraint, Option<SourceSpan>)>,
     env: TypeEnv,
     builtin_types: HashMap<String, InferType>,
     object_definitions: HashMap<String, InferType>,
     store_types: HashMap<String, InferType>,
     actor_types: HashMap<String, InferType>,
+    enum_definitions: HashMap<String, InferType>,
+    trait_definitions: HashMap<String, InferType>,
+    trait_impls: HashMap<String, Vec<String>>,
+    type_params: HashMap<String, TypeVar>,
+    /// Effects performed so far by the body being inferred
+    current_effects: EffectSet,
+    /// Fields of the object whose method is being inferred, assignable by bare name
+    method_fields: HashMap<String, InferType>,
+    /// Each object's fields in declaration order, with whether they have a default
+    object_fields: HashMap<String, Vec<(String, bool)>>,
+    /// Variables standing for numeric literals, with the type each defaults to
+    numeric_literals: Vec<(TypeVar, InferType, Option<SourceSpan>)>,
+    /// Return type of the function being inferred, and whether a `return` was seen
+    current_return: Option<(InferType, bool)>,
+    /// Statement or expression being inferred, or the constraint being solved
+    current_span: Option<SourceSpan>,
+    /// Numeric/Addable constraints the last solve left on unsolved variables
+    pending_classes: Vec<Constraint>,
+    /// Class constraints carried by quantified variables, re-imposed on instantiation
+    class_bounds: HashMap<TypeVar, Vec<Constraint>>,
+    /// Inferred type of every expression, before the final substitution
+    expr_types: HashMap<NodeId, InferType>,
+    /// Solved expression types, filled in once resolution succeeds
+    type_table: TypeTable,
 }
 
 impl TypeResolver {
@@ -33,6 +60,20 @@ impl TypeResolver {
             object_definitions: HashMap::new(),
             store_types: HashMap::new(),
             actor_types: HashMap::new(),
+            enum_definitions: HashMap::new(),
+            trait_definitions: HashMap::new(),
+            trait_impls: HashMap::new(),
+            type_params: HashMap::new(),
+            current_effects: EffectSet::pure(),
+            method_fields: HashMap::new(),
+            object_fields: HashMap::new(),
+            numeric_literals: Vec::new(),
+            current_return: None,
+            current_span: None,
+            pending_classes: Vec::new(),
+            class_bounds: HashMap::new(),
+            expr_types: HashMap::new(),
+            type_table: TypeTable::new(),
         };
         
         resolver.initialize_builtins();
@@ -40,22 +81,484 @@ impl TypeResolver {
     }
     
     /// Main entry point for type resolution
-    pub fn resolve_program(&mut self, program: &mut Program) -> Result<(), TypeError> {
-        // Phase 1: Collect all type definitions (objects, stores, actors) and function signatures
+    pub fn resolve_program(&mut self, program: &Program) -> Result<(), LocatedTypeError> {
+        self.resolve_phases(program).map_err(|error| LocatedTypeError {
+            error,
+            span: self.current_span.take(),
+        })
+    }
+    
+    fn resolve_phases(&mut self, program: &Program) -> Result<(), TypeError> {
+        // Phase 1: Collect all type definitions (enums, traits, objects, stores, actors)
         self.collect_type_definitions(program)?;
-        self.collect_function_signatures(program)?;
         
-        // Phase 2: Generate constraints for all statements
+        // Constants are folded up front, so function bodies can use them
+        self.bind_constants(program);
+        
+        // Top-level variables are visible in functions before their assignments are inferred
+        let globals = self.bind_globals(program);
+        
+        // Phase 2: Infer and generalize top-level functions, callees first
+        self.infer_function_groups(program)?;
+        
+        // Phase 3: Generate constraints for the remaining statements
         for stmt in &program.statements {
-            self.infer_statement(stmt)?;
+            if !matches!(stmt.kind, StmtKind::Function { .. }) {
+                self.infer_statement(stmt)?;
+            }
+        }
+        for (name, placeholder) in globals {
+            if let Some(assigned) = self.env.lookup(&name) {
+                self.constrain(Constraint::Equal(placeholder, assigned));
+            }
         }
         
-        // Phase 3: Solve constraints using unification
+        // Phase 4: Solve constraints using unification
         let subst = self.solve_constraints()?;
         
-        // Phase 4: Apply substitutions to resolve all types
-        self.apply_substitutions_to_program(program, &subst)?;
+        // Phase 5: Record the solved type of every expression, object or store field and function
+        for (id, ty) in &self.expr_types {
+            let solved = self.apply_substitution(ty, &subst);
+            self.type_table.insert(*id, self.infer_type_to_ast_type(&solved));
+        }
+        for (name, definition) in self.object_definitions.iter().chain(&self.store_types) {
+            if let InferType::Object { fields, .. } = definition {
+                for (field, ty) in fields {
+                    let solved = self.apply_substitution(ty, &subst);
+                    self.type_table.insert_field(name, field, self.infer_type_to_ast_type(&solved));
+                }
+            }
+        }
+        for stmt in &program.statements {
+            if let StmtKind::Function { name, .. } = &stmt.kind {
+                if let Some(scheme) = self.env.lookup(name) {
+                    let solved = self.apply_substitution(&scheme, &subst);
+                    self.type_table.insert_function(name, self.signature_to_ast_type(&solved));
+                }
+            }
+        }
         
         Ok(())
     }
+    
+    /// Bind the type of every top-level constant with a known value; constants
+    /// that fail to evaluate are reported by the semantic pass
+    fn bind_constants(&mut self, program: &Program) {
+        let Ok(constants) = crate::consteval::program_constants(program) else {
+            return;
+        };
+        for (name, value) in constants {
+            let ty = match value {
+                ConstValue::Int(_) => InferType::Int,
+                ConstValue::Float(_) => InferType::Float,
+                ConstValue::Bool(_) => InferType::Bool,
+                ConstValue::String(_) => InferType::String,
+            };
+            self.env.bind(name, ty);
+        }
+    }
+    
+    /// Bind a type variable for each variable assigned at the top level; the
+    /// variable is tied to the assigned type once top-level code is inferred
+    fn bind_globals(&mut self, program: &Program) -> Vec<(String, InferType)> {
+        let mut globals: Vec<(String, InferType)> = Vec::new();
+        for stmt in &program.statements {
+            let StmtKind::Assignment { target, .. } = &stmt.kind else {
+                continue;
+            };
+            let ExprKind::Identifier(name) = &target.kind else {
+                continue;
+            };
+            if is_constant_name(name) || globals.iter().any(|(global, _)| global == name) {
+                continue;
+            }
+            let placeholder = InferType::Var(self.var_gen.fresh());
+            self.env.bind(name.clone(), placeholder.clone());
+            globals.push((name.clone(), placeholder));
+        }
+        globals
+    }
+    
+    /// Solved type of each expression node, keyed by `NodeId`
+    pub fn type_table(&self) -> &TypeTable {
+        &self.type_table
+    }
+    
+    /// Inferred signature of each top-level function, including its effects
+    pub fn function_types(&self, program: &Program) -> Vec<(String, String)> {
+        program.statements.iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::Function { name, .. } => {
+                    self.env.lookup(name).map(|ty| (name.clone(), self.type_to_string(&ty)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn resolve(input: &str) -> Result<(), TypeError> {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        let program = parser.parse().unwrap();
+        TypeResolver::new().resolve_program(&program).map_err(|e| e.error)
+    }
+
+    fn function_types(input: &str) -> Vec<(String, String)> {
+        let mut lexer = Lexer::new(input.to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let mut parser = Parser::new(tokens, "test".to_string());
+        let program = parser.parse().unwrap();
+        let mut resolver = TypeResolver::new();
+        resolver.resolve_program(&program).unwrap();
+        resolver.function_types(&program)
+    }
+
+    #[test]
+    fn test_enum_variant_construction() {
+        let code = "enum shape\n    circle(f64)\n    point\n\nfn build()\n    a is shape.circle(1.5)\n    b is shape.point\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_enum_variant_arity_mismatch() {
+        let code = "enum shape\n    rect(f64, f64)\n\nfn build()\n    a is shape.rect(1.5)\n";
+        assert!(matches!(resolve(code), Err(TypeError::ArityMismatch(2, 1))));
+    }
+
+    #[test]
+    fn test_enum_unknown_variant() {
+        let code = "enum shape\n    point\n\nfn build()\n    a is shape.line\n";
+        assert!(matches!(resolve(code), Err(TypeError::VariantNotFound(_, _))));
+    }
+
+    #[test]
+    fn test_enum_payload_type_mismatch() {
+        let code = "enum shape\n    circle(f64)\n\nfn build()\n    a is shape.circle('wide')\n";
+        assert!(resolve(code).is_err());
+    }
+
+    const SHAPE_TRAIT: &str = "trait shape\n    area() -> f64\n";
+
+    #[test]
+    fn test_trait_conformance() {
+        let code = format!(
+            "{}object circle is shape\n    r: f64\n    area() -> f64:\n        return 3.0\nfn total(s: shape) -> f64\n    return s.area()\nfn use_circle(c: circle) -> f64\n    return total(c)\n",
+            SHAPE_TRAIT
+        );
+        assert!(resolve(&code).is_ok());
+    }
+
+    #[test]
+    fn test_trait_missing_method() {
+        let code = format!("{}object circle is shape\n    r: f64\n", SHAPE_TRAIT);
+        assert!(matches!(resolve(&code), Err(TypeError::MethodNotFound(ref m)) if m == "area"));
+    }
+
+    #[test]
+    fn test_trait_bound_rejects_non_conforming_object() {
+        let code = format!(
+            "{}object square\n    side: f64\n    area() -> f64:\n        return 1.0\nfn total(s: shape) -> f64\n    return s.area()\nfn use_square(q: square) -> f64\n    return total(q)\n",
+            SHAPE_TRAIT
+        );
+        assert!(matches!(resolve(&code), Err(TypeError::TraitNotImplemented(ref o, ref t)) if o == "square" && t == "shape"));
+    }
+
+    #[test]
+    fn test_trait_value_does_not_fit_an_object_slot() {
+        let code = format!(
+            "{}object circle is shape\n    r: f64\n    area() -> f64:\n        return 3.0\nfn radius(c: circle) -> f64\n    return c.r\nfn leak(s: shape) -> f64\n    return radius(s)\n",
+            SHAPE_TRAIT
+        );
+        assert!(matches!(resolve(&code), Err(TypeError::TypeMismatch(InferType::Object { .. }, InferType::Trait { .. }))));
+    }
+
+    #[test]
+    fn test_unknown_trait() {
+        assert!(matches!(resolve("object circle is shape\n    r: f64\n"), Err(TypeError::UnknownTrait(_))));
+    }
+
+    #[test]
+    fn test_generic_function_instantiated_per_use() {
+        let code = "fn identity of T(x: T) -> T\n    return x\nfn main()\n    a is identity(1)\n    b is identity('one')\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_generic_param_must_stay_abstract() {
+        let code = "fn bump of T(x: T) -> T\n    return x + 1\n";
+        assert!(matches!(resolve(code), Err(TypeError::TypeParamConstrained(ref p, _)) if p == "T"));
+    }
+
+    #[test]
+    fn test_generic_object() {
+        let code = "object box of T\n    value: T\nfn main()\n    a is box.make(1)\n    b is box.make('one')\nfn unwrap(b: box of i64) -> i64\n    return b.value\n";
+        assert!(resolve(code).is_ok());
+
+        let code = "object box of T\n    value: T\nfn unwrap(b: box of i64) -> string\n    return b.value\n";
+        assert!(resolve(code).is_err());
+    }
+
+    #[test]
+    fn test_let_polymorphism() {
+        let code = "fn main()\n    a is id(1)\n    b is id('one')\nfn id(x)\n    return x\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_generalized_signatures_keep_their_variables() {
+        let mut lexer = Lexer::new("fn id(x)\n    return x\nfn main()\n    a is id(1)\n".to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let program = Parser::new(tokens, "test".to_string()).parse().unwrap();
+        let mut resolver = TypeResolver::new();
+        resolver.resolve_program(&program).unwrap();
+        match resolver.type_table().function_type("id") {
+            Some(Type::Function { params, return_type }) => {
+                assert!(matches!(params[0], Type::TypeVar(_)));
+                assert_eq!(params[0], **return_type);
+            }
+            other => panic!("Expected a function signature, got {:?}", other),
+        }
+        assert_eq!(
+            resolver.type_table().function_type("main"),
+            Some(&Type::Function { params: vec![], return_type: Box::new(Type::Unit) })
+        );
+    }
+
+    #[test]
+    fn test_mutually_recursive_group_generalized_together() {
+        let code = "fn ping(x)\n    return pong(x)\nfn pong(x)\n    return ping(x)\nfn main()\n    a is ping(1)\n    b is pong('one')\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_recursion_is_monomorphic_within_group() {
+        let code = "fn f(x)\n    a is f(1)\n    b is f('one')\n    return x\n";
+        assert!(resolve(code).is_err());
+    }
+
+    #[test]
+    fn test_effects_propagate_through_calls() {
+        let code = "fn shout(x)\n    log(x)\nfn greet()\n    shout('hi')\nfn add(a: i64, b: i64) -> i64\n    return a + b\n";
+        let types = function_types(code);
+        assert_eq!(types[1], ("greet".to_string(), "() -> unit with io".to_string()));
+        assert_eq!(types[2], ("add".to_string(), "(int, int) -> int".to_string()));
+    }
+
+    #[test]
+    fn test_generalized_signatures_print_named_variables() {
+        let code = "fn apply(f, v)\n    return f(v)\nfn id(x)\n    return x\n";
+        let types = function_types(code);
+        assert_eq!(types[0].1, "((a) -> b, a) -> b");
+        assert_eq!(types[1].1, "(a) -> a");
+    }
+
+    #[test]
+    fn test_pure_function_rejects_effects() {
+        assert!(resolve("pure fn add(a: i64, b: i64) -> i64\n    return a + b\n").is_ok());
+
+        let code = "fn shout(x)\n    log(x)\npure fn greet()\n    shout('hi')\n";
+        assert!(matches!(resolve(code), Err(TypeError::ImpureFunction(ref name, ref effects)) if name == "greet" && effects.io));
+    }
+
+    #[test]
+    fn test_numeric_literals_default() {
+        let code = "fn one()\n    return 1\nfn half()\n    return 0.5\nfn mixed()\n    return 1 + 0.5\n";
+        let types = function_types(code);
+        assert_eq!(types[0].1, "() -> int");
+        assert_eq!(types[1].1, "() -> float");
+        assert_eq!(types[2].1, "() -> float");
+    }
+
+    #[test]
+    fn test_sized_numerics_widen_implicitly() {
+        let code = "fn wide(x: i64) -> f64\n    return x\nfn narrow(x: i8) -> i64\n    return x\nfn call(y: i32) -> i64\n    return wide_int(y)\nfn wide_int(x: i64) -> i64\n    return x\n";
+        assert!(resolve(code).is_ok());
+
+        let code = "fn small(x: i32) -> i32\n    return x + 1\n";
+        assert_eq!(function_types(code)[0].1, "(i32) -> i32");
+    }
+
+    #[test]
+    fn test_narrowing_requires_cast() {
+        let code = "fn narrow(x: i64) -> i32\n    return x\n";
+        assert!(matches!(resolve(code), Err(TypeError::ImplicitNarrowing(InferType::Int, InferType::I32))));
+
+        let code = "fn take(x: f32) -> f32\n    return x\nfn give(y: f64) -> f32\n    return take(y)\n";
+        assert!(matches!(resolve(code), Err(TypeError::ImplicitNarrowing(InferType::Float, InferType::F32))));
+
+        let code = "fn narrow(x: i64) -> i32\n    return x as i32\nfn truncate(x: f64) -> i8\n    return x as i8\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_cast() {
+        let code = "fn convert(s: string) -> i32\n    return s as i32\n";
+        assert!(matches!(resolve(code), Err(TypeError::InvalidCast(InferType::String, InferType::I32))));
+    }
+
+    #[test]
+    fn test_string_concatenation() {
+        let code = "fn greet(name: string) -> string\n    return 'hello ' + name\n";
+        assert!(resolve(code).is_ok());
+
+        assert!(matches!(resolve("fn bad()\n    x is 'a' - 'b'\n"), Err(TypeError::ConstraintUnsatisfied(_))));
+        assert!(resolve("fn bad()\n    x is 'a' + 1\n").is_err());
+    }
+
+    #[test]
+    fn test_mixed_arithmetic_promotes() {
+        let code = "fn mix(a: i32, b: i64)\n    return a * b\nfn scale(a: i32)\n    return a + 2.5\nfn half(a: f32, b: i64)\n    return b / a\n";
+        let types = function_types(code);
+        assert_eq!(types[0].1, "(i32, int) -> int");
+        assert_eq!(types[1].1, "(i32) -> float");
+        assert_eq!(types[2].1, "(f32, int) -> f32");
+    }
+
+    #[test]
+    fn test_addable_stays_polymorphic() {
+        let code = "fn add(a, b)\n    return a + b\nfn main()\n    x is add(1, 2)\n    y is add('a', 'b')\n";
+        assert!(resolve(code).is_ok());
+
+        let code = "fn add(a, b)\n    return a + b\nfn main()\n    x is add(true, false)\n";
+        assert!(matches!(resolve(code), Err(TypeError::ConstraintUnsatisfied(Constraint::Addable(InferType::Bool)))));
+    }
+
+    #[test]
+    fn test_every_return_matches_the_return_type() {
+        let code = "fn f(n: i64) -> i64\n    if n lt 0\n        return 'oops'\n    return n\n";
+        assert!(matches!(resolve(code), Err(TypeError::TypeMismatch(_, _))));
+
+        let code = "fn g(n: i64)\n    if n lt 0\n        return 'neg'\n    return n\n";
+        assert!(resolve(code).is_err());
+
+        let code = "fn pick(n: i64)\n    if n lt 0\n        return 0\n    return n\nfn run() -> i64\n    return pick(2)\n";
+        assert!(resolve(code).is_ok());
+    }
+
+    #[test]
+    fn test_member_without_parens_calls_a_zero_arity_method() {
+        let code = "object doc\n    n: i64\n    process() -> i64:\n        return n + 1\nfn run() -> i64\n    d is doc.make(4)\n    return d.process\n";
+        assert!(resolve(code).is_ok());
+
+        let code = "object doc\n    n: i64\n    scale(k: i64) -> i64:\n        return n * k\nfn run() -> i64\n    d is doc.make(4)\n    return d.scale\n";
+        assert!(matches!(resolve(code), Err(TypeError::ArityMismatch(1, 0))));
+
+        let code = "object doc\n    n: i64\nfn run() -> i64\n    d is doc.make(4)\n    return d.size\n";
+        assert!(matches!(resolve(code), Err(TypeError::FieldNotFound(_))));
+    }
+
+    #[test]
+    fn test_float_literal_rejects_integer_type() {
+        let code = "fn count() -> i32\n    return 1.5\n";
+        assert!(resolve(code).is_err());
+    }
+
+    #[test]
+    fn test_functions_read_top_level_variables() {
+        let code = "total is 40\nfn bump(n: i64) -> i64\n    return n + total\ntotal is total + 1\n";
+        assert!(resolve(code).is_ok());
+
+        let code = "name is 'coral'\nfn bump(n: i64) -> i64\n    return n + name\n";
+        assert!(resolve(code).is_err());
+    }
+
+    #[test]
+    fn test_logical_operands_must_be_bool() {
+        assert!(resolve("fn f(n: i64) -> bool\n    return n gt 0 and n lt 10 or false\n").is_ok());
+        assert!(matches!(
+            resolve("fn f(n: i64) -> bool\n    return n and true\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_functions_as_values() {
+        let code = "fn twice(f: fn(i64) -> i64, x: i64) -> i64\n    return f(f(x))\nfn inc(n: i64) -> i64\n    return n + 1\nfn main() -> i64\n    step is 2\n    return twice(inc, 1) + twice(fn(n) -> n + step, 1)\n";
+        assert!(resolve(code).is_ok());
+        assert!(matches!(
+            resolve("fn twice(f: fn(i64) -> i64, x: i64) -> i64\n    return f(f(x))\nfn main() -> i64\n    return twice(fn(n: bool) -> n, 1)\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_methods_see_self_and_fields() {
+        let code = "object counter\n    count: i64\n    bump(by: i64) -> i64:\n        count is count + by\n        return self.current()\n    current() -> i64:\n        return count\n";
+        assert!(resolve(code).is_ok());
+        assert!(matches!(
+            resolve("object counter\n    count: i64\n    reset() -> i64:\n        count is 'zero'\n        return count\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+        assert!(matches!(
+            resolve("object counter\n    count: i64\n    current() -> string:\n        return count\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_make_fills_fields_in_order_with_defaults() {
+        let object = "object point\n    x: i64\n    label: string\n    seen ? no\n";
+        assert!(resolve(&format!("{}p is point.make 1, 'a'\nq is point.make(2, 'b', yes)\n", object)).is_ok());
+        assert!(matches!(
+            resolve(&format!("{}p is point.make 1\n", object)),
+            Err(TypeError::ArityMismatch(3, 1))
+        ));
+        assert!(matches!(
+            resolve(&format!("{}p is point.make 'a', 1\n", object)),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+        // A user `make` takes no parameters of its own
+        assert!(matches!(
+            resolve("object point\n    x: i64\n    make(y: i64):\n        x is y\n"),
+            Err(TypeError::ArityMismatch(0, 1))
+        ));
+    }
+
+    #[test]
+    fn test_field_and_element_assignment_is_checked() {
+        let objects = "object point\n    x: i64\nobject line\n    start: point\n";
+        assert!(resolve(&format!("{}fn f(l: line)\n    l.start is point.make(1)\n    l.start.x is 2\n", objects)).is_ok());
+        assert!(matches!(
+            resolve(&format!("{}fn f(l: line)\n    l.start.x is \"one\"\n", objects)),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+        assert!(resolve("xs is [1, 2]\nxs(0) is 3\nxs[1] is 4\ncfg is {\"host\": \"a\"}\ncfg(\"host\") is \"b\"\n").is_ok());
+        assert!(matches!(
+            resolve("cfg is {\"host\": \"a\"}\ncfg(\"host\") is 1\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_call_on_a_collection_reads_an_element() {
+        assert!(resolve("fn f(xs: (i64), ages: (string: i64)) -> i64\n    return xs(0) + xs[1] + ages(\"ann\")\n").is_ok());
+        assert!(resolve("fn f(word: string) -> string\n    return word(0)\n").is_ok());
+        assert!(matches!(
+            resolve("fn f(xs: (i64)) -> string\n    return xs(0)\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+        assert!(matches!(
+            resolve("fn f(ages: (string: i64)) -> i64\n    return ages(1)\n"),
+            Err(TypeError::TypeMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_unannotated_fields_are_solved_from_their_uses() {
+        let mut lexer = Lexer::new("object point\n    x\n    y\nfn main()\n    p is point.make 1, \"a\"\n".to_string(), "test".to_string());
+        let tokens = lexer.tokenize().unwrap();
+        let program = Parser::new(tokens, "test".to_string()).parse().unwrap();
+        let mut resolver = TypeResolver::new();
+        resolver.resolve_program(&program).unwrap();
+        assert_eq!(resolver.type_table().field_type("point", "x"), Some(&Type::I64));
+        assert_eq!(resolver.type_table().field_type("point", "y"), Some(&Type::String));
+    }
 }
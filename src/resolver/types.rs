@@ -37,6 +37,11 @@ pub enum InferType {
     Bool,
     Int,
     Float,
+    // Sized numerics; `Int` and `Float` are the 64-bit defaults
+    I8,
+    I16,
+    I32,
+    F32,
     String,
     List(Box<InferType>),
     Map(Box<InferType>, Box<InferType>),
@@ -71,6 +76,18 @@ pub enum InferType {
         handlers: HashMap<Type, InferType>,
     },
     
+    // Sum type: tagged union of unit and payload-carrying variants, in declaration order
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<InferType>)>,
+    },
+    
+    // Trait (interface) type: method signatures without `self`, in declaration order
+    Trait {
+        name: String,
+        methods: Vec<(String, InferType)>,
+    },
+    
     // Type variables for inference
     Var(TypeVar),
     
@@ -97,6 +114,10 @@ impl PartialEq for InferType {
             (InferType::Bool, InferType::Bool) => true,
             (InferType::Int, InferType::Int) => true,
             (InferType::Float, InferType::Float) => true,
+            (InferType::I8, InferType::I8) => true,
+            (InferType::I16, InferType::I16) => true,
+            (InferType::I32, InferType::I32) => true,
+            (InferType::F32, InferType::F32) => true,
             (InferType::String, InferType::String) => true,
             (InferType::List(a), InferType::List(b)) => a == b,
             (InferType::Map(k1, v1), InferType::Map(k2, v2)) => k1 == k2 && v1 == v2,
@@ -106,6 +127,8 @@ impl PartialEq for InferType {
             (InferType::Object { name: n1, .. }, InferType::Object { name: n2, .. }) => n1 == n2,
             (InferType::Store { name: n1, .. }, InferType::Store { name: n2, .. }) => n1 == n2,
             (InferType::Actor { name: n1, .. }, InferType::Actor { name: n2, .. }) => n1 == n2,
+            (InferType::Enum { name: n1, .. }, InferType::Enum { name: n2, .. }) => n1 == n2,
+            (InferType::Trait { name: n1, .. }, InferType::Trait { name: n2, .. }) => n1 == n2,
             (InferType::Var(v1), InferType::Var(v2)) => v1 == v2,
             (InferType::Result(o1, e1), InferType::Result(o2, e2)) => o1 == o2 && e1 == e2,
             (InferType::Iterator(t1), InferType::Iterator(t2)) => t1 == t2,
@@ -141,6 +164,10 @@ impl EffectSet {
         Self { actor_send: true, ..Default::default() }
     }
     
+    pub fn is_pure(&self) -> bool {
+        !(self.io || self.store || self.actor_send || self.mutation)
+    }
+    
     pub fn union(&self, other: &Self) -> Self {
         Self {
             io: self.io || other.io,
@@ -151,6 +178,25 @@ impl EffectSet {
     }
 }
 
+impl std::fmt::Display for EffectSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_pure() {
+            return write!(f, "pure");
+        }
+        let names: Vec<&str> = [
+            (self.io, "io"),
+            (self.store, "store"),
+            (self.actor_send, "actor"),
+            (self.mutation, "mutation"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, name)| *name)
+        .collect();
+        write!(f, "{}", names.join(", "))
+    }
+}
+
 /// Type substitution map
 pub type Substitution = HashMap<TypeVar, InferType>;
 
@@ -162,16 +208,160 @@ pub enum Constraint {
     HasMethod(InferType, String, InferType),
     IsCallable(InferType, Vec<InferType>, InferType),
     IsIterable(InferType, InferType),
+    /// The first type must implicitly widen to the second (i8 -> i32, f32 -> f64, ...)
+    Widens(InferType, InferType),
+    /// The type must be an integer or float type
+    Numeric(InferType),
+    /// The type must support `+`: numeric or string
+    Addable(InferType),
+    /// Operands of a binary operator and the common type they promote to
+    Promotes(InferType, InferType, InferType),
 }
 
 impl InferType {
+    /// Bit width of an integer type
+    pub fn int_width(&self) -> Option<u32> {
+        match self {
+            InferType::I8 => Some(8),
+            InferType::I16 => Some(16),
+            InferType::I32 => Some(32),
+            InferType::Int => Some(64),
+            _ => None,
+        }
+    }
+
+    /// Bit width of a floating-point type
+    pub fn float_width(&self) -> Option<u32> {
+        match self {
+            InferType::F32 => Some(32),
+            InferType::Float => Some(64),
+            _ => None,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.int_width().is_some()
+    }
+
+    pub fn is_float(&self) -> bool {
+        self.float_width().is_some()
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+
+    /// Whether a value of this type converts implicitly to `target` without loss of range.
+    /// Integers widen to wider integers and to any float; floats widen to wider floats.
+    pub fn widens_to(&self, target: &InferType) -> bool {
+        match (self.int_width(), self.float_width(), target.int_width(), target.float_width()) {
+            (Some(from), _, Some(to), _) => from <= to,
+            (Some(_), _, _, Some(_)) => true,
+            (_, Some(from), _, Some(to)) => from <= to,
+            _ => self == target,
+        }
+    }
+
+    /// Common type of two numeric operands: any float wins over integers,
+    /// otherwise the wider of the two
+    pub fn promote(&self, other: &InferType) -> InferType {
+        if self.widens_to(other) {
+            other.clone()
+        } else {
+            self.clone()
+        }
+    }
+
+    /// Look up the payload types of an enum variant by name
+    pub fn enum_variant(&self, variant: &str) -> Option<(usize, &[InferType])> {
+        match self {
+            InferType::Enum { variants, .. } => variants
+                .iter()
+                .enumerate()
+                .find(|(_, (name, _))| name == variant)
+                .map(|(tag, (_, payload))| (tag, payload.as_slice())),
+            _ => None,
+        }
+    }
+
+    /// Look up a trait method signature, with `self_type` prepended as the receiver
+    pub fn trait_method(&self, method: &str, self_type: &InferType) -> Option<InferType> {
+        match self {
+            InferType::Trait { methods, .. } => methods
+                .iter()
+                .find(|(name, _)| name == method)
+                .map(|(_, sig)| match sig {
+                    InferType::Function { params, return_type, effects } => {
+                        let mut with_self = vec![self_type.clone()];
+                        with_self.extend(params.iter().cloned());
+                        InferType::Function {
+                            params: with_self,
+                            return_type: return_type.clone(),
+                            effects: effects.clone(),
+                        }
+                    }
+                    other => other.clone(),
+                }),
+            _ => None,
+        }
+    }
+
+    /// Collect the type variables occurring free in this type, in first-seen order
+    pub fn free_vars(&self, out: &mut Vec<TypeVar>) {
+        match self {
+            InferType::Var(v) if !out.contains(v) => out.push(*v),
+            InferType::List(inner) | InferType::Iterator(inner) => inner.free_vars(out),
+            InferType::Map(a, b) | InferType::Result(a, b) => {
+                a.free_vars(out);
+                b.free_vars(out);
+            }
+            InferType::Function { params, return_type, .. } => {
+                for param in params {
+                    param.free_vars(out);
+                }
+                return_type.free_vars(out);
+            }
+            InferType::Object { fields, methods, .. } => {
+                for ty in fields.values().chain(methods.values()) {
+                    ty.free_vars(out);
+                }
+            }
+            InferType::Enum { variants, .. } => {
+                for (_, payload) in variants {
+                    for ty in payload {
+                        ty.free_vars(out);
+                    }
+                }
+            }
+            InferType::Union(types) => {
+                for ty in types {
+                    ty.free_vars(out);
+                }
+            }
+            InferType::Forall(bound, body) => {
+                let mut inner = Vec::new();
+                body.free_vars(&mut inner);
+                for v in inner {
+                    if !bound.contains(&v) && !out.contains(&v) {
+                        out.push(v);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Convert InferType to a simplified AST Type for error messages and final AST representation
     pub fn to_ast_type(&self) -> Type {
         match self {
             InferType::Unit => Type::Unit,
             InferType::Bool => Type::Bool,
-            InferType::Int => Type::I64, // Default to i64 for now
-            InferType::Float => Type::F64, // Default to f64
+            InferType::Int => Type::I64,
+            InferType::Float => Type::F64,
+            InferType::I8 => Type::I8,
+            InferType::I16 => Type::I16,
+            InferType::I32 => Type::I32,
+            InferType::F32 => Type::F32,
             InferType::String => Type::String,
             InferType::List(t) => Type::List(Box::new(t.to_ast_type())),
             InferType::Map(k, v) => Type::Map(Box::new(k.to_ast_type()), Box::new(v.to_ast_type())),
@@ -191,6 +381,13 @@ impl InferType {
                 name: name.clone(),
                 message_types: handlers.keys().cloned().collect(),
             },
+            InferType::Enum { name, variants } => Type::Enum {
+                name: name.clone(),
+                variants: variants.iter()
+                    .map(|(v, payload)| (v.clone(), payload.iter().map(|p| p.to_ast_type()).collect()))
+                    .collect(),
+            },
+            InferType::Trait { name, .. } => Type::Trait { name: name.clone() },
             InferType::Var(v) => Type::TypeVar(v.0 as u32),
             InferType::Result(ok, err) => Type::Result(Box::new(ok.to_ast_type()), Box::new(err.to_ast_type())),
             _ => Type::Unknown,
@@ -1,3 +1,4 @@
+use crate::ast::SourceSpan;
 use crate::resolver::{
     error::TypeError,
     types::{Constraint, InferType, Substitution},
@@ -5,45 +6,226 @@ use crate::resolver::{
 };
 use std::collections::{HashMap, VecDeque};
 
+/// Constraints waiting to be solved, each with the source it was generated
+/// for. Constraints pushed while one is being solved inherit its source.
+#[derive(Default)]
+struct WorkQueue {
+    items: VecDeque<(Constraint, Option<SourceSpan>)>,
+    span: Option<SourceSpan>,
+}
+
+impl WorkQueue {
+    fn push_back(&mut self, constraint: Constraint) {
+        self.items.push_back((constraint, self.span.clone()));
+    }
+    
+    fn push_front(&mut self, constraint: Constraint) {
+        self.items.push_front((constraint, self.span.clone()));
+    }
+    
+    fn push(&mut self, constraint: Constraint) {
+        self.push_back(constraint);
+    }
+}
+
 impl TypeResolver {
     /// Solve all constraints using unification algorithm
     pub(super) fn solve_constraints(&mut self) -> Result<Substitution, TypeError> {
         let mut subst = Substitution::new();
-        let mut work_queue: VecDeque<Constraint> = self.constraints.drain(..).collect();
+        let mut work_queue = WorkQueue { items: self.constraints.drain(..).collect(), span: None };
+        let mut deferred = WorkQueue::default();
         
-        while let Some(constraint) = work_queue.pop_front() {
+        loop {
+            let Some((constraint, span)) = work_queue.items.pop_front() else {
+                if deferred.items.is_empty() || !self.resume_deferred(&mut deferred, &subst, &mut work_queue) {
+                    break;
+                }
+                continue;
+            };
+            // A failure, and whatever this constraint gives rise to, points at its source
+            self.current_span = span.clone();
+            work_queue.span = span.clone();
+            deferred.span = span;
             match constraint {
                 Constraint::Equal(t1, t2) => {
                     let unified_subst = self.unify(&t1, &t2)?;
                     subst = self.compose_substitutions(&subst, &unified_subst);
                     
                     // Apply new substitution to remaining constraints
-                    for constraint in &mut work_queue {
+                    for (constraint, _) in &mut work_queue.items {
                         *constraint = self.apply_subst_to_constraint(constraint, &unified_subst);
                     }
                 }
                 
                 Constraint::HasField(obj_type, field_name, field_type) => {
-                    self.solve_has_field_constraint(obj_type, field_name, field_type, &mut subst, &mut work_queue)?;
+                    self.solve_has_field_constraint(obj_type, field_name, field_type, &mut work_queue)?;
                 }
                 
                 Constraint::HasMethod(obj_type, method_name, method_type) => {
-                    self.solve_has_method_constraint(obj_type, method_name, method_type, &mut subst, &mut work_queue)?;
+                    self.solve_has_method_constraint(obj_type, method_name, method_type, &mut work_queue)?;
                 }
                 
                 Constraint::IsCallable(func_type, arg_types, return_type) => {
-                    self.solve_callable_constraint(func_type, arg_types, return_type, &mut subst, &mut work_queue)?;
+                    self.solve_callable_constraint(func_type, arg_types, return_type, &mut work_queue)?;
                 }
                 
                 Constraint::IsIterable(container_type, element_type) => {
-                    self.solve_iterable_constraint(container_type, element_type, &mut subst, &mut work_queue)?;
+                    self.solve_iterable_constraint(container_type, element_type, &mut work_queue)?;
+                }
+                
+                Constraint::Widens(actual, expected) => {
+                    self.solve_widens_constraint(actual, expected, &mut deferred, &mut work_queue)?;
+                }
+                
+                Constraint::Promotes(left, right, result) => {
+                    self.solve_promotes_constraint(left, right, result, &mut deferred, &mut work_queue);
+                }
+                
+                class @ (Constraint::Numeric(_) | Constraint::Addable(_)) => {
+                    self.solve_class_constraint(class, &mut deferred)?;
                 }
             }
         }
         
+        self.default_numeric_literals(&mut subst)?;
+        
+        // Only class constraints on unsolved variables are left; keep those for generalization
+        let mut unsolved = WorkQueue::default();
+        for (class, span) in deferred.items {
+            self.current_span = span;
+            let class = self.apply_subst_to_constraint(&class, &subst);
+            self.solve_class_constraint(class, &mut unsolved)?;
+        }
+        self.pending_classes = unsolved.items.into_iter().map(|(class, _)| class).collect();
+        self.current_span = None;
         Ok(subst)
     }
     
+    /// Solve Promotes constraint. A literal takes the type of the other operand,
+    /// except that a float literal next to an integer promotes both to float.
+    fn solve_promotes_constraint(
+        &mut self,
+        left: InferType,
+        right: InferType,
+        result: InferType,
+        deferred: &mut WorkQueue,
+        work_queue: &mut WorkQueue,
+    ) {
+        match (&left, &right) {
+            (InferType::Var(_), InferType::Var(_)) => {
+                deferred.push(Constraint::Promotes(left, right, result));
+            }
+            (InferType::Var(v), other) | (other, InferType::Var(v)) => {
+                let is_float_literal = self.numeric_literals.iter().any(|(lit, default, _)| lit == v && default.is_float());
+                let common = if is_float_literal && other.is_integer() { InferType::Float } else { other.clone() };
+                work_queue.push_back(Constraint::Equal(InferType::Var(*v), common.clone()));
+                work_queue.push_back(Constraint::Equal(result, common));
+            }
+            _ if left.is_numeric() && right.is_numeric() => {
+                work_queue.push_back(Constraint::Equal(result, left.promote(&right)));
+            }
+            _ => {
+                work_queue.push_back(Constraint::Equal(left.clone(), right));
+                work_queue.push_back(Constraint::Equal(result, left));
+            }
+        }
+    }
+    
+    /// Solve Numeric or Addable constraint; waits while the type is a variable
+    fn solve_class_constraint(&mut self, class: Constraint, deferred: &mut WorkQueue) -> Result<(), TypeError> {
+        let satisfied = match &class {
+            Constraint::Numeric(InferType::Var(_)) | Constraint::Addable(InferType::Var(_)) => {
+                deferred.push(class);
+                return Ok(());
+            }
+            Constraint::Numeric(ty) => ty.is_numeric(),
+            Constraint::Addable(ty) => ty.is_numeric() || *ty == InferType::String,
+            _ => true,
+        };
+        if satisfied {
+            Ok(())
+        } else {
+            Err(TypeError::ConstraintUnsatisfied(class))
+        }
+    }
+    
+    /// Solve Widens constraint; waits while either side is still a variable
+    fn solve_widens_constraint(
+        &mut self,
+        actual: InferType,
+        expected: InferType,
+        deferred: &mut WorkQueue,
+        work_queue: &mut WorkQueue,
+    ) -> Result<(), TypeError> {
+        match (&actual, &expected) {
+            (InferType::Var(_), _) | (_, InferType::Var(_)) => {
+                deferred.push(Constraint::Widens(actual, expected));
+            }
+            _ if actual.is_numeric() && expected.is_numeric() => {
+                if !actual.widens_to(&expected) {
+                    return Err(TypeError::ImplicitNarrowing(actual, expected));
+                }
+            }
+            _ => work_queue.push_back(Constraint::Equal(expected, actual)),
+        }
+        Ok(())
+    }
+    
+    /// Requeue deferred constraints once the queue drains. If none can make
+    /// progress, the first widening or promotion is settled by requiring its
+    /// types to be equal. Returns false when only class constraints on
+    /// unsolved variables remain.
+    fn resume_deferred(&self, deferred: &mut WorkQueue, subst: &Substitution, work_queue: &mut WorkQueue) -> bool {
+        let is_var = |ty: &InferType| matches!(ty, InferType::Var(_));
+        let mut pending: Vec<(Constraint, Option<SourceSpan>)> = deferred.items.drain(..)
+            .map(|(c, span)| (self.apply_subst_to_constraint(&c, subst), span))
+            .collect();
+        let ready = pending.iter().any(|(c, _)| match c {
+            Constraint::Widens(a, b) => !is_var(a) && !is_var(b),
+            Constraint::Promotes(a, b, _) => !is_var(a) || !is_var(b),
+            Constraint::Numeric(ty) | Constraint::Addable(ty) => !is_var(ty),
+            _ => true,
+        });
+        if !ready {
+            let Some(index) = pending.iter().position(|(c, _)| matches!(c, Constraint::Widens(..) | Constraint::Promotes(..))) else {
+                deferred.items = pending.into();
+                return false;
+            };
+            let (constraint, span) = pending.remove(index);
+            work_queue.span = span;
+            match constraint {
+                Constraint::Widens(actual, expected) => work_queue.push_back(Constraint::Equal(expected, actual)),
+                Constraint::Promotes(left, right, result) => {
+                    work_queue.push_back(Constraint::Equal(left.clone(), right));
+                    work_queue.push_back(Constraint::Equal(result, left));
+                }
+                other => work_queue.push_back(other),
+            }
+        }
+        work_queue.items.extend(pending);
+        true
+    }
+    
+    /// Give numeric literals nothing else constrained their default type,
+    /// and check the ones that were constrained. Float literals go first so
+    /// that `1 + 2.5` settles on float rather than rejecting the `2.5`.
+    fn default_numeric_literals(&mut self, subst: &mut Substitution) -> Result<(), TypeError> {
+        let mut literals = self.numeric_literals.clone();
+        literals.sort_by_key(|(_, default, _)| !default.is_float());
+        for (var, default, span) in literals {
+            self.current_span = span;
+            match self.apply_substitution(&InferType::Var(var), subst) {
+                InferType::Var(v) => {
+                    let defaulted = self.unify(&InferType::Var(v), &default)?;
+                    *subst = self.compose_substitutions(subst, &defaulted);
+                }
+                ty if ty.is_float() || (ty.is_integer() && !default.is_float()) => {}
+                ty => return Err(TypeError::TypeMismatch(ty, default)),
+            }
+        }
+        Ok(())
+    }
+    
     /// Unification algorithm - the core of type inference
     fn unify(&mut self, t1: &InferType, t2: &InferType) -> Result<Substitution, TypeError> {
         match (t1, t2) {
@@ -52,6 +234,10 @@ impl TypeResolver {
             (InferType::Bool, InferType::Bool) |
             (InferType::Int, InferType::Int) |
             (InferType::Float, InferType::Float) |
+            (InferType::I8, InferType::I8) |
+            (InferType::I16, InferType::I16) |
+            (InferType::I32, InferType::I32) |
+            (InferType::F32, InferType::F32) |
             (InferType::String, InferType::String) => Ok(Substitution::new()),
             
             // Variable unification
@@ -122,6 +308,50 @@ impl TypeResolver {
                 Ok(subst)
             }
             
+            (InferType::Enum { name: n1, variants: v1 },
+             InferType::Enum { name: n2, variants: v2 }) => {
+                if n1 != n2 {
+                    return Err(TypeError::TypeMismatch(t1.clone(), t2.clone()));
+                }
+                
+                // Same enum: payload types must agree variant by variant
+                let mut subst = Substitution::new();
+                for ((_, payload1), (_, payload2)) in v1.iter().zip(v2.iter()) {
+                    for (p1, p2) in payload1.iter().zip(payload2.iter()) {
+                        let payload_subst = self.unify(
+                            &self.apply_substitution(p1, &subst),
+                            &self.apply_substitution(p2, &subst),
+                        )?;
+                        subst = self.compose_substitutions(&subst, &payload_subst);
+                    }
+                }
+                
+                Ok(subst)
+            }
+            
+            (InferType::Trait { name: n1, .. }, InferType::Trait { name: n2, .. }) => {
+                if n1 != n2 {
+                    return Err(TypeError::TypeMismatch(t1.clone(), t2.clone()));
+                }
+                Ok(Substitution::new())
+            }
+            
+            // An object flows into a trait-typed slot only if it declared
+            // conformance. Constraints put the expected type first, and a trait
+            // value never fits where a concrete object is expected.
+            (InferType::Trait { name: trait_name, .. }, InferType::Object { name: object_name, .. }) => {
+                let implements = self.trait_impls.get(object_name)
+                    .is_some_and(|traits| traits.contains(trait_name));
+                if !implements {
+                    return Err(TypeError::TraitNotImplemented(object_name.clone(), trait_name.clone()));
+                }
+                Ok(Substitution::new())
+            }
+            
+            (InferType::Object { .. }, InferType::Trait { .. }) => {
+                Err(TypeError::TypeMismatch(t1.clone(), t2.clone()))
+            }
+            
             (InferType::Result(o1, e1), InferType::Result(o2, e2)) => {
                 let ok_subst = self.unify(o1, o2)?;
                 let err_subst = self.unify(e1, e2)?;
@@ -177,6 +407,9 @@ impl TypeResolver {
             InferType::Forall(vars, ty) => !vars.contains(&var) && self.occurs_check(var, ty),
             InferType::Union(types) => types.iter().any(|t| self.occurs_check(var, t)),
             InferType::Result(ok, err) => self.occurs_check(var, ok) || self.occurs_check(var, err),
+            InferType::Enum { variants, .. } => {
+                variants.iter().any(|(_, payload)| payload.iter().any(|p| self.occurs_check(var, p)))
+            }
             InferType::Iterator(inner) => self.occurs_check(var, inner),
             _ => false,
         }
@@ -243,12 +476,30 @@ impl TypeResolver {
                     is_store: *is_store,
                 }
             }
+            InferType::Enum { name, variants } => InferType::Enum {
+                name: name.clone(),
+                variants: variants.iter()
+                    .map(|(v, payload)| (v.clone(), payload.iter().map(|p| self.apply_substitution(p, subst)).collect()))
+                    .collect(),
+            },
+            InferType::Result(ok, err) => InferType::Result(
+                Box::new(self.apply_substitution(ok, subst)),
+                Box::new(self.apply_substitution(err, subst)),
+            ),
+            InferType::Forall(vars, body) => {
+                // Bound variables are not touched by outer substitutions
+                let inner: Substitution = subst.iter()
+                    .filter(|(v, _)| !vars.contains(v))
+                    .map(|(v, t)| (*v, t.clone()))
+                    .collect();
+                InferType::Forall(vars.clone(), Box::new(self.apply_substitution(body, &inner)))
+            }
             _ => ty.clone(),
         }
     }
     
     /// Apply substitution to constraint
-    fn apply_subst_to_constraint(&self, constraint: &Constraint, subst: &Substitution) -> Constraint {
+    pub(super) fn apply_subst_to_constraint(&self, constraint: &Constraint, subst: &Substitution) -> Constraint {
         match constraint {
             Constraint::Equal(t1, t2) => {
                 Constraint::Equal(
@@ -283,6 +534,21 @@ impl TypeResolver {
                     self.apply_substitution(element, subst),
                 )
             }
+            Constraint::Widens(actual, expected) => {
+                Constraint::Widens(
+                    self.apply_substitution(actual, subst),
+                    self.apply_substitution(expected, subst),
+                )
+            }
+            Constraint::Numeric(ty) => Constraint::Numeric(self.apply_substitution(ty, subst)),
+            Constraint::Addable(ty) => Constraint::Addable(self.apply_substitution(ty, subst)),
+            Constraint::Promotes(left, right, result) => {
+                Constraint::Promotes(
+                    self.apply_substitution(left, subst),
+                    self.apply_substitution(right, subst),
+                    self.apply_substitution(result, subst),
+                )
+            }
         }
     }
     
@@ -292,13 +558,19 @@ impl TypeResolver {
         obj_type: InferType,
         field_name: String,
         field_type: InferType,
-        subst: &mut Substitution,
-        work_queue: &mut VecDeque<Constraint>,
+        work_queue: &mut WorkQueue,
     ) -> Result<(), TypeError> {
         match obj_type {
-            InferType::Object { fields, .. } => {
+            InferType::Object { ref name, ref fields, ref methods, .. } => {
                 if let Some(actual_field_type) = fields.get(&field_name) {
-                    work_queue.push_back(Constraint::Equal(field_type, actual_field_type.clone()));
+                    // Solved first, so a chained access like `a.b.c` sees `a.b`'s type
+                    work_queue.push_front(Constraint::Equal(field_type, actual_field_type.clone()));
+                } else if let Some(InferType::Function { params, return_type, .. }) = self.object_method(name, methods, &field_name) {
+                    // `task.complete` calls a method that takes nothing but `self`
+                    if params.len() != 1 {
+                        return Err(TypeError::ArityMismatch(params.len().saturating_sub(1), 0));
+                    }
+                    work_queue.push_front(Constraint::Equal(field_type, *return_type));
                 } else {
                     return Err(TypeError::FieldNotFound(field_name));
                 }
@@ -316,7 +588,7 @@ impl TypeResolver {
                     is_store: false,
                 };
                 
-                subst.insert(v, obj_type);
+                work_queue.push_front(Constraint::Equal(InferType::Var(v), obj_type));
             }
             _ => return Err(TypeError::NotAnObject(obj_type)),
         }
@@ -324,26 +596,45 @@ impl TypeResolver {
         Ok(())
     }
     
+    /// Type of an object's method, receiver included
+    fn object_method(&self, name: &str, methods: &HashMap<String, InferType>, method_name: &str) -> Option<InferType> {
+        // `self` inside a method is typed before its object's methods are known
+        let defined = self.object_definitions.get(name).map(|definition| match definition {
+            InferType::Forall(_, body) => body.as_ref(),
+            other => other,
+        });
+        methods.get(method_name).or_else(|| match defined {
+            Some(InferType::Object { methods, .. }) => methods.get(method_name),
+            _ => None,
+        }).cloned()
+    }
+    
     /// Solve HasMethod constraint
     fn solve_has_method_constraint(
         &mut self,
         obj_type: InferType,
         method_name: String,
         method_type: InferType,
-        subst: &mut Substitution,
-        work_queue: &mut VecDeque<Constraint>,
+        work_queue: &mut WorkQueue,
     ) -> Result<(), TypeError> {
         match obj_type {
-            InferType::Object { methods, .. } => {
+            InferType::Object { name, methods, .. } => {
+                match self.object_method(&name, &methods, &method_name) {
+                    Some(actual) => work_queue.push_back(Constraint::Equal(method_type, actual)),
+                    None => return Err(TypeError::MethodNotFound(method_name)),
+                }
+            }
+            InferType::Store { methods, .. } => {
                 if let Some(actual_method_type) = methods.get(&method_name) {
                     work_queue.push_back(Constraint::Equal(method_type, actual_method_type.clone()));
                 } else {
                     return Err(TypeError::MethodNotFound(method_name));
                 }
             }
-            InferType::Store { methods, .. } => {
-                if let Some(actual_method_type) = methods.get(&method_name) {
-                    work_queue.push_back(Constraint::Equal(method_type, actual_method_type.clone()));
+            InferType::Trait { .. } => {
+                // Receiver is the trait value itself; dispatch happens at runtime
+                if let Some(actual_method_type) = obj_type.trait_method(&method_name, &obj_type) {
+                    work_queue.push_back(Constraint::Equal(method_type, actual_method_type));
                 } else {
                     return Err(TypeError::MethodNotFound(method_name));
                 }
@@ -361,7 +652,7 @@ impl TypeResolver {
                     is_store: false,
                 };
                 
-                subst.insert(v, obj_type);
+                work_queue.push_front(Constraint::Equal(InferType::Var(v), obj_type));
             }
             _ => return Err(TypeError::NotAnObject(obj_type)),
         }
@@ -375,8 +666,7 @@ impl TypeResolver {
         func_type: InferType,
         arg_types: Vec<InferType>,
         return_type: InferType,
-        subst: &mut Substitution,
-        work_queue: &mut VecDeque<Constraint>,
+        work_queue: &mut WorkQueue,
     ) -> Result<(), TypeError> {
         match func_type {
             InferType::Function { params, return_type: func_return, .. } => {
@@ -384,9 +674,9 @@ impl TypeResolver {
                     return Err(TypeError::ArityMismatch(params.len(), arg_types.len()));
                 }
                 
-                // Unify parameters
+                // Arguments may widen to the parameter types
                 for (param, arg) in params.iter().zip(arg_types.iter()) {
-                    work_queue.push_back(Constraint::Equal(param.clone(), arg.clone()));
+                    work_queue.push_back(Constraint::Widens(arg.clone(), param.clone()));
                 }
                 
                 // Unify return type
@@ -400,7 +690,7 @@ impl TypeResolver {
                     effects: crate::resolver::types::EffectSet::pure(),
                 };
                 
-                subst.insert(v, func_type);
+                work_queue.push_front(Constraint::Equal(InferType::Var(v), func_type));
             }
             _ => return Err(TypeError::NotCallable(func_type)),
         }
@@ -413,8 +703,7 @@ impl TypeResolver {
         &mut self,
         container_type: InferType,
         element_type: InferType,
-        subst: &mut Substitution,
-        work_queue: &mut VecDeque<Constraint>,
+        work_queue: &mut WorkQueue,
     ) -> Result<(), TypeError> {
         match container_type {
             InferType::List(inner) => {
@@ -426,7 +715,7 @@ impl TypeResolver {
             InferType::Var(v) => {
                 // Assume it's a list
                 let list_type = InferType::List(Box::new(element_type));
-                subst.insert(v, list_type);
+                work_queue.push_front(Constraint::Equal(InferType::Var(v), list_type));
             }
             _ => return Err(TypeError::NotIterable(container_type)),
         }
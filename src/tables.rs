@@ -0,0 +1,169 @@
+//! Side tables keyed by `NodeId`.
+//!
+//! Analysis results are kept here instead of on the tree, so the AST stays as
+//! the parser built it and tooling can ask about any node by its ID.
+
+use crate::ast::{NodeId, SourceSpan, Type};
+use std::collections::{HashMap, HashSet};
+
+/// Resolved type of every expression node, object field and top-level function
+#[derive(Debug, Clone, Default)]
+pub struct TypeTable {
+    types: HashMap<NodeId, Type>,
+    /// Keyed by object name, then field name; fields are not nodes
+    fields: HashMap<String, HashMap<String, Type>>,
+    /// Signatures of top-level functions, with `Type::TypeVar` where they are generic
+    functions: HashMap<String, Type>,
+}
+
+impl TypeTable {
+    pub fn new() -> Self {
+        Self { types: HashMap::new(), fields: HashMap::new(), functions: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, id: NodeId, type_: Type) {
+        self.types.insert(id, type_);
+    }
+
+    pub fn insert_field(&mut self, object: &str, field: &str, type_: Type) {
+        self.fields.entry(object.to_string()).or_default().insert(field.to_string(), type_);
+    }
+
+    /// Type of an object's field, including fields written without an annotation
+    pub fn field_type(&self, object: &str, field: &str) -> Option<&Type> {
+        self.fields.get(object)?.get(field)
+    }
+
+    pub fn insert_function(&mut self, name: &str, signature: Type) {
+        self.functions.insert(name.to_string(), signature);
+    }
+
+    /// Inferred signature of a top-level function, annotated or not
+    pub fn function_type(&self, name: &str) -> Option<&Type> {
+        self.functions.get(name)
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Type> {
+        self.types.get(&id)
+    }
+
+    /// Type of a node, `Unknown` if inference left it open
+    pub fn type_of(&self, id: NodeId) -> Type {
+        self.get(id).cloned().unwrap_or(Type::Unknown)
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefinitionKind {
+    Variable,
+    Parameter,
+    Field,
+    Function,
+    Type,
+    Module,
+}
+
+/// Where a name was introduced
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Definition {
+    pub name: String,
+    pub kind: DefinitionKind,
+    pub span: SourceSpan,
+}
+
+/// Def-use map: the definition each identifier node refers to
+#[derive(Debug, Clone, Default)]
+pub struct ResolutionTable {
+    definitions: HashMap<NodeId, Definition>,
+    /// The nodes referring to each definition, the inverse of `definitions`
+    uses: HashMap<Definition, Vec<NodeId>>,
+    /// Every definition, in the order name resolution met them
+    introduced: Vec<Definition>,
+    /// Identifier nodes that are assigned to rather than read
+    writes: HashSet<NodeId>,
+    /// Definitions paired with the outer definition they hide
+    shadowed: Vec<(Definition, Definition)>,
+}
+
+impl ResolutionTable {
+    pub fn new() -> Self {
+        Self {
+            definitions: HashMap::new(),
+            uses: HashMap::new(),
+            introduced: Vec::new(),
+            writes: HashSet::new(),
+            shadowed: Vec::new(),
+        }
+    }
+
+    pub fn introduce(&mut self, definition: Definition) {
+        self.introduced.push(definition);
+    }
+
+    pub fn definitions(&self) -> &[Definition] {
+        &self.introduced
+    }
+
+    pub fn mark_write(&mut self, id: NodeId) {
+        self.writes.insert(id);
+    }
+
+    pub fn is_write(&self, id: NodeId) -> bool {
+        self.writes.contains(&id)
+    }
+
+    pub fn shadow(&mut self, inner: Definition, outer: Definition) {
+        self.shadowed.push((inner, outer));
+    }
+
+    pub fn shadowed(&self) -> &[(Definition, Definition)] {
+        &self.shadowed
+    }
+
+    /// Link the identifier node `id` to the definition it refers to
+    pub fn record_use(&mut self, id: NodeId, definition: Definition) {
+        if let Some(previous) = self.definitions.insert(id, definition.clone()) {
+            if let Some(ids) = self.uses.get_mut(&previous) {
+                ids.retain(|use_id| *use_id != id);
+            }
+        }
+        self.uses.entry(definition).or_default().push(id);
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Definition> {
+        self.definitions.get(&id)
+    }
+
+    /// Every node that refers to `definition`, in source order
+    pub fn uses_of(&self, definition: &Definition) -> Vec<NodeId> {
+        let mut uses = self.uses.get(definition).cloned().unwrap_or_default();
+        uses.sort_by_key(NodeId::as_u32);
+        uses
+    }
+
+    /// Nodes that read `definition`, leaving out assignments to it
+    pub fn reads_of(&self, definition: &Definition) -> Vec<NodeId> {
+        self.uses_of(definition).into_iter().filter(|id| !self.is_write(*id)).collect()
+    }
+
+    /// Assignments to `definition`, the defining one included
+    pub fn writes_of(&self, definition: &Definition) -> Vec<NodeId> {
+        self.uses_of(definition).into_iter().filter(|id| self.is_write(*id)).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}